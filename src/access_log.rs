@@ -0,0 +1,143 @@
+//! Ring buffer of the most recent register accesses, enabled with the `access-log` feature.
+//!
+//! After an unexpected error, dumping the log shows what the driver last read from and wrote to
+//! the chip, which helps to reconstruct intermittent failures in the field.
+
+use super::{Register, PCF85063};
+
+/// Number of register accesses kept in the log.
+pub const ACCESS_LOG_LEN: usize = 16;
+
+/// Direction of a register access
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    #[default]
+    Read,
+    Write,
+}
+
+/// A single register access.
+///
+/// Burst transfers are logged as one access per register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Access {
+    pub register: Register,
+    pub direction: Direction,
+    /// Value written, or read; meaningless for failed reads.
+    pub value: u8,
+    /// The transaction completed without bus error.
+    pub ok: bool,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct AccessLog {
+    entries: [Access; ACCESS_LOG_LEN],
+    /// Index of the slot the next access goes into.
+    next: usize,
+    len: usize,
+}
+
+impl AccessLog {
+    pub(crate) const fn new() -> Self {
+        AccessLog {
+            entries: [Access {
+                register: Register::CONTROL_1,
+                direction: Direction::Read,
+                value: 0,
+                ok: false,
+            }; ACCESS_LOG_LEN],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Log a burst transfer of `values`, starting at `register`.
+    pub(crate) fn record(
+        &mut self,
+        register: Register,
+        direction: Direction,
+        values: &[u8],
+        ok: bool,
+    ) {
+        for (i, &value) in values.iter().enumerate() {
+            let Some(register) = register.offset(i as u8) else {
+                break;
+            };
+            self.entries[self.next] = Access {
+                register,
+                direction,
+                value,
+                ok,
+            };
+            self.next = (self.next + 1) % ACCESS_LOG_LEN;
+            self.len = (self.len + 1).min(ACCESS_LOG_LEN);
+        }
+    }
+
+    /// Iterate over the logged accesses, oldest first.
+    fn iter(&self) -> impl Iterator<Item = &Access> {
+        let start = (self.next + ACCESS_LOG_LEN - self.len) % ACCESS_LOG_LEN;
+        (0..self.len).map(move |i| &self.entries[(start + i) % ACCESS_LOG_LEN])
+    }
+}
+
+impl<I2C> PCF85063<I2C> {
+    /// The most recent register accesses, oldest first.
+    pub fn access_log(&self) -> impl Iterator<Item = &Access> {
+        self.access_log.iter()
+    }
+
+    /// Forget all logged register accesses.
+    pub fn clear_access_log(&mut self) {
+        self.access_log = AccessLog::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_most_recent_accesses_in_order() {
+        let mut log = AccessLog::new();
+        log.record(
+            Register::CONTROL_1,
+            Direction::Write,
+            &[0; ACCESS_LOG_LEN],
+            true,
+        );
+        log.record(
+            Register::SECONDS,
+            Direction::Read,
+            &[0x10, 0x20, 0x30],
+            false,
+        );
+
+        assert_eq!(ACCESS_LOG_LEN, log.iter().count());
+        let last: [Register; 3] =
+            core::array::from_fn(|i| log.iter().nth(ACCESS_LOG_LEN - 3 + i).unwrap().register);
+        assert_eq!(
+            [Register::SECONDS, Register::MINUTES, Register::HOURS],
+            last
+        );
+        assert_eq!(
+            Some(&Access {
+                register: Register::HOURS,
+                direction: Direction::Read,
+                value: 0x30,
+                ok: false,
+            }),
+            log.iter().last()
+        );
+    }
+
+    #[test]
+    fn stops_logging_past_last_register() {
+        let mut log = AccessLog::new();
+        log.record(Register::TIMER_VALUE, Direction::Write, &[1, 2, 3], true);
+        assert_eq!(2, log.iter().count());
+    }
+}
@@ -1,91 +1,220 @@
-use super::{decode_bcd, encode_bcd, BitFlags, Control, Error, Register, DEVICE_ADDRESS, PCF85063};
+use super::calendar::next_date_with_day;
+use super::units::{check, DayOfMonth, Hours, Minutes, Seconds};
+#[allow(deprecated)]
+use super::Control;
+use super::{bcd, codec, BitFlags, DataError, Error, Register, UsageError, PCF85063};
 use embedded_hal_async::i2c::I2c;
-use time::Time;
+use time::{Date, PrimitiveDateTime, Time};
 
 impl<I2C, E> PCF85063<I2C>
 where
     I2C: I2c<Error = E>,
 {
-    /// Set the alarm seconds, minutes and hours, keeping the AE bit unchanged.
+    /// Set the alarm seconds, minutes and hours, keeping the AE bits unchanged.
+    ///
+    /// The three registers are written in a single transaction, so cancelling this never leaves
+    /// a partially updated alarm time.
     pub async fn set_alarm_time(&mut self, time: Time) -> Result<(), Error<E>> {
-        self.set_alarm_seconds(time.second()).await?;
-        self.set_alarm_minutes(time.minute()).await?;
-        self.set_alarm_hours(time.hour()).await?;
-        Ok(())
+        let mut data = [0; 3];
+        self.read_bytes(Register::SECOND_ALARM, &mut data).await?;
+
+        let mut payload = [Register::SECOND_ALARM as u8; 4];
+        payload[1..].copy_from_slice(&codec::encode_alarm_time(time, data));
+        self.write_bytes(&payload).await
+    }
+
+    /// Set the alarm to fire at `at`: seconds, minutes, hours and day of the month are
+    /// enabled, the weekday is disabled.
+    ///
+    /// The alarm matches on the day of the month, so it fires at `at` as long as that is
+    /// within a month. All five registers are written in a single transaction.
+    pub async fn set_alarm_at(&mut self, at: PrimitiveDateTime) -> Result<(), Error<E>> {
+        let mut payload = [Register::SECOND_ALARM as u8; 6];
+        payload[1..].copy_from_slice(&codec::encode_alarm_at(&at));
+        self.write_bytes(&payload).await
     }
 
     /// Set the alarm seconds [0-59], keeping the AE bit unchanged.
-    pub async fn set_alarm_seconds(&mut self, seconds: u8) -> Result<(), Error<E>> {
-        if seconds > 59 {
-            return Err(Error::InvalidInputData);
-        }
+    pub async fn set_alarm_seconds(
+        &mut self,
+        seconds: impl TryInto<Seconds>,
+    ) -> Result<(), Error<E>> {
+        let seconds = check::<Seconds, E>(seconds)?.get();
         let data: u8 = self.read_register(Register::SECOND_ALARM).await?; // read current value
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
-        let setting: u8 = encode_bcd(seconds);
+        let setting: u8 = bcd::encode(seconds);
         let data: u8 = data | setting;
         self.write_register(Register::SECOND_ALARM, data).await
     }
 
     /// Set the alarm minutes [0-59], keeping the AE bit unchanged.
-    pub async fn set_alarm_minutes(&mut self, minutes: u8) -> Result<(), Error<E>> {
-        if minutes > 59 {
-            return Err(Error::InvalidInputData);
-        }
+    pub async fn set_alarm_minutes(
+        &mut self,
+        minutes: impl TryInto<Minutes>,
+    ) -> Result<(), Error<E>> {
+        let minutes = check::<Minutes, E>(minutes)?.get();
         let data: u8 = self.read_register(Register::MINUTE_ALARM).await?; // read current value
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
-        let setting: u8 = encode_bcd(minutes);
+        let setting: u8 = bcd::encode(minutes);
         let data: u8 = data | setting;
         self.write_register(Register::MINUTE_ALARM, data).await
     }
 
     /// Set the alarm hours [0-23], keeping the AE bit unchanged.
-    pub async fn set_alarm_hours(&mut self, hours: u8) -> Result<(), Error<E>> {
-        if hours > 23 {
-            return Err(Error::InvalidInputData);
-        }
+    pub async fn set_alarm_hours(&mut self, hours: impl TryInto<Hours>) -> Result<(), Error<E>> {
+        let hours = check::<Hours, E>(hours)?.get();
         let data: u8 = self.read_register(Register::HOUR_ALARM).await?; // read current value
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
-        let setting: u8 = encode_bcd(hours);
+        let setting: u8 = bcd::encode(hours);
         let data: u8 = data | setting;
         self.write_register(Register::HOUR_ALARM, data).await
     }
 
     /// Set the alarm day [1-31], keeping the AE bit unchanged.
-    pub async fn set_alarm_day(&mut self, day: u8) -> Result<(), Error<E>> {
-        if !(1..=31).contains(&day) {
-            return Err(Error::InvalidInputData);
-        }
+    pub async fn set_alarm_day(&mut self, day: impl TryInto<DayOfMonth>) -> Result<(), Error<E>> {
+        let day = check::<DayOfMonth, E>(day)?.get();
         let data: u8 = self.read_register(Register::DAY_ALARM).await?; // read current value
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
-        let setting: u8 = encode_bcd(day);
+        let setting: u8 = bcd::encode(day);
         let data: u8 = data | setting;
         self.write_register(Register::DAY_ALARM, data).await
     }
 
+    /// Set the alarm day [1-31] like `set_alarm_day`, and return the next date it matches on,
+    /// from today.
+    ///
+    /// The chip never matches a day that the current month lacks, such as the 31st in April, so
+    /// that date may lie in a later month than expected.
+    pub async fn set_alarm_day_checked(
+        &mut self,
+        day: impl TryInto<DayOfMonth>,
+    ) -> Result<Date, Error<E>> {
+        let day = check::<DayOfMonth, E>(day)?.get();
+        self.set_alarm_day(day).await?;
+        let today = self.get_datetime().await?.date();
+        next_date_with_day(today, day).ok_or(Error::Data(DataError::ComponentRange))
+    }
+
+    /// The next date the alarm day matches on, from today; `None` if alarm day is disabled.
+    ///
+    /// The chip never matches a day that the current month lacks, so this may lie in a later
+    /// month.
+    pub async fn next_alarm_day(&mut self) -> Result<Option<Date>, Error<E>> {
+        let Some(day) = self.get_alarm_day().await? else {
+            return Ok(None);
+        };
+        let today = self.get_datetime().await?.date();
+        next_date_with_day(today, day)
+            .map(Some)
+            .ok_or(Error::Data(DataError::ComponentRange))
+    }
+
     /// Set the alarm weekday [0-6], keeping the AE bit unchanged.
     pub async fn set_alarm_weekday(&mut self, weekday: u8) -> Result<(), Error<E>> {
         if weekday > 6 {
-            return Err(Error::InvalidInputData);
+            return Err(Error::Usage(UsageError::InvalidInputData));
         }
         let data: u8 = self.read_register(Register::WEEKDAY_ALARM).await?; // read current value
         let data: u8 = data & BitFlags::AE; // keep the AE bit as is
-        let setting: u8 = encode_bcd(weekday);
+        let setting: u8 = bcd::encode(weekday);
         let data: u8 = data | setting;
         self.write_register(Register::WEEKDAY_ALARM, data).await
     }
 
+    /// Set the alarm seconds [0-59] and whether they are enabled, in a single write.
+    ///
+    /// Unlike `set_alarm_seconds`, this does not read the register first.
+    pub async fn set_alarm_seconds_enabled(
+        &mut self,
+        seconds: impl TryInto<Seconds>,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        let seconds = check::<Seconds, E>(seconds)?.get();
+        self.write_alarm_field(Register::SECOND_ALARM, seconds, enabled)
+            .await
+    }
+
+    /// Set the alarm minutes [0-59] and whether they are enabled, in a single write.
+    pub async fn set_alarm_minutes_enabled(
+        &mut self,
+        minutes: impl TryInto<Minutes>,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        let minutes = check::<Minutes, E>(minutes)?.get();
+        self.write_alarm_field(Register::MINUTE_ALARM, minutes, enabled)
+            .await
+    }
+
+    /// Set the alarm hours [0-23] and whether they are enabled, in a single write.
+    pub async fn set_alarm_hours_enabled(
+        &mut self,
+        hours: impl TryInto<Hours>,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        let hours = check::<Hours, E>(hours)?.get();
+        self.write_alarm_field(Register::HOUR_ALARM, hours, enabled)
+            .await
+    }
+
+    /// Set the alarm day [1-31] and whether it is enabled, in a single write.
+    pub async fn set_alarm_day_enabled(
+        &mut self,
+        day: impl TryInto<DayOfMonth>,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        let day = check::<DayOfMonth, E>(day)?.get();
+        self.write_alarm_field(Register::DAY_ALARM, day, enabled)
+            .await
+    }
+
+    /// Set the alarm weekday [0-6] and whether it is enabled, in a single write.
+    pub async fn set_alarm_weekday_enabled(
+        &mut self,
+        weekday: u8,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        if weekday > 6 {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        self.write_alarm_field(Register::WEEKDAY_ALARM, weekday, enabled)
+            .await
+    }
+
+    /// Clear the AE bit of an alarm register to enable it, or set it to disable it.
+    async fn enable_alarm_field(
+        &mut self,
+        register: Register,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        if enabled {
+            self.clear_register_bit_flag(register, BitFlags::AE).await
+        } else {
+            self.set_register_bit_flag(register, BitFlags::AE).await
+        }
+    }
+
+    /// Write an alarm register; the AE bit is set to disable the alarm.
+    async fn write_alarm_field(
+        &mut self,
+        register: Register,
+        value: u8,
+        enabled: bool,
+    ) -> Result<(), Error<E>> {
+        self.write_register(register, codec::encode_alarm_field(value, enabled))
+            .await
+    }
+
+    /// Enable or disable the alarm seconds.
+    pub async fn enable_alarm_seconds(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.enable_alarm_field(Register::SECOND_ALARM, enabled)
+            .await
+    }
+
     /// Control alarm seconds (On: alarm enabled, Off: alarm disabled).
+    #[deprecated(note = "use `enable_alarm_seconds`")]
+    #[allow(deprecated)]
     pub async fn control_alarm_seconds(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => {
-                self.set_register_bit_flag(Register::SECOND_ALARM, BitFlags::AE)
-                    .await
-            }
-            Control::On => {
-                self.clear_register_bit_flag(Register::SECOND_ALARM, BitFlags::AE)
-                    .await
-            }
-        }
+        self.enable_alarm_seconds(status.into()).await
     }
 
     /// Is alarm seconds enabled?
@@ -95,18 +224,17 @@ where
             .await?)
     }
 
+    /// Enable or disable the alarm minutes.
+    pub async fn enable_alarm_minutes(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.enable_alarm_field(Register::MINUTE_ALARM, enabled)
+            .await
+    }
+
     /// Control alarm minutes (On: alarm enabled, Off: alarm disabled).
+    #[deprecated(note = "use `enable_alarm_minutes`")]
+    #[allow(deprecated)]
     pub async fn control_alarm_minutes(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => {
-                self.set_register_bit_flag(Register::MINUTE_ALARM, BitFlags::AE)
-                    .await
-            }
-            Control::On => {
-                self.clear_register_bit_flag(Register::MINUTE_ALARM, BitFlags::AE)
-                    .await
-            }
-        }
+        self.enable_alarm_minutes(status.into()).await
     }
 
     /// Is alarm minutes enabled?
@@ -116,18 +244,16 @@ where
             .await?)
     }
 
+    /// Enable or disable the alarm hours.
+    pub async fn enable_alarm_hours(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.enable_alarm_field(Register::HOUR_ALARM, enabled).await
+    }
+
     /// Control alarm hours (On: alarm enabled, Off: alarm disabled).
+    #[deprecated(note = "use `enable_alarm_hours`")]
+    #[allow(deprecated)]
     pub async fn control_alarm_hours(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => {
-                self.set_register_bit_flag(Register::HOUR_ALARM, BitFlags::AE)
-                    .await
-            }
-            Control::On => {
-                self.clear_register_bit_flag(Register::HOUR_ALARM, BitFlags::AE)
-                    .await
-            }
-        }
+        self.enable_alarm_hours(status.into()).await
     }
 
     /// Is alarm hours enabled?
@@ -137,18 +263,16 @@ where
             .await?)
     }
 
+    /// Enable or disable the alarm day.
+    pub async fn enable_alarm_day(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.enable_alarm_field(Register::DAY_ALARM, enabled).await
+    }
+
     /// Control alarm day (On: alarm enabled, Off: alarm disabled).
+    #[deprecated(note = "use `enable_alarm_day`")]
+    #[allow(deprecated)]
     pub async fn control_alarm_day(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => {
-                self.set_register_bit_flag(Register::DAY_ALARM, BitFlags::AE)
-                    .await
-            }
-            Control::On => {
-                self.clear_register_bit_flag(Register::DAY_ALARM, BitFlags::AE)
-                    .await
-            }
-        }
+        self.enable_alarm_day(status.into()).await
     }
 
     /// Is alarm day enabled?
@@ -158,18 +282,17 @@ where
             .await?)
     }
 
+    /// Enable or disable the alarm weekday.
+    pub async fn enable_alarm_weekday(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.enable_alarm_field(Register::WEEKDAY_ALARM, enabled)
+            .await
+    }
+
     /// Control alarm weekday (On: alarm enabled, Off: alarm disabled).
+    #[deprecated(note = "use `enable_alarm_weekday`")]
+    #[allow(deprecated)]
     pub async fn control_alarm_weekday(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::Off => {
-                self.set_register_bit_flag(Register::WEEKDAY_ALARM, BitFlags::AE)
-                    .await
-            }
-            Control::On => {
-                self.clear_register_bit_flag(Register::WEEKDAY_ALARM, BitFlags::AE)
-                    .await
-            }
-        }
+        self.enable_alarm_weekday(status.into()).await
     }
 
     /// Is alarm weekday enabled?
@@ -179,76 +302,61 @@ where
             .await?)
     }
 
+    /// Enable or disable the alarm interrupt.
+    pub async fn enable_alarm_interrupt(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        if enabled {
+            self.set_register_bit_flag(Register::CONTROL_2, BitFlags::AIE)
+                .await
+        } else {
+            self.clear_register_bit_flag(Register::CONTROL_2, BitFlags::AIE)
+                .await
+        }
+    }
+
     /// Enable or disable alarm interrupt.
+    #[deprecated(note = "use `enable_alarm_interrupt`")]
+    #[allow(deprecated)]
     pub async fn control_alarm_interrupt(&mut self, status: Control) -> Result<(), Error<E>> {
-        match status {
-            Control::On => {
-                self.set_register_bit_flag(Register::CONTROL_2, BitFlags::AIE)
-                    .await
-            }
-            Control::Off => {
-                self.clear_register_bit_flag(Register::CONTROL_2, BitFlags::AIE)
-                    .await
-            }
-        }
+        self.enable_alarm_interrupt(status.into()).await
     }
 
+    /// Read the alarm seconds, minutes and hours as stored, regardless of whether they are
+    /// enabled; use the individual getters to see which are.
     pub async fn get_alarm_time(&mut self) -> Result<Time, Error<E>> {
-        Ok(Time::from_hms(
-            self.get_alarm_hours().await?,
-            self.get_alarm_minutes().await?,
-            self.get_alarm_seconds().await?,
-        )?)
-    }
-
-    /// Read the alarm seconds setting.        
-    pub async fn get_alarm_seconds(&mut self) -> Result<u8, Error<E>> {
-        let mut data = [0];
-        self.i2c
-            .write_read(DEVICE_ADDRESS, &[Register::SECOND_ALARM], &mut data)
-            .await
-            .map_err(Error::I2C)?;
-        Ok(decode_bcd(data[0]))
+        let mut data = [0; 3];
+        self.read_bytes(Register::SECOND_ALARM, &mut data).await?;
+        codec::decode_alarm_time(data).map_err(Error::Data)
     }
 
-    /// Read the alarm minutes setting.        
-    pub async fn get_alarm_minutes(&mut self) -> Result<u8, Error<E>> {
-        let mut data = [0];
-        self.i2c
-            .write_read(DEVICE_ADDRESS, &[Register::MINUTE_ALARM], &mut data)
-            .await
-            .map_err(Error::I2C)?;
-        Ok(decode_bcd(data[0]))
+    /// Read the alarm seconds setting, `None` if alarm seconds are disabled.
+    pub async fn get_alarm_seconds(&mut self) -> Result<Option<u8>, Error<E>> {
+        self.get_alarm_field(Register::SECOND_ALARM).await
     }
 
-    /// Read the alarm hours setting.
-    pub async fn get_alarm_hours(&mut self) -> Result<u8, Error<E>> {
-        let mut data = [0];
-        self.i2c
-            .write_read(DEVICE_ADDRESS, &[Register::HOUR_ALARM], &mut data)
-            .await
-            .map_err(Error::I2C)?;
-        Ok(decode_bcd(data[0]))
+    /// Read the alarm minutes setting, `None` if alarm minutes are disabled.
+    pub async fn get_alarm_minutes(&mut self) -> Result<Option<u8>, Error<E>> {
+        self.get_alarm_field(Register::MINUTE_ALARM).await
     }
 
-    /// Read the alarm day setting.
-    pub async fn get_alarm_day(&mut self) -> Result<u8, Error<E>> {
-        let mut data = [0];
-        self.i2c
-            .write_read(DEVICE_ADDRESS, &[Register::DAY_ALARM], &mut data)
-            .await
-            .map_err(Error::I2C)?;
-        Ok(decode_bcd(data[0]))
+    /// Read the alarm hours setting, `None` if alarm hours are disabled.
+    pub async fn get_alarm_hours(&mut self) -> Result<Option<u8>, Error<E>> {
+        self.get_alarm_field(Register::HOUR_ALARM).await
     }
 
-    /// Read the alarm weekday setting.
-    pub async fn get_alarm_weekday(&mut self) -> Result<u8, Error<E>> {
-        let mut data = [0];
-        self.i2c
-            .write_read(DEVICE_ADDRESS, &[Register::WEEKDAY_ALARM], &mut data)
-            .await
-            .map_err(Error::I2C)?;
-        Ok(decode_bcd(data[0]))
+    /// Read the alarm day setting, `None` if alarm day is disabled.
+    pub async fn get_alarm_day(&mut self) -> Result<Option<u8>, Error<E>> {
+        self.get_alarm_field(Register::DAY_ALARM).await
+    }
+
+    /// Read the alarm weekday setting, `None` if alarm weekday is disabled.
+    pub async fn get_alarm_weekday(&mut self) -> Result<Option<u8>, Error<E>> {
+        self.get_alarm_field(Register::WEEKDAY_ALARM).await
+    }
+
+    /// Read an alarm register, `None` if its AE bit marks it as disabled.
+    async fn get_alarm_field(&mut self, register: Register) -> Result<Option<u8>, Error<E>> {
+        let data = self.read_register(register).await?;
+        Ok(codec::decode_alarm_field(data))
     }
 
     /// Get the alarm flag (if true, alarm event happened).
@@ -263,6 +371,16 @@ where
             .await
     }
 
+    /// Clear the alarm flag and enable the alarm interrupt in a single write.
+    ///
+    /// Calling `clear_alarm_flag` and `enable_alarm_interrupt` back to back leaves a window in
+    /// which a firing alarm is lost, or a stale flag asserts the interrupt pin right away.
+    pub async fn arm_alarm_interrupt(&mut self) -> Result<(), Error<E>> {
+        let value = self.read_register(Register::CONTROL_2).await?;
+        self.write_register(Register::CONTROL_2, codec::arm_alarm_interrupt(value))
+            .await
+    }
+
     /// Check if alarm interrupt is enabled.
     pub async fn is_alarm_interrupt_enabled(&mut self) -> Result<bool, Error<E>> {
         self.is_register_bit_flag_high(Register::CONTROL_2, BitFlags::AIE)
@@ -270,12 +388,15 @@ where
     }
 
     /// Shut off the alarms at once.
+    ///
+    /// All five alarm registers are written in a single transaction, so cancelling this never
+    /// leaves only some of the alarms disabled.
     pub async fn disable_all_alarms(&mut self) -> Result<(), Error<E>> {
-        self.control_alarm_seconds(Control::Off).await?;
-        self.control_alarm_minutes(Control::Off).await?;
-        self.control_alarm_hours(Control::Off).await?;
-        self.control_alarm_day(Control::Off).await?;
-        self.control_alarm_weekday(Control::Off).await?;
-        Ok(())
+        let mut data = [0; 5];
+        self.read_bytes(Register::SECOND_ALARM, &mut data).await?;
+
+        let mut payload = [Register::SECOND_ALARM as u8; 6];
+        payload[1..].copy_from_slice(&codec::disable_alarms(data));
+        self.write_bytes(&payload).await
     }
 }
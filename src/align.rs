@@ -0,0 +1,100 @@
+//! Scheduling the alarm on clean wall-clock boundaries.
+//!
+//! Periodic reporting started at boot drifts along with the boot time; aligning each wake to
+//! the next boundary keeps reports on the quarter hour, say, however long the device took to
+//! start:
+//!
+//! ```ignore
+//! loop {
+//!     let at = rtc.align_to(Boundary::QuarterHour).await?;
+//!     rtc.arm_alarm_interrupt().await?;
+//!     int.wait_for_low().await?;
+//!     report().await;
+//! }
+//! ```
+
+use super::{DataError, Error, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{Duration, PrimitiveDateTime, Time};
+
+/// A wall-clock boundary, as passed to [`PCF85063::align_to`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Boundary {
+    /// Whole minutes
+    Minute,
+    /// :00, :15, :30 and :45
+    QuarterHour,
+    /// Whole hours
+    Hour,
+    /// Midnight
+    Day,
+}
+
+impl Boundary {
+    /// Length of the period between boundaries, in seconds.
+    const fn seconds(self) -> i64 {
+        match self {
+            Boundary::Minute => 60,
+            Boundary::QuarterHour => 15 * 60,
+            Boundary::Hour => 3600,
+            Boundary::Day => 86_400,
+        }
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Set the alarm to the first `boundary` strictly after the current time, and return it.
+    ///
+    /// The seconds, minutes, hours and day of the month are matched, as with `set_alarm_at`;
+    /// the alarm interrupt and flag are left alone.
+    pub async fn align_to(&mut self, boundary: Boundary) -> Result<PrimitiveDateTime, Error<E>> {
+        let now = self.get_datetime().await?;
+        let at = next_boundary(now, boundary).ok_or(Error::Data(DataError::ComponentRange))?;
+        self.set_alarm_at(at).await?;
+        Ok(at)
+    }
+}
+
+/// The first `boundary` strictly after `after`, `None` beyond the range of `time::Date`.
+fn next_boundary(after: PrimitiveDateTime, boundary: Boundary) -> Option<PrimitiveDateTime> {
+    let period = boundary.seconds();
+    let since_midnight = (after.time() - Time::MIDNIGHT).whole_seconds();
+    let next = (since_midnight / period + 1) * period;
+    PrimitiveDateTime::new(after.date(), Time::MIDNIGHT).checked_add(Duration::seconds(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn finds_next_boundary() {
+        let now = datetime!(2024-02-29 23:52:30.5);
+        assert_eq!(
+            Some(datetime!(2024-02-29 23:53:00)),
+            next_boundary(now, Boundary::Minute)
+        );
+        assert_eq!(
+            Some(datetime!(2024-03-01 00:00:00)),
+            next_boundary(now, Boundary::QuarterHour)
+        );
+        assert_eq!(
+            Some(datetime!(2024-03-01 00:00:00)),
+            next_boundary(now, Boundary::Hour)
+        );
+        assert_eq!(
+            Some(datetime!(2024-03-01 00:00:00)),
+            next_boundary(now, Boundary::Day)
+        );
+        // a boundary that is now is not ahead
+        assert_eq!(
+            Some(datetime!(2024-02-29 12:15:00)),
+            next_boundary(datetime!(2024-02-29 12:00:00), Boundary::QuarterHour)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! A ring log of time changes in persistent storage, for devices where every adjustment of the
+//! clock must be traceable (metering, compliance).
+//!
+//! Each entry records the time before and after the change and an application-defined source
+//! tag (e.g. 1 for NTP, 2 for a user). Entries carry a sequence number and a checksum, so the
+//! log is found again after a reboot and torn writes are skipped.
+
+use super::persist::{crc8, PersistError, PersistentStorage};
+use super::{Error, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+/// Number of bytes taken by a log entry.
+pub const AUDIT_ENTRY_LEN: usize = 22;
+
+/// Marks an unknown time before the change.
+const UNKNOWN: i64 = i64::MIN;
+
+/// A logged change of the time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeChange {
+    /// Increases by one with every entry
+    pub sequence: u32,
+    /// The time before the change, `None` if the clock held no valid time
+    pub old: Option<PrimitiveDateTime>,
+    pub new: PrimitiveDateTime,
+    /// Application-defined source of the change
+    pub source: u8,
+}
+
+impl TimeChange {
+    fn to_entry(self) -> [u8; AUDIT_ENTRY_LEN] {
+        let mut entry = [0; AUDIT_ENTRY_LEN];
+        entry[0..4].copy_from_slice(&self.sequence.to_le_bytes());
+        let old = self.old.map_or(UNKNOWN, unix_timestamp);
+        entry[4..12].copy_from_slice(&old.to_le_bytes());
+        entry[12..20].copy_from_slice(&unix_timestamp(self.new).to_le_bytes());
+        entry[20] = self.source;
+        entry[21] = crc8(&entry[..21]);
+        entry
+    }
+
+    fn from_entry(entry: &[u8; AUDIT_ENTRY_LEN]) -> Option<Self> {
+        // erased flash reads as all ones
+        if entry[21] != crc8(&entry[..21]) || entry[..4] == [0xff; 4] {
+            return None;
+        }
+        let field = |range: core::ops::Range<usize>| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&entry[range]);
+            i64::from_le_bytes(bytes)
+        };
+        let mut sequence = [0; 4];
+        sequence.copy_from_slice(&entry[0..4]);
+        let old = match field(4..12) {
+            UNKNOWN => None,
+            old => Some(from_unix_timestamp(old)?),
+        };
+        Some(TimeChange {
+            sequence: u32::from_le_bytes(sequence),
+            old,
+            new: from_unix_timestamp(field(12..20))?,
+            source: entry[20],
+        })
+    }
+}
+
+fn unix_timestamp(datetime: PrimitiveDateTime) -> i64 {
+    datetime.assume_utc().unix_timestamp()
+}
+
+fn from_unix_timestamp(timestamp: i64) -> Option<PrimitiveDateTime> {
+    let datetime = OffsetDateTime::from_unix_timestamp(timestamp).ok()?;
+    Some(PrimitiveDateTime::new(datetime.date(), datetime.time()))
+}
+
+/// A ring of `capacity` entries of `AUDIT_ENTRY_LEN` bytes, starting at `address`.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    address: u32,
+    capacity: u32,
+    /// Sequence number of the next entry, once the log has been scanned
+    next: Option<u32>,
+}
+
+impl AuditLog {
+    /// Create a log of `capacity` (at least one) entries at `address`.
+    pub const fn new(address: u32, capacity: u32) -> Self {
+        AuditLog {
+            address,
+            capacity: if capacity == 0 { 1 } else { capacity },
+            next: None,
+        }
+    }
+
+    /// Append `change`, overwriting the oldest entry once the log is full. Its sequence number
+    /// is assigned by the log.
+    pub async fn append<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        change: TimeChange,
+    ) -> Result<TimeChange, S::Error> {
+        let sequence = match self.next {
+            Some(next) => next,
+            None => self
+                .newest(storage)
+                .await?
+                .map_or(0, |newest| newest.sequence + 1),
+        };
+        let change = TimeChange { sequence, ..change };
+        storage
+            .write(self.slot_address(sequence), &change.to_entry())
+            .await?;
+        self.next = Some(sequence.wrapping_add(1));
+        Ok(change)
+    }
+
+    /// Read the entry `n` changes back, 0 being the most recent.
+    pub async fn get<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        n: u32,
+    ) -> Result<Option<TimeChange>, S::Error> {
+        let Some(newest) = self.newest(storage).await? else {
+            return Ok(None);
+        };
+        if n >= self.capacity || n > newest.sequence {
+            return Ok(None);
+        }
+        let sequence = newest.sequence - n;
+        Ok(self
+            .read_slot(storage, sequence)
+            .await?
+            .filter(|change| change.sequence == sequence))
+    }
+
+    /// The most recent entry, found by scanning all slots.
+    async fn newest<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<Option<TimeChange>, S::Error> {
+        let mut newest: Option<TimeChange> = None;
+        for slot in 0..self.capacity {
+            if let Some(change) = self.read_slot(storage, slot).await? {
+                if newest.is_none_or(|newest| change.sequence > newest.sequence) {
+                    newest = Some(change);
+                }
+            }
+        }
+        self.next = Some(newest.map_or(0, |newest| newest.sequence + 1));
+        Ok(newest)
+    }
+
+    async fn read_slot<S: PersistentStorage>(
+        &self,
+        storage: &mut S,
+        sequence: u32,
+    ) -> Result<Option<TimeChange>, S::Error> {
+        let mut entry = [0; AUDIT_ENTRY_LEN];
+        storage
+            .read(self.slot_address(sequence), &mut entry)
+            .await?;
+        Ok(TimeChange::from_entry(&entry))
+    }
+
+    fn slot_address(&self, sequence: u32) -> u32 {
+        self.address + (sequence % self.capacity) * AUDIT_ENTRY_LEN as u32
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Set date and time, and record the change with `source` in `log`; returns `None` if the
+    /// write was skipped under the write threshold, which is not recorded.
+    ///
+    /// The change is recorded after the clock was set, so a failing storage never blocks
+    /// setting the time, but the error is returned.
+    pub async fn set_datetime_audited<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        log: &mut AuditLog,
+        datetime: &PrimitiveDateTime,
+        source: u8,
+    ) -> Result<Option<TimeChange>, PersistError<Error<E>, S::Error>> {
+        let old = self.get_valid_datetime().await.map_err(PersistError::Rtc)?;
+
+        let written = self
+            .set_datetime(datetime)
+            .await
+            .map_err(PersistError::Rtc)?;
+        if !written {
+            return Ok(None);
+        }
+
+        let change = TimeChange {
+            sequence: 0,
+            old,
+            new: *datetime,
+            source,
+        };
+        log.append(storage, change)
+            .await
+            .map(Some)
+            .map_err(PersistError::Storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn can_round_trip_entries() {
+        let change = TimeChange {
+            sequence: 7,
+            old: Some(datetime!(2024-02-29 23:30:59)),
+            new: datetime!(2024-03-01 00:00:00),
+            source: 1,
+        };
+        assert_eq!(Some(change), TimeChange::from_entry(&change.to_entry()));
+
+        let change = TimeChange {
+            old: None,
+            ..change
+        };
+        assert_eq!(Some(change), TimeChange::from_entry(&change.to_entry()));
+        assert_eq!(None, TimeChange::from_entry(&[0xff; AUDIT_ENTRY_LEN]));
+    }
+}
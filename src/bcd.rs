@@ -0,0 +1,114 @@
+//! Binary Coded Decimal conversion, as used by the time, date and alarm registers.
+//!
+//! Several registers share their byte with flags (e.g. the AE bit of the alarm registers), so
+//! the masked variants decode only the bits selected by a mask.
+
+/// Convert a Binary Coded Decimal byte [0x00-0x99] to decimal.
+///
+/// Invalid digits are not detected, see [`decode_checked`].
+pub const fn decode(input: u8) -> u8 {
+    let digits: u8 = input & 0xf;
+    let tens: u8 = input >> 4;
+    10 * tens + digits
+}
+
+/// Convert the bits of a Binary Coded Decimal byte selected by `mask` to decimal.
+pub const fn decode_masked(input: u8, mask: u8) -> u8 {
+    decode(input & mask)
+}
+
+/// Convert a Binary Coded Decimal byte to decimal, or `None` if either digit is above 9.
+pub const fn decode_checked(input: u8) -> Option<u8> {
+    if input & 0xf > 9 || input >> 4 > 9 {
+        return None;
+    }
+    Some(decode(input))
+}
+
+/// Convert a decimal value [0-99] to Binary Coded Decimal.
+///
+/// Values above 99 are not detected, see [`encode_checked`].
+pub const fn encode(input: u8) -> u8 {
+    let digits: u8 = input % 10;
+    let tens: u8 = input / 10;
+    let tens = tens << 4;
+    tens + digits
+}
+
+/// Convert a decimal value to Binary Coded Decimal, or `None` if it is above 99.
+pub const fn encode_checked(input: u8) -> Option<u8> {
+    if input > 99 {
+        return None;
+    }
+    Some(encode(input))
+}
+
+/// Convert each Binary Coded Decimal byte of `input` to decimal in `output`.
+///
+/// Converts as many bytes as the shorter of the two slices holds.
+pub fn decode_slice(input: &[u8], output: &mut [u8]) {
+    for (output, &input) in output.iter_mut().zip(input) {
+        *output = decode(input);
+    }
+}
+
+/// Convert each decimal value of `input` to Binary Coded Decimal in `output`.
+///
+/// Converts as many values as the shorter of the two slices holds.
+pub fn encode_slice(input: &[u8], output: &mut [u8]) {
+    for (output, &input) in output.iter_mut().zip(input) {
+        *output = encode(input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_convert_decode_bcd() {
+        assert_eq!(0, decode(0b0000_0000));
+        assert_eq!(1, decode(0b0000_0001));
+        assert_eq!(9, decode(0b0000_1001));
+        assert_eq!(10, decode(0b0001_0000));
+        assert_eq!(11, decode(0b0001_0001));
+        assert_eq!(19, decode(0b0001_1001));
+        assert_eq!(20, decode(0b0010_0000));
+        assert_eq!(21, decode(0b0010_0001));
+        assert_eq!(59, decode(0b0101_1001));
+        assert_eq!(99, decode(0b1001_1001));
+    }
+
+    #[test]
+    fn can_convert_encode_bcd() {
+        assert_eq!(0b0000_0000, encode(0));
+        assert_eq!(0b0000_0001, encode(1));
+        assert_eq!(0b0000_1001, encode(9));
+        assert_eq!(0b0001_0000, encode(10));
+        assert_eq!(0b0001_0001, encode(11));
+        assert_eq!(0b0001_1001, encode(19));
+        assert_eq!(0b0010_0000, encode(20));
+        assert_eq!(0b0010_0001, encode(21));
+        assert_eq!(0b0101_1001, encode(59));
+        assert_eq!(0b1001_1001, encode(99));
+    }
+
+    #[test]
+    fn can_convert_masked_and_checked() {
+        assert_eq!(59, decode_masked(0b1101_1001, 0b0111_1111));
+        assert_eq!(None, decode_checked(0x1a));
+        assert_eq!(None, decode_checked(0xa1));
+        assert_eq!(None, encode_checked(100));
+    }
+
+    #[test]
+    fn can_convert_slices() {
+        let mut decoded = [0; 3];
+        decode_slice(&[0x59, 0x30, 0x23], &mut decoded);
+        assert_eq!([59, 30, 23], decoded);
+
+        let mut encoded = [0; 3];
+        encode_slice(&decoded, &mut encoded);
+        assert_eq!([0x59, 0x30, 0x23], encoded);
+    }
+}
@@ -0,0 +1,89 @@
+//! Read and set the PCF85063A from Linux, e.g. on a Raspberry Pi HAT.
+//!
+//! ```text
+//! pcf85063a-cli [--bus /dev/i2c-1] <command>
+//! ```
+
+use pcf85063a::linux::{block_on, LinuxI2c};
+use pcf85063a::{OffsetMode, Register, PCF85063};
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+const USAGE: &str = "\
+usage: pcf85063a-cli [--bus <device>] <command>
+
+commands:
+  get            print the date and time
+  systohc        set the date and time from the system clock, in UTC
+  dump           print all registers
+  offset [ppm]   print the offset correction, or set it in ppm
+
+The bus defaults to /dev/i2c-1.";
+
+fn main() {
+    if let Err(error) = run(std::env::args().skip(1).collect()) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let (bus, args) = match args.as_slice() {
+        [flag, bus, args @ ..] if flag == "--bus" => (bus.as_str(), args),
+        args => ("/dev/i2c-1", args),
+    };
+    let command: Vec<&str> = args.iter().map(String::as_str).collect();
+    let bus = LinuxI2c::open(bus).map_err(|error| format!("{bus}: {error}"))?;
+    let mut rtc = PCF85063::new(bus);
+
+    match command.as_slice() {
+        ["get"] => println!("{}", block_on(rtc.get_datetime())?),
+        ["systohc"] => {
+            let now = system_time_at_next_second();
+            block_on(rtc.set_datetime(&now))?;
+            println!("{now}");
+        }
+        ["dump"] => {
+            for register in Register::ALL {
+                let value = block_on(rtc.read_register(register))?;
+                println!(
+                    "{:#04x} {register:?}: {value:#04x} {value:#010b}",
+                    register as u8
+                );
+            }
+        }
+        ["offset"] => {
+            let (mode, offset) = block_on(rtc.get_offset())?;
+            let ppm = offset as f32 * mode.ppm_per_step();
+            println!("{offset} steps, {mode:?} mode: {ppm:+.2} ppm");
+        }
+        ["offset", ppm] => {
+            let ppm: f32 = ppm.parse()?;
+            let steps = (ppm / OffsetMode::Normal.ppm_per_step()).round();
+            if !(-64.0..=63.0).contains(&steps) {
+                return Err(format!("{ppm} ppm is outside the correction range").into());
+            }
+            block_on(rtc.set_offset(OffsetMode::Normal, steps as i8))?;
+            println!("{steps} steps, Normal mode");
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    }
+    Ok(())
+}
+
+/// Wait for the next second of the system clock to start, and return it in UTC.
+fn system_time_at_next_second() -> PrimitiveDateTime {
+    let since_epoch = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("system clock before 1970");
+    std::thread::sleep(
+        Duration::from_secs(1) - Duration::from_nanos(since_epoch.subsec_nanos().into()),
+    );
+    let now = OffsetDateTime::from_unix_timestamp(since_epoch.as_secs() as i64 + 1)
+        .expect("system clock out of range");
+    PrimitiveDateTime::new(now.date(), now.time())
+}
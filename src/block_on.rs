@@ -0,0 +1,16 @@
+//! Running the driver's futures where nothing ever waits.
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+/// Run a future that completes without waiting, such as the driver's on a blocking bus or the
+/// simulator.
+///
+/// Panics if the future does wait, e.g. for a delay or a pin.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future waited, which nothing wakes"),
+    }
+}
@@ -0,0 +1,93 @@
+//! Serving recent reads of the time from a cache.
+//!
+//! Applications timestamping many events per second would otherwise read the RTC for each of
+//! them. `now_cached` reads it only when the last read is older than a maximum age, measured on
+//! an application monotonic milliseconds counter:
+//!
+//! ```ignore
+//! let now_ms = || embassy_time::Instant::now().as_millis();
+//!
+//! for event in events {
+//!     // at most one bus transaction per 100 ms
+//!     let timestamp = rtc.now_cached(100, now_ms).await?;
+//!     log(timestamp, event);
+//! }
+//! ```
+//!
+//! The cache is dropped on every write to the chip, and whenever the bus is lent out with
+//! `bus_mut` or `with_bus`.
+
+use super::{Error, FineClock, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Date and time, as last read if that was at most `max_age_ms` ago, with `now_ms`
+    /// reading the monotonic milliseconds counter; otherwise read from the chip.
+    pub async fn now_cached(
+        &mut self,
+        max_age_ms: u64,
+        mut now_ms: impl FnMut() -> u64,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        let monotonic_ms = now_ms();
+        if let Some(datetime) = fresh(&self.cached, max_age_ms, monotonic_ms) {
+            return Ok(datetime);
+        }
+        let datetime = self.get_datetime().await?;
+        self.cached.anchor(datetime, monotonic_ms);
+        Ok(datetime)
+    }
+}
+
+/// The cached time, if read at most `max_age_ms` before `monotonic_ms`.
+fn fresh(cached: &FineClock, max_age_ms: u64, monotonic_ms: u64) -> Option<PrimitiveDateTime> {
+    let age = cached.since_anchor(monotonic_ms)?;
+    (age <= max_age_ms).then_some(cached.anchored()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn serves_recent_reads() {
+        let mut cached = FineClock::new();
+        assert_eq!(None, fresh(&cached, 100, 1000));
+
+        cached.anchor(datetime!(2024-02-29 23:59:59), 1000);
+        assert_eq!(
+            Some(datetime!(2024-02-29 23:59:59)),
+            fresh(&cached, 100, 1100)
+        );
+        assert_eq!(None, fresh(&cached, 100, 1101));
+        // the counter wrapped or was reset
+        assert_eq!(None, fresh(&cached, 100, 999));
+    }
+
+    #[cfg(feature = "simulator")]
+    #[test]
+    fn drops_cache_when_lending_bus() {
+        use crate::simulator::{block_on, Simulator};
+        use crate::{Register, DEVICE_ADDRESS};
+
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let now = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 23:59:59)).await?;
+            rtc.now_cached(100, || 1000).await?;
+            // set the minutes and hours to 0:30 behind the driver's back
+            rtc.bus_mut()
+                .write(DEVICE_ADDRESS, &[Register::MINUTES as u8, 0x30, 0x00])
+                .await
+                .map_err(Error::I2C)?;
+            rtc.now_cached(100, || 1000).await
+        })
+        .unwrap();
+
+        assert_eq!(datetime!(2024-02-29 00:30:59), now);
+    }
+}
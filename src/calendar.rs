@@ -0,0 +1,133 @@
+//! Calendar arithmetic for recurring schedules, on the date and time the chip keeps.
+//!
+//! ```
+//! use pcf85063a::calendar;
+//! use time::macros::{date, datetime, time};
+//! use time::Weekday;
+//!
+//! let now = datetime!(2024-01-31 09:00:00);
+//! assert_eq!(
+//!     datetime!(2024-02-05 07:30:00),
+//!     calendar::next_occurrence_of(now, Weekday::Monday, time!(07:30)).unwrap()
+//! );
+//! assert_eq!(date!(2024 - 02 - 29), calendar::last_day_of_month(date!(2024 - 02 - 10)));
+//! assert_eq!(Some(datetime!(2024-02-29 09:00:00)), calendar::add_months(now, 1));
+//! ```
+
+use time::{Date, Duration, Month, PrimitiveDateTime, Time, Weekday};
+
+/// The first `weekday` at `time` strictly after `after`.
+///
+/// Returns `None` if that falls beyond the range of `time::Date`.
+pub fn next_occurrence_of(
+    after: PrimitiveDateTime,
+    weekday: Weekday,
+    time: Time,
+) -> Option<PrimitiveDateTime> {
+    let days_ahead = after.weekday().number_days_from_monday() as i64;
+    let days_ahead = (weekday.number_days_from_monday() as i64 - days_ahead).rem_euclid(7);
+    let candidate = after
+        .date()
+        .checked_add(Duration::days(days_ahead))?
+        .with_time(time);
+    if candidate > after {
+        Some(candidate)
+    } else {
+        candidate.checked_add(Duration::weeks(1))
+    }
+}
+
+/// The last day of the month `date` lies in.
+pub fn last_day_of_month(date: Date) -> Date {
+    let last = date.month().length(date.year());
+    // the day exists in the month
+    date.replace_day(last).unwrap_or(date)
+}
+
+/// Shift `datetime` by `months`, which may be negative, keeping the time of day.
+///
+/// A day that does not exist in the resulting month is clamped to its last day, so January 31
+/// plus one month is the last day of February. Returns `None` if the result falls beyond the
+/// range of `time::Date`.
+pub fn add_months(datetime: PrimitiveDateTime, months: i32) -> Option<PrimitiveDateTime> {
+    let index = datetime.year() * 12 + datetime.month() as i32 - 1;
+    let index = index.checked_add(months)?;
+    let year = index.div_euclid(12);
+    let month = Month::try_from(index.rem_euclid(12) as u8 + 1).ok()?;
+    let day = datetime.day().min(month.length(year));
+    let date = Date::from_calendar_date(year, month, day).ok()?;
+    Some(date.with_time(datetime.time()))
+}
+
+/// The first date on or after `from` falling on day `day` of its month.
+///
+/// Months without that day are skipped, as the alarm of the chip does. Returns `None` if `day`
+/// is not in [1-31], or the date falls beyond the range of `time::Date`.
+pub fn next_date_with_day(from: Date, day: u8) -> Option<Date> {
+    if !(1..=31).contains(&day) {
+        return None;
+    }
+    let mut month_start = from.replace_day(1).ok()?;
+    // a day of 31 is found within the coming two months
+    for _ in 0..3 {
+        if let Ok(date) = month_start.replace_day(day) {
+            if date >= from {
+                return Some(date);
+            }
+        }
+        month_start = last_day_of_month(month_start).next_day()?;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime, time};
+
+    #[test]
+    fn finds_next_occurrence() {
+        let monday = datetime!(2024-02-26 07:30:00);
+        let next = |after| next_occurrence_of(after, Weekday::Monday, time!(07:30));
+        assert_eq!(Some(datetime!(2024-03-04 07:30:00)), next(monday));
+        assert_eq!(Some(monday), next(datetime!(2024-02-26 07:29:59)));
+        assert_eq!(Some(monday), next(datetime!(2024-02-20 12:00:00)));
+    }
+
+    #[test]
+    fn shifts_by_months() {
+        assert_eq!(
+            date!(2023 - 02 - 28),
+            last_day_of_month(date!(2023 - 02 - 01))
+        );
+        assert_eq!(
+            date!(2024 - 12 - 31),
+            last_day_of_month(date!(2024 - 12 - 31))
+        );
+
+        let datetime = datetime!(2024-01-31 12:00:00);
+        assert_eq!(
+            Some(datetime!(2024-02-29 12:00:00)),
+            add_months(datetime, 1)
+        );
+        assert_eq!(
+            Some(datetime!(2025-02-28 12:00:00)),
+            add_months(datetime, 13)
+        );
+        assert_eq!(
+            Some(datetime!(2023-11-30 12:00:00)),
+            add_months(datetime, -2)
+        );
+        assert_eq!(None, add_months(datetime, i32::MAX));
+    }
+
+    #[test]
+    fn skips_months_without_day() {
+        let next = |from, day| next_date_with_day(from, day);
+        assert_eq!(Some(date!(2024 - 05 - 31)), next(date!(2024 - 04 - 01), 31));
+        assert_eq!(Some(date!(2024 - 03 - 31)), next(date!(2024 - 03 - 31), 31));
+        assert_eq!(Some(date!(2023 - 03 - 30)), next(date!(2023 - 01 - 31), 30));
+        assert_eq!(Some(date!(2024 - 02 - 29)), next(date!(2024 - 02 - 01), 29));
+        assert_eq!(None, next(date!(2024 - 02 - 01), 32));
+    }
+}
@@ -0,0 +1,106 @@
+//! Millisecond timestamps from counting the edges of CLKOUT.
+//!
+//! CLKOUT is divided from the same crystal as the seconds, so a timer counting its edges (an
+//! input-capture or external-clock timer of the MCU) interpolates between RTC seconds without
+//! the drift of an MCU clock, unlike [`FineClock`](super::FineClock):
+//!
+//! ```ignore
+//! let mut clock = CaptureClock::new(OutputFrequency::Hz1024);
+//! rtc.write_clock_output_frequency(OutputFrequency::Hz1024).await?;
+//!
+//! rtc.anchor_capture_clock(&mut clock, &mut edges, &mut delay).await?;
+//! let timestamp = clock.now(edges.count()).unwrap();
+//! ```
+//!
+//! The count wraps: at 32768 Hz after 36 hours, at 1024 Hz after 48 days. Anchor again within
+//! that time.
+
+use super::{Error, OutputFrequency, PCF85063};
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+use time::{Duration, PrimitiveDateTime};
+
+/// Poll interval while looking for the start of a second, the resolution of the anchor.
+const ANCHOR_POLL_MS: u32 = 1;
+
+/// A free-running count of CLKOUT edges, such as an input-capture timer fed by CLKOUT.
+pub trait EdgeCounter {
+    /// The number of edges counted, wrapping around at `u32::MAX`.
+    fn count(&mut self) -> u32;
+}
+
+/// An RTC time anchored to a count of CLKOUT edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureClock {
+    hz: u32,
+    /// The start of an RTC second, and the count it was seen at.
+    anchor: Option<(PrimitiveDateTime, u32)>,
+}
+
+impl CaptureClock {
+    /// A clock counting CLKOUT at `frequency`, which must not be `OutputFrequency::Hz0`.
+    pub const fn new(frequency: OutputFrequency) -> Self {
+        CaptureClock {
+            hz: frequency.hz(),
+            anchor: None,
+        }
+    }
+
+    /// Anchor the start of the second `datetime` to `count`.
+    pub fn anchor(&mut self, datetime: PrimitiveDateTime, count: u32) {
+        self.anchor = Some((datetime, count));
+    }
+
+    /// The time at `count` to the millisecond, `None` if the clock was never anchored or
+    /// counts no frequency.
+    pub fn now(&self, count: u32) -> Option<PrimitiveDateTime> {
+        let (datetime, anchor_count) = self.anchor?;
+        if self.hz == 0 {
+            return None;
+        }
+        let edges = count.wrapping_sub(anchor_count) as u64;
+        let ms = edges * 1000 / self.hz as u64;
+        datetime.checked_add(Duration::milliseconds(ms as i64))
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Wait for the next second to start and anchor `clock` to it, reading the count from
+    /// `counter`.
+    ///
+    /// Takes up to a second; the anchor is accurate to about the duration of one register read.
+    pub async fn anchor_capture_clock(
+        &mut self,
+        clock: &mut CaptureClock,
+        counter: &mut impl EdgeCounter,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error<E>> {
+        self.wait_for_second_change(delay, ANCHOR_POLL_MS).await?;
+        let count = counter.count();
+        // the second just started, so this reads the same second
+        let datetime = self.get_datetime().await?;
+        clock.anchor(datetime, count);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn interpolates_edge_counts() {
+        let mut clock = CaptureClock::new(OutputFrequency::Hz1024);
+        assert_eq!(None, clock.now(0));
+
+        clock.anchor(datetime!(2024-02-29 23:59:59), u32::MAX - 511);
+        assert_eq!(
+            Some(datetime!(2024-03-01 00:00:00.250)),
+            clock.now(1024 + 256 - 512)
+        );
+        assert_eq!(None, CaptureClock::new(OutputFrequency::Hz0).now(0));
+    }
+}
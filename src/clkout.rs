@@ -0,0 +1,180 @@
+//! Sharing the CLKOUT pin between several consumers.
+//!
+//! Each consumer (say MCU oscillator calibration, and an external chip clocked by the RTC)
+//! holds a [`ClkoutGuard`] while it needs the clock. The first guard enables CLKOUT at the
+//! requested frequency; [`ClkoutGuard::release`] on the last guard turns it off again.
+//!
+//! Dropping a guard cannot talk to the chip, as the bus is async: **CLKOUT keeps running after
+//! the last guard is dropped**, until [`ClkoutShare::release_unused`] is called, e.g. from a
+//! maintenance task. Prefer `release` over dropping where the bus is at hand.
+
+use super::{Error, OutputFrequency, UsageError, PCF85063};
+use core::cell::Cell;
+use embedded_hal_async::i2c::I2c;
+
+/// Reference count of the CLKOUT consumers.
+///
+/// This uses plain cells, so consumers share it within a single task or main loop.
+#[derive(Debug)]
+pub struct ClkoutShare {
+    users: Cell<u8>,
+    frequency: Cell<OutputFrequency>,
+}
+
+/// Keeps CLKOUT enabled while held.
+///
+/// Dropping the guard only gives up the claim; CLKOUT stays on until `release_unused`. Use
+/// [`release`](Self::release) to also switch it off when this is the last guard.
+#[derive(Debug)]
+#[must_use = "CLKOUT may be disabled as soon as the guard is dropped"]
+pub struct ClkoutGuard<'a> {
+    share: &'a ClkoutShare,
+}
+
+impl Drop for ClkoutGuard<'_> {
+    fn drop(&mut self) {
+        self.share.users.set(self.share.users.get() - 1);
+    }
+}
+
+impl ClkoutGuard<'_> {
+    /// Give up the claim, and disable CLKOUT if this was the last guard.
+    ///
+    /// Returns whether CLKOUT is disabled.
+    pub async fn release<I2C, E>(self, rtc: &mut PCF85063<I2C>) -> Result<bool, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+    {
+        let share = self.share;
+        drop(self);
+        share.release_unused(rtc).await
+    }
+}
+
+impl Default for ClkoutShare {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClkoutShare {
+    pub const fn new() -> Self {
+        ClkoutShare {
+            users: Cell::new(0),
+            frequency: Cell::new(OutputFrequency::Hz0),
+        }
+    }
+
+    /// Number of guards held.
+    pub fn users(&self) -> u8 {
+        self.users.get()
+    }
+
+    /// Enable CLKOUT at `frequency`, if it is not enabled yet, and return a guard.
+    ///
+    /// Will return a 'UsageError::InUse' if other consumers hold CLKOUT at another frequency, and
+    /// a 'UsageError::InvalidInputData' for `OutputFrequency::Hz0` (which disables CLKOUT).
+    pub async fn acquire<I2C, E>(
+        &self,
+        rtc: &mut PCF85063<I2C>,
+        frequency: OutputFrequency,
+    ) -> Result<ClkoutGuard<'_>, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+    {
+        if frequency == OutputFrequency::Hz0 || self.users.get() == u8::MAX {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        if self.users.get() > 0 && self.frequency.get() != frequency {
+            return Err(Error::Usage(UsageError::InUse));
+        }
+        if rtc.read_clock_output_frequency().await? != frequency {
+            rtc.write_clock_output_frequency(frequency).await?;
+        }
+        self.frequency.set(frequency);
+        self.users.set(self.users.get() + 1);
+        Ok(ClkoutGuard { share: self })
+    }
+
+    /// Disable CLKOUT if no guards are held, returning whether it is disabled.
+    ///
+    /// CLKOUT is enabled at 32768 Hz after power-on, so this also turns that off.
+    pub async fn release_unused<I2C, E>(&self, rtc: &mut PCF85063<I2C>) -> Result<bool, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+    {
+        if self.users.get() > 0 {
+            return Ok(false);
+        }
+        if rtc.read_clock_output_frequency().await? != OutputFrequency::Hz0 {
+            rtc.write_clock_output_frequency(OutputFrequency::Hz0)
+                .await?;
+        }
+        self.frequency.set(OutputFrequency::Hz0);
+        Ok(true)
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+
+    #[test]
+    fn shares_one_frequency() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let share = ClkoutShare::new();
+
+        let first = block_on(share.acquire(&mut rtc, OutputFrequency::Hz1024)).unwrap();
+        let second = block_on(share.acquire(&mut rtc, OutputFrequency::Hz1024)).unwrap();
+        assert_eq!(2, share.users());
+        assert!(matches!(
+            block_on(share.acquire(&mut rtc, OutputFrequency::Hz1)),
+            Err(Error::Usage(UsageError::InUse))
+        ));
+        assert!(matches!(
+            block_on(share.acquire(&mut rtc, OutputFrequency::Hz0)),
+            Err(Error::Usage(UsageError::InvalidInputData))
+        ));
+        assert_eq!(
+            OutputFrequency::Hz1024,
+            block_on(rtc.read_clock_output_frequency()).unwrap()
+        );
+        drop((first, second));
+    }
+
+    #[test]
+    fn last_release_disables_clkout() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let share = ClkoutShare::new();
+
+        let first = block_on(share.acquire(&mut rtc, OutputFrequency::Hz1024)).unwrap();
+        let second = block_on(share.acquire(&mut rtc, OutputFrequency::Hz1024)).unwrap();
+        assert!(!block_on(first.release(&mut rtc)).unwrap());
+        assert_eq!(
+            OutputFrequency::Hz1024,
+            block_on(rtc.read_clock_output_frequency()).unwrap()
+        );
+        assert!(block_on(second.release(&mut rtc)).unwrap());
+        assert_eq!(0, share.users());
+        assert_eq!(
+            OutputFrequency::Hz0,
+            block_on(rtc.read_clock_output_frequency()).unwrap()
+        );
+
+        // a dropped guard leaves CLKOUT running until release_unused
+        let guard = block_on(share.acquire(&mut rtc, OutputFrequency::Hz1)).unwrap();
+        drop(guard);
+        assert_eq!(
+            OutputFrequency::Hz1,
+            block_on(rtc.read_clock_output_frequency()).unwrap()
+        );
+        assert!(block_on(share.release_unused(&mut rtc)).unwrap());
+        assert_eq!(
+            OutputFrequency::Hz0,
+            block_on(rtc.read_clock_output_frequency()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Encoding and decoding of register contents, free of any I/O.
+//!
+//! The driver only moves bytes over the bus and leaves their meaning to the functions here, so
+//! blocking front-ends and host tooling, e.g. decoding a logged bus trace, can share them. All
+//! functions take and return plain register values in address order.
+
+use super::diagnostics::Diagnostics;
+use super::status::ChipStatus;
+use super::units::OutOfRange;
+use super::{bcd, BitFlags, DataError, OffsetMode, RawDateTime, Register, RegisterBytes};
+use time::{PrimitiveDateTime, Time};
+
+/// Offset register mode bit
+const OFFSET_MODE: u8 = 0b1000_0000;
+
+/// Encode `datetime` into the time and date registers, with year counter 0 at `base_year`.
+///
+/// Fails if the year is not within the hundred years starting at the base year.
+pub fn encode_datetime(
+    datetime: &PrimitiveDateTime,
+    base_year: i32,
+) -> Result<[u8; 7], OutOfRange> {
+    RawDateTime::from_datetime(datetime, base_year).map(Into::into)
+}
+
+/// Decode the time and date registers, with year counter 0 at `base_year`.
+///
+/// Returns a `DataError::InvalidRegisters` holding `data` for invalid digits or out of range
+/// values, a `DataError::LeapYearMismatch` for a February 29 the chip counted in a common
+/// year, and with `check_weekday` a `DataError::WeekdayMismatch` if the weekday register
+/// disagrees with the date.
+pub fn decode_datetime(
+    data: [u8; 7],
+    base_year: i32,
+    check_weekday: bool,
+) -> Result<PrimitiveDateTime, DataError> {
+    let invalid = || DataError::InvalidRegisters(RegisterBytes::new(Register::SECONDS, &data));
+    let raw = RawDateTime::try_from(data).map_err(|_| invalid())?;
+    if raw.month == 2 && raw.day == 29 && !time::util::is_leap_year(base_year + raw.year as i32) {
+        return Err(DataError::LeapYearMismatch);
+    }
+    let datetime = raw.to_datetime(base_year).map_err(|_| invalid())?;
+    if check_weekday && raw.weekday != datetime.weekday().number_days_from_sunday() {
+        return Err(DataError::WeekdayMismatch);
+    }
+    Ok(datetime)
+}
+
+/// Decode the time and date registers without checking the digits and ranges, see
+/// [`RawDateTime::from_bytes_unchecked`].
+pub fn decode_datetime_unchecked(
+    data: [u8; 7],
+    base_year: i32,
+) -> Result<PrimitiveDateTime, DataError> {
+    RawDateTime::from_bytes_unchecked(data)
+        .to_datetime(base_year)
+        .map_err(|_| DataError::InvalidRegisters(RegisterBytes::new(Register::SECONDS, &data)))
+}
+
+/// Encode an alarm register; the AE bit is set to disable it.
+pub const fn encode_alarm_field(value: u8, enabled: bool) -> u8 {
+    let ae = if enabled { 0 } else { BitFlags::AE };
+    bcd::encode(value) | ae
+}
+
+/// Decode an alarm register value, `None` if its AE bit marks it as disabled.
+pub const fn decode_alarm_field(data: u8) -> Option<u8> {
+    if data & BitFlags::AE != 0 {
+        None
+    } else {
+        Some(bcd::decode(data))
+    }
+}
+
+/// The five alarm registers for an alarm at `at`: seconds, minutes, hours and day of the month
+/// enabled, the weekday disabled.
+pub const fn encode_alarm_at(at: &PrimitiveDateTime) -> [u8; 5] {
+    [
+        bcd::encode(at.second()),
+        bcd::encode(at.minute()),
+        bcd::encode(at.hour()),
+        bcd::encode(at.day()),
+        BitFlags::AE,
+    ]
+}
+
+/// The alarm seconds, minutes and hours registers set to `time`, keeping the AE bits of
+/// `current`.
+pub fn encode_alarm_time(time: Time, current: [u8; 3]) -> [u8; 3] {
+    let values = [time.second(), time.minute(), time.hour()];
+    core::array::from_fn(|i| (current[i] & BitFlags::AE) | bcd::encode(values[i]))
+}
+
+/// Decode the alarm seconds, minutes and hours registers, regardless of their AE bits.
+pub fn decode_alarm_time(data: [u8; 3]) -> Result<Time, DataError> {
+    let [seconds, minutes, hours] = data.map(|value| bcd::decode_masked(value, !BitFlags::AE));
+    Time::from_hms(hours, minutes, seconds)
+        .map_err(|_| DataError::InvalidRegisters(RegisterBytes::new(Register::SECOND_ALARM, &data)))
+}
+
+/// The five alarm registers with all alarms disabled, keeping their values.
+pub fn disable_alarms(data: [u8; 5]) -> [u8; 5] {
+    data.map(|value| value | BitFlags::AE)
+}
+
+/// Control 2 with the alarm flag cleared and the alarm interrupt enabled, leaving the timer
+/// flag alone.
+pub const fn arm_alarm_interrupt(control_2: u8) -> u8 {
+    // flags are cleared by writing 0, writing 1 leaves them unchanged
+    (control_2 | BitFlags::AIE | BitFlags::TF) & !BitFlags::AF
+}
+
+/// Control 2 with `flags` (any of the alarm and timer flags) cleared, leaving the other flag
+/// alone.
+pub const fn clear_flags(control_2: u8, flags: u8) -> u8 {
+    // flags are cleared by writing 0, writing 1 leaves them unchanged
+    (control_2 | BitFlags::AF | BitFlags::TF) & !flags
+}
+
+/// Encode the offset register, `None` if the offset is outside [-64 to 63].
+pub const fn encode_offset(mode: OffsetMode, offset: i8) -> Option<u8> {
+    if offset < -64 || offset > 63 {
+        return None;
+    }
+    let mode = match mode {
+        OffsetMode::Normal => 0,
+        OffsetMode::Coarse => OFFSET_MODE,
+    };
+    Some(mode | (offset as u8 & 0x7f))
+}
+
+/// Decode the offset register into the correction mode and the signed offset.
+pub const fn decode_offset(value: u8) -> (OffsetMode, i8) {
+    let mode = if value & OFFSET_MODE != 0 {
+        OffsetMode::Coarse
+    } else {
+        OffsetMode::Normal
+    };
+    // sign-extend the 7-bit two's complement value
+    (mode, ((value << 1) as i8) >> 1)
+}
+
+/// Decode registers control 1 up to and including seconds into the chip state.
+pub fn decode_status(registers: [u8; 5]) -> ChipStatus {
+    ChipStatus::decode(registers)
+}
+
+/// Decode registers control 1 up to and including seconds, and timer mode, into the chip
+/// configuration and state.
+pub fn decode_diagnostics(registers: [u8; 5], timer_mode: u8) -> Diagnostics {
+    Diagnostics::decode(registers, timer_mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{datetime, time};
+
+    #[test]
+    fn can_decode_datetime() {
+        let data = encode_datetime(&datetime!(2024-02-29 23:59:59), 2000).unwrap();
+        assert_eq!(
+            Ok(datetime!(2024-02-29 23:59:59)),
+            decode_datetime(data, 2000, true)
+        );
+        assert_eq!(
+            Err(DataError::LeapYearMismatch),
+            decode_datetime(data, 2001, false)
+        );
+
+        let mut data = data;
+        data[4] = 0x00;
+        assert_eq!(
+            Err(DataError::WeekdayMismatch),
+            decode_datetime(data, 2000, true)
+        );
+        data[2] = 0x24;
+        assert_eq!(
+            Err(DataError::InvalidRegisters(RegisterBytes::new(
+                Register::SECONDS,
+                &data
+            ))),
+            decode_datetime(data, 2000, false)
+        );
+        assert_eq!(
+            Err(OutOfRange),
+            encode_datetime(&datetime!(2100-01-01 0:00), 2000)
+        );
+    }
+
+    #[test]
+    fn alarm_fields_round_trip() {
+        assert_eq!(Some(45), decode_alarm_field(0x45));
+        assert_eq!(None, decode_alarm_field(0x80 | 0x45));
+        for value in 0..=59 {
+            for enabled in [false, true] {
+                let decoded = decode_alarm_field(encode_alarm_field(value, enabled));
+                assert_eq!(enabled.then_some(value), decoded);
+            }
+        }
+
+        let data = encode_alarm_time(time!(12:34:56), [0x80, 0x00, 0x80]);
+        assert_eq!([0xd6, 0x34, 0x92], data);
+        assert_eq!(Ok(time!(12:34:56)), decode_alarm_time(data));
+        assert_eq!(
+            [0x56, 0x34, 0x12, 0x29, 0x80],
+            encode_alarm_at(&datetime!(2024-02-29 12:34:56))
+        );
+        assert_eq!(
+            [0x80, 0xb5, 0x80, 0x80, 0x86],
+            disable_alarms([0, 0x35, 0x80, 0, 6])
+        );
+    }
+
+    #[test]
+    fn offsets_round_trip() {
+        for offset in -64..=63 {
+            for mode in [OffsetMode::Normal, OffsetMode::Coarse] {
+                let value = encode_offset(mode, offset).unwrap();
+                assert_eq!((mode, offset), decode_offset(value));
+            }
+        }
+        assert_eq!(None, encode_offset(OffsetMode::Normal, 64));
+        assert_eq!(None, encode_offset(OffsetMode::Coarse, -65));
+    }
+
+    #[test]
+    fn arming_and_clearing_leave_other_flag() {
+        assert_eq!(0b1000_1000, arm_alarm_interrupt(0b0100_0000));
+        assert_eq!(0b1000_1001, arm_alarm_interrupt(0b1100_1001));
+        assert_eq!(0b1100_0000, clear_flags(0b1000_1000, BitFlags::TF));
+        assert_eq!(0b0100_0000, clear_flags(0b0000_0000, BitFlags::TF));
+    }
+}
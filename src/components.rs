@@ -0,0 +1,110 @@
+//! Setters for individual time and date registers, enabled with the `component-setters` feature.
+//!
+//! The datasheet recommends setting all time and date registers in one go: the chip keeps
+//! counting between separate writes, so e.g. setting the minutes just before the hours roll over
+//! leaves an hour-old time. Clock-only products that adjust a single field from a user interface
+//! can accept that risk, and use these setters instead of writing a full date and time.
+
+use super::units::{check, DayOfMonth, Hours, Minutes, Seconds};
+use super::{bcd, Error, Register, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::Month;
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Set the seconds [0-59]. This also clears the oscillator stop flag.
+    pub async fn set_seconds(&mut self, seconds: impl TryInto<Seconds>) -> Result<(), Error<E>> {
+        self.set_component(
+            Register::SECONDS,
+            check::<Seconds, E>(seconds)?.get(),
+            0..=59,
+        )
+        .await
+    }
+
+    /// Set the minutes [0-59].
+    pub async fn set_minutes(&mut self, minutes: impl TryInto<Minutes>) -> Result<(), Error<E>> {
+        self.set_component(
+            Register::MINUTES,
+            check::<Minutes, E>(minutes)?.get(),
+            0..=59,
+        )
+        .await
+    }
+
+    /// Set the hours [0-23].
+    pub async fn set_hours(&mut self, hours: impl TryInto<Hours>) -> Result<(), Error<E>> {
+        self.set_component(Register::HOURS, check::<Hours, E>(hours)?.get(), 0..=23)
+            .await
+    }
+
+    /// Set the day of the month [1-31]; it is not checked against the month.
+    pub async fn set_day(&mut self, day: impl TryInto<DayOfMonth>) -> Result<(), Error<E>> {
+        self.set_component(Register::DAYS, check::<DayOfMonth, E>(day)?.get(), 1..=31)
+            .await
+    }
+
+    /// Set the month.
+    pub async fn set_month(&mut self, month: Month) -> Result<(), Error<E>> {
+        self.set_component(Register::MONTHS, month.into(), 1..=12)
+            .await
+    }
+
+    /// Set the year, within the hundred years starting at the base year.
+    pub async fn set_year(&mut self, year: i32) -> Result<(), Error<E>> {
+        let year = year - self.base_year;
+        if !(0..=99).contains(&year) {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        self.set_component(Register::YEARS, year as u8, 0..=99)
+            .await
+    }
+
+    /// Write `value` to `register` if it is in `range`.
+    async fn set_component(
+        &mut self,
+        register: Register,
+        value: u8,
+        range: core::ops::RangeInclusive<u8>,
+    ) -> Result<(), Error<E>> {
+        if !range.contains(&value) {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        self.write_register(register, bcd::encode(value)).await
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use time::macros::datetime;
+
+    #[test]
+    fn sets_single_fields() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let now = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 23:59:59)).await?;
+            rtc.set_minutes(30u8).await?;
+            rtc.set_hours(7u8).await?;
+            rtc.set_month(Month::March).await?;
+            rtc.set_year(2031).await?;
+            rtc.get_datetime().await
+        })
+        .unwrap();
+        assert_eq!(datetime!(2031-03-29 07:30:59), now);
+
+        assert!(matches!(
+            block_on(rtc.set_minutes(60u8)),
+            Err(Error::Usage(UsageError::InvalidInputData))
+        ));
+        assert!(matches!(
+            block_on(rtc.set_year(2100)),
+            Err(Error::Usage(UsageError::InvalidInputData))
+        ));
+        assert_eq!(Some(now), simulator.datetime());
+    }
+}
@@ -0,0 +1,352 @@
+//! Alarm, configuration and calibration values with a compact binary form, for keeping them in
+//! an EEPROM or flash page without serde.
+//!
+//! Each encoding starts with a tag byte telling the kinds apart and ends with a checksum, so
+//! `from_bytes` rejects blank, corrupted or mixed-up storage. The layouts are stable: bytes
+//! written by one version of this crate are read by later ones.
+//!
+//! ```ignore
+//! let config = rtc.get_config().await?;
+//! eeprom.write(CONFIG_ADDRESS, &config.to_bytes())?;
+//! // after a power loss
+//! if let Some(config) = Config::from_bytes(&saved) {
+//!     rtc.set_config(&config).await?;
+//! }
+//! ```
+
+use super::persist::crc8;
+use super::{
+    codec, BitFlags, CapacitorSelection, Error, HourMode, OffsetMode, OutputFrequency, Register,
+    UsageError, PCF85063,
+};
+use embedded_hal_async::i2c::I2c;
+
+/// Number of bytes taken by an encoded [`Alarm`].
+pub const ALARM_LEN: usize = 8;
+/// Number of bytes taken by an encoded [`Config`].
+pub const CONFIG_LEN: usize = 4;
+/// Number of bytes taken by an encoded [`Calibration`].
+pub const CALIBRATION_LEN: usize = 3;
+
+const ALARM_TAG: u8 = 0xa1;
+const CONFIG_TAG: u8 = 0xc0;
+const CALIBRATION_TAG: u8 = 0xca;
+
+/// Bits of control 1 and control 2 held by a [`Config`]
+const CONTROL_1_BITS: u8 = BitFlags::CAP_SEL | BitFlags::MODE_12_24;
+const CONTROL_2_BITS: u8 = BitFlags::AIE | BitFlags::MI | BitFlags::HMI | BitFlags::COF;
+
+/// The alarm settings: each field matches when set, and is disabled when `None`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Alarm {
+    pub seconds: Option<u8>,
+    pub minutes: Option<u8>,
+    pub hours: Option<u8>,
+    pub day: Option<u8>,
+    /// Day of the week, 0 is Sunday.
+    pub weekday: Option<u8>,
+    /// The alarm asserts the INT pin.
+    pub interrupt: bool,
+}
+
+impl Alarm {
+    /// The alarm registers, seconds up to and including weekday; `None` if a field is out of
+    /// range.
+    fn to_registers(self) -> Option<[u8; 5]> {
+        let fields = [
+            (self.seconds, 0..=59),
+            (self.minutes, 0..=59),
+            (self.hours, 0..=23),
+            (self.day, 1..=31),
+            (self.weekday, 0..=6),
+        ];
+        let mut registers = [0; 5];
+        for (register, (field, range)) in registers.iter_mut().zip(fields) {
+            *register = match field {
+                Some(value) if range.contains(&value) => codec::encode_alarm_field(value, true),
+                Some(_) => return None,
+                None => BitFlags::AE,
+            };
+        }
+        Some(registers)
+    }
+
+    fn from_registers(registers: [u8; 5], interrupt: bool) -> Self {
+        let [seconds, minutes, hours, day, weekday] = registers.map(codec::decode_alarm_field);
+        Alarm {
+            seconds,
+            minutes,
+            hours,
+            day,
+            weekday,
+            interrupt,
+        }
+    }
+
+    /// Encode as bytes with a checksum; `None` if a field is out of range.
+    pub fn to_bytes(&self) -> Option<[u8; ALARM_LEN]> {
+        let mut bytes = [0; ALARM_LEN];
+        bytes[0] = ALARM_TAG;
+        bytes[1..6].copy_from_slice(&self.to_registers()?);
+        bytes[6] = self.interrupt as u8;
+        bytes[7] = crc8(&bytes[..7]);
+        Some(bytes)
+    }
+
+    /// Decode bytes, `None` if they are not a valid encoded alarm.
+    pub fn from_bytes(bytes: &[u8; ALARM_LEN]) -> Option<Self> {
+        if bytes[0] != ALARM_TAG || bytes[6] > 1 || bytes[7] != crc8(&bytes[..7]) {
+            return None;
+        }
+        let mut registers = [0; 5];
+        registers.copy_from_slice(&bytes[1..6]);
+        // only registers this encoding produces survive the round trip, e.g. no invalid BCD
+        let alarm = Self::from_registers(registers, bytes[6] == 1);
+        (alarm.to_registers() == Some(registers)).then_some(alarm)
+    }
+}
+
+/// The chip configuration kept in control 1 and control 2.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    pub hour_mode: HourMode,
+    pub capacitor: CapacitorSelection,
+    pub clock_output: OutputFrequency,
+    pub alarm_interrupt: bool,
+    pub minute_interrupt: bool,
+    pub half_minute_interrupt: bool,
+}
+
+impl Config {
+    /// The configuration bits of control 1 and control 2.
+    fn to_registers(self) -> [u8; 2] {
+        let flag = |on: bool, mask: u8| if on { mask } else { 0 };
+        [
+            flag(self.hour_mode == HourMode::H12, BitFlags::MODE_12_24)
+                | flag(
+                    self.capacitor == CapacitorSelection::Pf12_5,
+                    BitFlags::CAP_SEL,
+                ),
+            flag(self.alarm_interrupt, BitFlags::AIE)
+                | flag(self.minute_interrupt, BitFlags::MI)
+                | flag(self.half_minute_interrupt, BitFlags::HMI)
+                | self.clock_output.bits(),
+        ]
+    }
+
+    fn from_registers([control_1, control_2]: [u8; 2]) -> Self {
+        let flag = |value: u8, mask: u8| value & mask != 0;
+        Config {
+            hour_mode: if flag(control_1, BitFlags::MODE_12_24) {
+                HourMode::H12
+            } else {
+                HourMode::H24
+            },
+            capacitor: if flag(control_1, BitFlags::CAP_SEL) {
+                CapacitorSelection::Pf12_5
+            } else {
+                CapacitorSelection::Pf7
+            },
+            clock_output: OutputFrequency::from_bits(control_2),
+            alarm_interrupt: flag(control_2, BitFlags::AIE),
+            minute_interrupt: flag(control_2, BitFlags::MI),
+            half_minute_interrupt: flag(control_2, BitFlags::HMI),
+        }
+    }
+
+    /// Encode as bytes with a checksum.
+    pub fn to_bytes(&self) -> [u8; CONFIG_LEN] {
+        let mut bytes = [0; CONFIG_LEN];
+        bytes[0] = CONFIG_TAG;
+        bytes[1..3].copy_from_slice(&self.to_registers());
+        bytes[3] = crc8(&bytes[..3]);
+        bytes
+    }
+
+    /// Decode bytes, `None` if they are not a valid encoded configuration.
+    pub fn from_bytes(bytes: &[u8; CONFIG_LEN]) -> Option<Self> {
+        let stray_bits = bytes[1] & !CONTROL_1_BITS != 0 || bytes[2] & !CONTROL_2_BITS != 0;
+        if bytes[0] != CONFIG_TAG || stray_bits || bytes[3] != crc8(&bytes[..3]) {
+            return None;
+        }
+        Some(Self::from_registers([bytes[1], bytes[2]]))
+    }
+}
+
+/// The offset correction, as found by calibrating the oscillator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Calibration {
+    pub mode: OffsetMode,
+    /// Signed offset [-64 to 63], in steps of [`OffsetMode::ppm_per_step`].
+    pub offset: i8,
+}
+
+impl Calibration {
+    /// Encode as bytes with a checksum; `None` if the offset is out of range.
+    pub fn to_bytes(&self) -> Option<[u8; CALIBRATION_LEN]> {
+        let mut bytes = [0; CALIBRATION_LEN];
+        bytes[0] = CALIBRATION_TAG;
+        bytes[1] = codec::encode_offset(self.mode, self.offset)?;
+        bytes[2] = crc8(&bytes[..2]);
+        Some(bytes)
+    }
+
+    /// Decode bytes, `None` if they are not a valid encoded calibration.
+    pub fn from_bytes(bytes: &[u8; CALIBRATION_LEN]) -> Option<Self> {
+        if bytes[0] != CALIBRATION_TAG || bytes[2] != crc8(&bytes[..2]) {
+            return None;
+        }
+        let (mode, offset) = codec::decode_offset(bytes[1]);
+        Some(Calibration { mode, offset })
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the alarm settings, and whether the alarm interrupt is enabled.
+    pub async fn get_alarm(&mut self) -> Result<Alarm, Error<E>> {
+        let mut registers = [0; 5];
+        self.read_bytes(Register::SECOND_ALARM, &mut registers)
+            .await?;
+        let interrupt = self.is_alarm_interrupt_enabled().await?;
+        Ok(Alarm::from_registers(registers, interrupt))
+    }
+
+    /// Write the alarm settings, and enable or disable the alarm interrupt.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if a field is out of range.
+    pub async fn set_alarm(&mut self, alarm: &Alarm) -> Result<(), Error<E>> {
+        let registers = alarm
+            .to_registers()
+            .ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        let mut payload = [Register::SECOND_ALARM as u8; 6];
+        payload[1..].copy_from_slice(&registers);
+        self.write_bytes(&payload).await?;
+        self.enable_alarm_interrupt(alarm.interrupt).await
+    }
+
+    /// Read the configuration from control 1 and control 2.
+    pub async fn get_config(&mut self) -> Result<Config, Error<E>> {
+        let mut registers = [0; 2];
+        self.read_bytes(Register::CONTROL_1, &mut registers).await?;
+        Ok(Config::from_registers(registers))
+    }
+
+    /// Write the configuration in one burst, leaving the other bits and the flags unchanged.
+    ///
+    /// The driver reads and writes the hours in 24-hour format, so this returns a
+    /// 'UsageError::InvalidInputData' for `HourMode::H12`, without writing anything.
+    pub async fn set_config(&mut self, config: &Config) -> Result<(), Error<E>> {
+        if config.hour_mode == HourMode::H12 {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        self.setup().config(config).commit().await
+    }
+
+    /// Read the offset correction.
+    pub async fn get_calibration(&mut self) -> Result<Calibration, Error<E>> {
+        let (mode, offset) = self.get_offset().await?;
+        Ok(Calibration { mode, offset })
+    }
+
+    /// Write the offset correction.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the offset is out of range.
+    pub async fn set_calibration(&mut self, calibration: &Calibration) -> Result<(), Error<E>> {
+        self.set_offset(calibration.mode, calibration.offset).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alarm_round_trips() {
+        let alarm = Alarm {
+            seconds: Some(0),
+            minutes: Some(30),
+            hours: Some(7),
+            weekday: Some(1),
+            interrupt: true,
+            ..Alarm::default()
+        };
+        let bytes = alarm.to_bytes().unwrap();
+        assert_eq!([0xa1, 0x00, 0x30, 0x07, 0x80, 0x01, 0x01], bytes[..7]);
+        assert_eq!(Some(alarm), Alarm::from_bytes(&bytes));
+
+        assert_eq!(
+            None,
+            Alarm {
+                hours: Some(24),
+                ..alarm
+            }
+            .to_bytes()
+        );
+        assert_eq!(
+            None,
+            Alarm {
+                day: Some(0),
+                ..alarm
+            }
+            .to_bytes()
+        );
+        let mut corrupted = bytes;
+        corrupted[3] = 0x0a;
+        corrupted[7] = crc8(&corrupted[..7]);
+        assert_eq!(None, Alarm::from_bytes(&corrupted));
+    }
+
+    #[test]
+    fn config_and_calibration_round_trip() {
+        let config = Config {
+            hour_mode: HourMode::H12,
+            clock_output: OutputFrequency::Hz1,
+            half_minute_interrupt: true,
+            ..Config::default()
+        };
+        let bytes = config.to_bytes();
+        assert_eq!([0xc0, 0x02, 0x16], bytes[..3]);
+        assert_eq!(Some(config), Config::from_bytes(&bytes));
+        assert_eq!(None, Config::from_bytes(&[0xc0, 0x22, 0x16, 0x00]));
+        assert_eq!(None, Alarm::from_bytes(&[0xff; ALARM_LEN]));
+
+        let calibration = Calibration {
+            mode: OffsetMode::Coarse,
+            offset: -3,
+        };
+        let bytes = calibration.to_bytes().unwrap();
+        assert_eq!([0xca, 0xfd], bytes[..2]);
+        assert_eq!(Some(calibration), Calibration::from_bytes(&bytes));
+        assert_eq!(None, Calibration::from_bytes(&[0xc0, 0xfd, bytes[2]]));
+        let out_of_range = Calibration {
+            offset: 64,
+            ..calibration
+        };
+        assert_eq!(None, out_of_range.to_bytes());
+    }
+
+    #[cfg(feature = "simulator")]
+    #[test]
+    fn rejects_12_hour_config() {
+        use crate::simulator::{block_on, Simulator};
+
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let config = Config {
+            hour_mode: HourMode::H12,
+            minute_interrupt: true,
+            ..Config::default()
+        };
+        assert!(matches!(
+            block_on(rtc.set_config(&config)),
+            Err(Error::Usage(UsageError::InvalidInputData))
+        ));
+        assert_eq!([0, 0], simulator.registers()[..2]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Detecting unexpected changes of the configuration with a checksum kept in the RAM byte.
+//!
+//! After configuring the chip, `store_config_checksum` saves a checksum of both control
+//! registers and the offset register in a field of the RAM byte. At startup, `verify_config`
+//! tells whether they still hold those values, or were corrupted or written by other software
+//! since. The alarm and timer flags are left out, as they change in normal operation.
+//!
+//! The checksum is cut down to the width of the field, so the other bits of the RAM byte stay
+//! free for e.g. a boot counter; a narrow field misses more changes (one in 2^width).
+
+use super::persist::crc8;
+use super::ram::{extract_field, is_field_mask};
+use super::{BitFlags, Error, Register, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+
+const SEED: u8 = 0x63;
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Save a checksum of the current configuration in the field of the RAM byte selected by
+    /// `mask`.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the mask is empty or not contiguous.
+    pub async fn store_config_checksum(&mut self, mask: u8) -> Result<(), Error<E>> {
+        if !is_field_mask(mask) {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        let config = self.read_config().await?;
+        self.set_ram_bits(mask, field_checksum(&config, mask)).await
+    }
+
+    /// Check the configuration against the checksum saved by `store_config_checksum` with the
+    /// same `mask`, returning `false` if it changed.
+    pub async fn verify_config(&mut self, mask: u8) -> Result<bool, Error<E>> {
+        if !is_field_mask(mask) {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        let mut data = [0; 4];
+        self.read_bytes(Register::CONTROL_1, &mut data).await?;
+        let [control_1, control_2, offset, ram] = data;
+        Ok(field_checksum(&[control_1, control_2, offset], mask) == extract_field(ram, mask))
+    }
+
+    /// Read both control registers and the offset register.
+    async fn read_config(&mut self) -> Result<[u8; 3], Error<E>> {
+        let mut config = [0; 3];
+        self.read_bytes(Register::CONTROL_1, &mut config).await?;
+        Ok(config)
+    }
+}
+
+/// The checksum cut down to the width of the field selected by `mask`.
+fn field_checksum(config: &[u8; 3], mask: u8) -> u8 {
+    config_checksum(config) & (mask >> mask.trailing_zeros())
+}
+
+/// Checksum of the control and offset registers, ignoring the flags.
+///
+/// The checksum is seeded, so the all-zero power-on state does not pass as valid.
+fn config_checksum(config: &[u8; 3]) -> u8 {
+    let [control_1, control_2, offset] = *config;
+    crc8(&[
+        SEED,
+        control_1,
+        control_2 & !(BitFlags::AF | BitFlags::TF),
+        offset,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_ignores_flags() {
+        let checksum = config_checksum(&[0b0000_0010, BitFlags::AIE, 0x05]);
+        assert_eq!(
+            checksum,
+            config_checksum(&[
+                0b0000_0010,
+                BitFlags::AIE | BitFlags::AF | BitFlags::TF,
+                0x05
+            ])
+        );
+        assert_ne!(
+            checksum,
+            config_checksum(&[0b0000_0010, BitFlags::AIE, 0x06])
+        );
+        assert_ne!(
+            checksum,
+            config_checksum(&[0b0000_0011, BitFlags::AIE, 0x05])
+        );
+    }
+
+    #[test]
+    fn power_on_state_does_not_verify() {
+        assert_ne!(0, config_checksum(&[0, 0, 0]));
+    }
+
+    #[cfg(feature = "simulator")]
+    #[test]
+    fn shares_ram_byte_with_boot_counter() {
+        use crate::simulator::{block_on, Simulator};
+
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let count = block_on(async {
+            rtc.increment_boot_counter(0x0f).await?;
+            rtc.store_config_checksum(0xf0).await?;
+            assert!(rtc.verify_config(0xf0).await?);
+            assert_eq!(2, rtc.increment_boot_counter(0x0f).await?);
+            assert!(rtc.verify_config(0xf0).await?);
+
+            rtc.write_register(Register::OFFSET, 0x05).await?;
+            assert!(!rtc.verify_config(0xf0).await?);
+            rtc.ram_bits(0x0f).await
+        })
+        .unwrap();
+        assert_eq!(2, count);
+        assert!(matches!(
+            block_on(rtc.store_config_checksum(0b0101_0000)),
+            Err(Error::Usage(UsageError::InvalidInputData))
+        ));
+    }
+}
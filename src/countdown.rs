@@ -0,0 +1,156 @@
+//! A blocking, `nb`-style countdown over the hardware timer, enabled with the `nb` feature.
+//!
+//! The rest of the driver is async; this small wrapper talks to the timer registers through a
+//! blocking `embedded_hal::i2c::I2c` bus, so the RTC timer fits into nb-based superloop
+//! firmware:
+//!
+//! ```ignore
+//! let mut countdown = pcf85063a::CountDown::new(i2c);
+//! countdown.start(Duration::from_secs(5))?;
+//! loop {
+//!     if let Ok(()) = countdown.wait() {
+//!         // every five seconds
+//!     }
+//!     // other work
+//! }
+//! ```
+
+use super::block_on::block_on;
+use super::timer::timer_setting;
+use super::{codec, BitFlags, Error, Register, UsageError, PCF85063};
+use core::time::Duration;
+use embedded_hal::i2c::I2c;
+use embedded_hal_async::i2c::{self as async_i2c, ErrorType, Operation, SevenBitAddress};
+
+/// Periodic countdown over the hardware timer, using a blocking I2C bus.
+#[derive(Debug)]
+pub struct CountDown<I2C> {
+    rtc: PCF85063<Blocking<I2C>>,
+}
+
+impl<I2C, E> CountDown<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    pub const fn new(i2c: I2C) -> Self {
+        CountDown {
+            rtc: PCF85063::new(Blocking(i2c)),
+        }
+    }
+
+    /// Destroy the countdown, return I2C bus instance. The timer keeps running.
+    pub fn destroy(self) -> I2C {
+        self.rtc.destroy().0
+    }
+
+    /// Start counting down `period`, repeating until cancelled.
+    ///
+    /// The period is rounded to the nearest tick of the finest timer clock that can count it.
+    /// Will return a 'UsageError::InvalidInputData' if it is zero or above 255 minutes.
+    pub fn start(&mut self, period: Duration) -> Result<(), Error<E>> {
+        let (clock, ticks) =
+            timer_setting(period).ok_or(Error::Usage(UsageError::InvalidInputData))?;
+
+        self.clear_timer_flag()?;
+        let mode = clock.bits() | BitFlags::TE;
+        block_on(
+            self.rtc
+                .write_bytes(&[Register::TIMER_VALUE as u8, ticks, mode]),
+        )
+    }
+
+    /// Check whether the period elapsed, acknowledging it if so.
+    pub fn wait(&mut self) -> nb::Result<(), Error<E>> {
+        if self.read(Register::CONTROL_2)? & BitFlags::TF == 0 {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.clear_timer_flag()?;
+        Ok(())
+    }
+
+    /// Stop the timer.
+    pub fn cancel(&mut self) -> Result<(), Error<E>> {
+        let mode = self.read(Register::TIMER_MODE)?;
+        self.write(Register::TIMER_MODE, mode & !BitFlags::TE)
+    }
+
+    fn clear_timer_flag(&mut self) -> Result<(), Error<E>> {
+        let value = self.read(Register::CONTROL_2)?;
+        self.write(Register::CONTROL_2, codec::clear_flags(value, BitFlags::TF))
+    }
+
+    fn read(&mut self, register: Register) -> Result<u8, Error<E>> {
+        block_on(self.rtc.read_register(register))
+    }
+
+    fn write(&mut self, register: Register, data: u8) -> Result<(), Error<E>> {
+        block_on(self.rtc.write_register(register, data))
+    }
+}
+
+/// A blocking bus as the driver's async bus; its futures complete at once.
+#[derive(Debug)]
+struct Blocking<I2C>(I2C);
+
+impl<I2C: I2c> ErrorType for Blocking<I2C> {
+    type Error = I2C::Error;
+}
+
+impl<I2C: I2c> async_i2c::I2c for Blocking<I2C> {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.0.transaction(address, operations)
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{SimBus, SimError, Simulator};
+
+    /// The simulator's bus as a blocking bus
+    struct BlockingSim<'a>(SimBus<'a>);
+
+    impl ErrorType for BlockingSim<'_> {
+        type Error = SimError;
+    }
+
+    impl I2c for BlockingSim<'_> {
+        fn transaction(
+            &mut self,
+            address: SevenBitAddress,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            block_on(async_i2c::I2c::transaction(
+                &mut self.0,
+                address,
+                operations,
+            ))
+        }
+    }
+
+    #[test]
+    fn counts_down_periodically() {
+        let simulator = Simulator::new();
+        let mut countdown = CountDown::new(BlockingSim(simulator.bus()));
+
+        countdown.start(Duration::from_secs(2)).unwrap();
+        assert!(matches!(countdown.wait(), Err(nb::Error::WouldBlock)));
+        simulator.advance(core::time::Duration::from_millis(1990));
+        assert!(matches!(countdown.wait(), Err(nb::Error::WouldBlock)));
+        simulator.advance(core::time::Duration::from_millis(20));
+        assert!(countdown.wait().is_ok());
+        assert!(matches!(countdown.wait(), Err(nb::Error::WouldBlock)));
+
+        // the timer repeats
+        simulator.advance(core::time::Duration::from_secs(2));
+        assert!(countdown.wait().is_ok());
+
+        countdown.cancel().unwrap();
+        simulator.advance(core::time::Duration::from_secs(4));
+        assert!(matches!(countdown.wait(), Err(nb::Error::WouldBlock)));
+    }
+}
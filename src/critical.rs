@@ -0,0 +1,252 @@
+//! A driver instance shared between the main loop and interrupt handlers, guarded by
+//! `critical-section`, enabled with the `critical-section` feature.
+//!
+//! For firmware without async executor or RTOS: [`CsRtc`] talks to the chip over a blocking
+//! `embedded_hal::i2c::I2c` bus, and holds it in a critical section for the length of each
+//! call, so it can live in a `static` and be used from any context:
+//!
+//! ```ignore
+//! static RTC: CsRtc<I2c0> = CsRtc::new();
+//!
+//! fn main() -> ! {
+//!     RTC.init(i2c);
+//!     RTC.set_datetime(&datetime!(2024-02-29 12:00:00)).unwrap();
+//!     // ...
+//! }
+//!
+//! #[interrupt]
+//! fn EXTI0() {
+//!     if RTC.take_alarm_flag().unwrap_or(false) {
+//!         // the alarm fired
+//!     }
+//! }
+//! ```
+//!
+//! Interrupts stay disabled during the bus transfers, which take some hundreds of microseconds
+//! at 100 kHz. Until `init` is called, all calls return a 'UsageError::NotInitialized'.
+
+use super::{codec, BitFlags, Error, Register, UsageError, DEVICE_ADDRESS};
+use core::cell::RefCell;
+use critical_section::Mutex;
+use embedded_hal::i2c::I2c;
+use time::PrimitiveDateTime;
+
+/// The chip on a blocking bus, shared through critical sections
+#[derive(Debug)]
+pub struct CsRtc<I2C> {
+    i2c: Mutex<RefCell<Option<I2C>>>,
+    base_year: i32,
+}
+
+impl<I2C> Default for CsRtc<I2C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I2C> CsRtc<I2C> {
+    /// An instance without bus yet, with the base year 2000.
+    pub const fn new() -> Self {
+        Self::with_base_year(2000)
+    }
+
+    /// An instance without bus yet, with year counter 0 at `base_year`; `None` if `base_year`
+    /// is not divisible by four, see [`PCF85063::new_with_base_year`](crate::PCF85063::new_with_base_year).
+    pub const fn new_with_base_year(base_year: i32) -> Option<Self> {
+        if base_year % 4 != 0 {
+            return None;
+        }
+        Some(Self::with_base_year(base_year))
+    }
+
+    const fn with_base_year(base_year: i32) -> Self {
+        CsRtc {
+            i2c: Mutex::new(RefCell::new(None)),
+            base_year,
+        }
+    }
+
+    /// Hand over the bus, returning the one held before, if any.
+    pub fn init(&self, i2c: I2C) -> Option<I2C> {
+        critical_section::with(|cs| self.i2c.borrow_ref_mut(cs).replace(i2c))
+    }
+
+    /// Take back the bus, after which calls return a 'UsageError::NotInitialized'.
+    pub fn release(&self) -> Option<I2C> {
+        critical_section::with(|cs| self.i2c.borrow_ref_mut(cs).take())
+    }
+}
+
+impl<I2C, E> CsRtc<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Run `f` on the bus in a critical section.
+    pub fn with_bus<T>(
+        &self,
+        f: impl FnOnce(&mut I2C) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        critical_section::with(|cs| {
+            let mut i2c = self.i2c.borrow_ref_mut(cs);
+            let i2c = i2c
+                .as_mut()
+                .ok_or(Error::Usage(UsageError::NotInitialized))?;
+            f(i2c)
+        })
+    }
+
+    /// Read date and time all at once.
+    pub fn get_datetime(&self) -> Result<PrimitiveDateTime, Error<E>> {
+        let mut data = [0; 7];
+        self.with_bus(|i2c| read(i2c, Register::SECONDS, &mut data))?;
+        codec::decode_datetime(data, self.base_year, false).map_err(Error::Data)
+    }
+
+    /// Set date and time all at once.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the year is not within the hundred
+    /// years starting at the base year.
+    pub fn set_datetime(&self, datetime: &PrimitiveDateTime) -> Result<(), Error<E>> {
+        let data = codec::encode_datetime(datetime, self.base_year)
+            .map_err(|_| Error::Usage(UsageError::InvalidInputData))?;
+        let mut payload = [Register::SECONDS as u8; 8];
+        payload[1..].copy_from_slice(&data);
+        self.with_bus(|i2c| i2c.write(DEVICE_ADDRESS, &payload).map_err(Error::I2C))
+    }
+
+    /// Get the alarm flag (if true, alarm event happened).
+    pub fn get_alarm_flag(&self) -> Result<bool, Error<E>> {
+        Ok(self.read_control_2()? & BitFlags::AF != 0)
+    }
+
+    /// Clear the alarm flag.
+    pub fn clear_alarm_flag(&self) -> Result<(), Error<E>> {
+        self.take_flag(BitFlags::AF).map(|_| ())
+    }
+
+    /// Check and clear the alarm flag in one critical section, returning whether it was set.
+    pub fn take_alarm_flag(&self) -> Result<bool, Error<E>> {
+        self.take_flag(BitFlags::AF)
+    }
+
+    /// Get the timer flag, set by the countdown timer and the (half) minute interrupts.
+    pub fn get_timer_flag(&self) -> Result<bool, Error<E>> {
+        Ok(self.read_control_2()? & BitFlags::TF != 0)
+    }
+
+    /// Clear the timer flag.
+    pub fn clear_timer_flag(&self) -> Result<(), Error<E>> {
+        self.take_flag(BitFlags::TF).map(|_| ())
+    }
+
+    /// Check and clear the timer flag in one critical section, returning whether it was set.
+    pub fn take_timer_flag(&self) -> Result<bool, Error<E>> {
+        self.take_flag(BitFlags::TF)
+    }
+
+    fn read_control_2(&self) -> Result<u8, Error<E>> {
+        let mut data = [0];
+        self.with_bus(|i2c| read(i2c, Register::CONTROL_2, &mut data))?;
+        Ok(data[0])
+    }
+
+    /// Clear `flag` if set, leaving the other flag alone.
+    fn take_flag(&self, flag: u8) -> Result<bool, Error<E>> {
+        self.with_bus(|i2c| {
+            let mut data = [0];
+            read(i2c, Register::CONTROL_2, &mut data)?;
+            if data[0] & flag == 0 {
+                return Ok(false);
+            }
+            let value = codec::clear_flags(data[0], flag);
+            i2c.write(DEVICE_ADDRESS, &[Register::CONTROL_2 as u8, value])
+                .map_err(Error::I2C)?;
+            Ok(true)
+        })
+    }
+}
+
+fn read<I2C: I2c>(
+    i2c: &mut I2C,
+    register: Register,
+    data: &mut [u8],
+) -> Result<(), Error<I2C::Error>> {
+    i2c.write_read(DEVICE_ADDRESS, &[register.into()], data)
+        .map_err(Error::I2C)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal::i2c::{ErrorKind, ErrorType, Operation, SevenBitAddress};
+    use time::macros::datetime;
+
+    /// Registers behind a blocking bus.
+    struct Registers([u8; 18]);
+
+    impl ErrorType for Registers {
+        type Error = ErrorKind;
+    }
+
+    impl I2c for Registers {
+        fn transaction(
+            &mut self,
+            _address: SevenBitAddress,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            let mut pointer = None;
+            for operation in operations {
+                match operation {
+                    Operation::Write(bytes) => {
+                        let start = bytes[0] as usize;
+                        self.0[start..start + bytes.len() - 1].copy_from_slice(&bytes[1..]);
+                        pointer = Some(start);
+                    }
+                    Operation::Read(buffer) => {
+                        let start = pointer.unwrap();
+                        buffer.copy_from_slice(&self.0[start..start + buffer.len()]);
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shares_the_chip() {
+        static RTC: CsRtc<Registers> = CsRtc::new();
+        assert!(matches!(
+            RTC.get_alarm_flag(),
+            Err(Error::Usage(UsageError::NotInitialized))
+        ));
+
+        RTC.init(Registers([0; 18]));
+        RTC.set_datetime(&datetime!(2024-02-29 12:34:56)).unwrap();
+        assert_eq!(datetime!(2024-02-29 12:34:56), RTC.get_datetime().unwrap());
+
+        RTC.with_bus(|i2c| {
+            i2c.0[Register::CONTROL_2 as usize] = BitFlags::AF | BitFlags::TF;
+            Ok(())
+        })
+        .unwrap();
+        assert!(RTC.take_alarm_flag().unwrap());
+        assert!(!RTC.take_alarm_flag().unwrap());
+        assert!(RTC.get_timer_flag().unwrap());
+
+        let registers = RTC.release().unwrap();
+        // writing 1 leaves the timer flag alone on the chip
+        assert_eq!(
+            BitFlags::TF,
+            registers.0[Register::CONTROL_2 as usize] & BitFlags::TF
+        );
+    }
+
+    #[test]
+    fn rejects_base_years_out_of_leap_year_step() {
+        assert!(CsRtc::<Registers>::new_with_base_year(1970).is_none());
+        let rtc = CsRtc::new_with_base_year(1972).unwrap();
+        rtc.init(Registers([0; 18]));
+        rtc.set_datetime(&datetime!(1976-02-29 12:00:00)).unwrap();
+        assert_eq!(datetime!(1976-02-29 12:00:00), rtc.get_datetime().unwrap());
+    }
+}
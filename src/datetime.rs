@@ -1,14 +1,15 @@
 //! All date and time-related functions will be defined here.
 //!
-//! Reading and setting single elements (seconds, hours, months) will NOT be implemented
+//! Reading and setting single elements (seconds, hours, months) is not implemented here,
 //! following the recommendations in the NXP datasheet to set and read all the seven date and time registers in one go.
+//! Setters for single elements are available behind the `component-setters` feature.
 //!
 //! TO DO: As the chip may be used for devices that are clocks only, without the calendar function
 //! a convenient set_time() function could be added (sets only seconds, minutes and hours)
 
-use super::{decode_bcd, encode_bcd, Error, Register, DEVICE_ADDRESS, PCF85063};
+use super::{bcd, codec, DataError, Error, RawDateTime, Register, UsageError, PCF85063};
 use embedded_hal_async::i2c::I2c;
-use time::{Date, PrimitiveDateTime, Time};
+use time::{Duration, PrimitiveDateTime, Time, Weekday};
 
 impl<I2C, E> PCF85063<I2C>
 where
@@ -17,56 +18,294 @@ where
     /// Read date and time all at once.
     pub async fn get_datetime(&mut self) -> Result<PrimitiveDateTime, Error<E>> {
         let mut data = [0; 7];
-        self.i2c
-            .write_read(DEVICE_ADDRESS, &[Register::SECONDS], &mut data)
-            .await
-            .map_err(Error::I2C)?;
-
-        Ok(PrimitiveDateTime::new(
-            Date::from_calendar_date(
-                2000 + decode_bcd(data[6]) as i32,
-                decode_bcd(data[5] & 0x1f).try_into()?,
-                decode_bcd(data[3] & 0x3f),
-            )?,
-            Time::from_hms(
-                decode_bcd(data[2] & 0x3f),
-                decode_bcd(data[1] & 0b0111_1111),
-                decode_bcd(data[0] & 0b0111_1111),
-            )?,
-        ))
+        self.read_bytes(Register::SECONDS, &mut data).await?;
+
+        codec::decode_datetime(data, self.base_year, self.check_weekday).map_err(Error::Data)
+    }
+
+    /// Read date and time all at once, without checking the register values.
+    ///
+    /// A fast path for wake loops that read back what this driver wrote: the BCD digits, the
+    /// ranges and the leap year quirk are not checked. The `time` crate has no unchecked
+    /// constructors, so an impossible date still returns a 'DataError::InvalidRegisters'.
+    pub async fn get_datetime_unchecked(&mut self) -> Result<PrimitiveDateTime, Error<E>> {
+        let mut data = [0; 7];
+        self.read_bytes(Register::SECONDS, &mut data).await?;
+
+        codec::decode_datetime_unchecked(data, self.base_year).map_err(Error::Data)
+    }
+
+    /// Read date and time, `None` if the clock holds no valid time: the oscillator stopped,
+    /// or the registers are out of range.
+    pub(crate) async fn get_valid_datetime(
+        &mut self,
+    ) -> Result<Option<PrimitiveDateTime>, Error<E>> {
+        let mut data = [0; 7];
+        self.read_bytes(Register::SECONDS, &mut data).await?;
+        Ok(RawDateTime::try_from(data)
+            .ok()
+            .filter(|raw| !raw.oscillator_stopped)
+            .and_then(|raw| raw.to_datetime(self.base_year).ok()))
+    }
+
+    /// The first year within the configured hundred-year window where the chip's leap years
+    /// are wrong, if any.
+    ///
+    /// The chip treats every year with a counter divisible by four as a leap year. With the
+    /// default base year 2000 that is right for the whole window, but e.g. with base year 2020
+    /// the chip will count a February 29 in 2100, after which it runs a day behind. Reading
+    /// that day returns a 'DataError::LeapYearMismatch'.
+    pub fn leap_year_mismatch(&self) -> Option<i32> {
+        leap_year_mismatch(self.base_year)
+    }
+
+    /// Refuse `set_datetime` calls that move the clock back by more than `limit`, or allow
+    /// any jump with `None` (the default).
+    ///
+    /// This protects log ordering and certificate checks from a bad time source. Deliberate
+    /// corrections can still be made with [`set_datetime_forced`](Self::set_datetime_forced).
+    pub fn set_backwards_limit(&mut self, limit: Option<Duration>) {
+        self.backwards_limit = limit;
+    }
+
+    /// Make `set_datetime` skip the write when the clock is off by less than `threshold`, or
+    /// always write with `None` (the default).
+    ///
+    /// Every write restarts the sub-second prescaler, so frequent syncs against a time source
+    /// that agrees with the clock would only add phase error. The clock is read in whole
+    /// seconds, so a threshold of a second or less skips only writes of the current second.
+    pub fn set_write_threshold(&mut self, threshold: Option<Duration>) {
+        self.write_threshold = threshold;
+    }
+
+    /// Make `get_datetime` check the weekday register against the date, or ignore it (the
+    /// default).
+    ///
+    /// A mismatch returns a 'DataError::WeekdayMismatch'; it means the time was set only partly,
+    /// e.g. by other firmware, or the registers were corrupted. Leave the check off when the
+    /// weekday is deliberately set apart from the date with `set_weekday`.
+    pub fn set_weekday_check(&mut self, check: bool) {
+        self.check_weekday = check;
     }
 
     /// Set date and time all at once.
-    pub async fn set_datetime(&mut self, datetime: &PrimitiveDateTime) -> Result<(), Error<E>> {
-        let payload = [
-            Register::SECONDS, //first register
-            encode_bcd(datetime.second()),
-            encode_bcd(datetime.minute()),
-            encode_bcd(datetime.hour()),
-            encode_bcd(datetime.day()),
-            encode_bcd(datetime.weekday().number_days_from_sunday()),
-            encode_bcd(datetime.month().into()),
-            encode_bcd((datetime.year() - 2000) as u8),
-        ];
-        self.i2c
-            .write(DEVICE_ADDRESS, &payload)
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the year is not within the hundred
+    /// years starting at the base year, and a 'UsageError::BackwardsJump' if this moves the
+    /// clock back by more than the backwards limit. The limit is not enforced while the clock
+    /// holds no valid time, e.g. after a power loss.
+    ///
+    /// Returns whether the clock was written: with a write threshold set, the write is skipped
+    /// if the clock is already within the threshold of `datetime`.
+    pub async fn set_datetime(&mut self, datetime: &PrimitiveDateTime) -> Result<bool, Error<E>> {
+        if self.backwards_limit.is_some() || self.write_threshold.is_some() {
+            if let Some(current) = self.get_valid_datetime().await? {
+                let jump = *datetime - current;
+                if !needs_write(jump, self.backwards_limit, self.write_threshold)? {
+                    return Ok(false);
+                }
+            }
+        }
+        self.set_datetime_forced(datetime).await?;
+        Ok(true)
+    }
+
+    /// Set date and time all at once, regardless of the backwards limit and write threshold.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the year is not within the hundred years
+    /// starting at the base year.
+    pub async fn set_datetime_forced(
+        &mut self,
+        datetime: &PrimitiveDateTime,
+    ) -> Result<(), Error<E>> {
+        let data = codec::encode_datetime(datetime, self.base_year)
+            .map_err(|_| Error::Usage(UsageError::InvalidInputData))?;
+
+        let mut payload = [Register::SECONDS as u8; 8]; //first register
+        payload[1..].copy_from_slice(&data);
+        self.write_bytes(&payload).await
+    }
+
+    /// Read date and time, let `modify` transform it, and write the result back.
+    ///
+    /// The write is done with the clock stopped, which also resets the sub-second prescaler, so
+    /// the new second starts exactly when the clock is started again. Returns the new date and
+    /// time.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped halfway, the clock may be left stopped, see
+    /// [`with_clock_stopped`](Self::with_clock_stopped). The same holds for
+    /// [`add_duration`](Self::add_duration) and [`sub_duration`](Self::sub_duration).
+    pub async fn modify_datetime(
+        &mut self,
+        modify: impl FnOnce(PrimitiveDateTime) -> PrimitiveDateTime,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        let datetime = modify(self.get_datetime().await?);
+        self.set_datetime_stopped(&datetime).await?;
+        Ok(datetime)
+    }
+
+    /// Shift the date and time by `duration`, which may be negative, and return the new date
+    /// and time.
+    ///
+    /// Will return a 'DataError::ComponentRange' if the result cannot be represented, and a
+    /// 'UsageError::InvalidInputData' if it falls outside the hundred years starting at the base
+    /// year.
+    pub async fn add_duration(
+        &mut self,
+        duration: Duration,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        let datetime = self
+            .get_datetime()
+            .await?
+            .checked_add(duration)
+            .ok_or(Error::Data(DataError::ComponentRange))?;
+        self.set_datetime_stopped(&datetime).await?;
+        Ok(datetime)
+    }
+
+    /// Shift the date and time back by `duration`, see [`add_duration`](Self::add_duration).
+    pub async fn sub_duration(
+        &mut self,
+        duration: Duration,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        let datetime = self
+            .get_datetime()
+            .await?
+            .checked_sub(duration)
+            .ok_or(Error::Data(DataError::ComponentRange))?;
+        self.set_datetime_stopped(&datetime).await?;
+        Ok(datetime)
+    }
+
+    /// Set date and time with the clock stopped, restarting it afterwards.
+    ///
+    /// These are deliberate corrections, so the backwards limit and write threshold do not
+    /// apply.
+    async fn set_datetime_stopped(&mut self, datetime: &PrimitiveDateTime) -> Result<(), Error<E>> {
+        self.with_clock_stopped(async |rtc| rtc.set_datetime_forced(datetime).await)
             .await
-            .map_err(Error::I2C)
     }
 
     /// Set only the time, date remains unchanged.
     ///
-    /// Will return an 'Error::InvalidInputData' if any of the parameters is out of range.
+    /// Will return a 'UsageError::InvalidInputData' if any of the parameters is out of range.
     pub async fn set_time(&mut self, time: &Time) -> Result<(), Error<E>> {
         let payload = [
-            Register::SECONDS, //first register
-            encode_bcd(time.second()),
-            encode_bcd(time.minute()),
-            encode_bcd(time.hour()),
+            Register::SECONDS as u8, //first register
+            bcd::encode(time.second()),
+            bcd::encode(time.minute()),
+            bcd::encode(time.hour()),
         ];
-        self.i2c
-            .write(DEVICE_ADDRESS, &payload)
+        self.write_bytes(&payload).await
+    }
+
+    /// Read the day of the week on its own.
+    pub async fn get_weekday(&mut self) -> Result<Weekday, Error<E>> {
+        let weekday = bcd::decode_masked(self.read_register(Register::WEEKDAYS).await?, 0x07);
+        if weekday > 6 {
+            return Err(Error::Data(DataError::ComponentRange));
+        }
+        Ok(Weekday::Sunday.nth_next(weekday))
+    }
+
+    /// Set the day of the week on its own, for clock-only applications that do not keep the
+    /// calendar. Note that the chip advances the weekday independently of the date; setting it
+    /// inconsistently with the date is not detected.
+    pub async fn set_weekday(&mut self, weekday: Weekday) -> Result<(), Error<E>> {
+        self.write_register(Register::WEEKDAYS, weekday.number_days_from_sunday())
             .await
-            .map_err(Error::I2C)
+    }
+}
+
+/// The first year from `base_year` on where the chip's leap year rule differs from the
+/// Gregorian one, if any.
+fn leap_year_mismatch(base_year: i32) -> Option<i32> {
+    (0..100).find_map(|counter| {
+        let year = base_year + counter;
+        (time::util::is_leap_year(year) != (counter % 4 == 0)).then_some(year)
+    })
+}
+
+/// Whether `set_datetime` moving the clock by `jump` writes, or a 'UsageError::BackwardsJump'
+/// if it moves back by more than `backwards_limit`.
+fn needs_write(
+    jump: Duration,
+    backwards_limit: Option<Duration>,
+    write_threshold: Option<Duration>,
+) -> Result<bool, UsageError> {
+    if backwards_limit.is_some_and(|limit| -jump > limit) {
+        return Err(UsageError::BackwardsJump);
+    }
+    Ok(write_threshold.is_none_or(|threshold| jump.abs() >= threshold))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_leap_year_mismatches() {
+        assert_eq!(None, leap_year_mismatch(2000));
+        assert_eq!(Some(2100), leap_year_mismatch(2020));
+        assert_eq!(Some(1970), leap_year_mismatch(1970));
+        assert_eq!(None, leap_year_mismatch(1904));
+    }
+
+    #[test]
+    fn skips_writes_within_threshold() {
+        let second = Duration::SECOND;
+        let limit = Some(Duration::minutes(1));
+        let threshold = Some(2 * second);
+        assert_eq!(Ok(true), needs_write(second, None, None));
+        assert_eq!(Ok(false), needs_write(second, None, threshold));
+        assert_eq!(Ok(false), needs_write(-second, limit, threshold));
+        assert_eq!(Ok(true), needs_write(-2 * second, limit, threshold));
+        assert_eq!(Ok(true), needs_write(Duration::hours(1), limit, threshold));
+        assert_eq!(
+            Err(UsageError::BackwardsJump),
+            needs_write(-Duration::hours(1), limit, threshold)
+        );
+    }
+
+    #[cfg(feature = "simulator")]
+    #[test]
+    fn corrections_bypass_backwards_limit() {
+        use crate::simulator::{block_on, Simulator};
+        use time::macros::datetime;
+
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        rtc.set_backwards_limit(Some(Duration::minutes(1)));
+        let shifted = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:00)).await?;
+            rtc.sub_duration(Duration::hours(1)).await
+        })
+        .unwrap();
+        assert_eq!(datetime!(2024-02-29 11:00:00), shifted);
+        assert_eq!(Some(shifted), simulator.datetime());
+    }
+
+    #[cfg(feature = "simulator")]
+    #[test]
+    fn reports_skipped_writes() {
+        use crate::simulator::{block_on, Simulator};
+        use time::macros::datetime;
+
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        rtc.set_write_threshold(Some(2 * Duration::SECOND));
+        let (first, again, shifted) = block_on(async {
+            Ok::<_, Error<_>>((
+                rtc.set_datetime(&datetime!(2024-02-29 12:00:00)).await?,
+                rtc.set_datetime(&datetime!(2024-02-29 12:00:01)).await?,
+                rtc.add_duration(Duration::SECOND).await?,
+            ))
+        })
+        .unwrap();
+        assert!(first && !again);
+        assert_eq!(Some(shifted), simulator.datetime());
+        assert_eq!(datetime!(2024-02-29 12:00:01), shifted);
     }
 }
@@ -0,0 +1,135 @@
+//! One-shot decoded view of the chip configuration and state, for field debugging.
+
+use super::{
+    codec, BitFlags, CapacitorSelection, Error, HourMode, OutputFrequency, Register, PCF85063,
+};
+use embedded_hal_async::i2c::I2c;
+
+/// Correction mode of the offset register
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OffsetMode {
+    /// Correction every two hours, 4.34 ppm per step
+    #[default]
+    Normal,
+    /// Correction every four minutes, 4.069 ppm per step
+    Coarse,
+}
+
+impl OffsetMode {
+    /// Correction in ppm per offset step.
+    pub fn ppm_per_step(self) -> f32 {
+        match self {
+            OffsetMode::Normal => 4.34,
+            OffsetMode::Coarse => 4.069,
+        }
+    }
+}
+
+/// Decoded chip state, as returned by [`PCF85063::diagnostics`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Diagnostics {
+    /// The STOP bit is clear.
+    pub running: bool,
+    /// The oscillator stop flag is set: clock integrity is not guaranteed.
+    pub oscillator_stopped: bool,
+    /// External clock test mode is enabled.
+    pub external_test: bool,
+    pub hour_mode: HourMode,
+    pub capacitor: CapacitorSelection,
+    pub clock_output: OutputFrequency,
+    pub correction_interrupt_enabled: bool,
+    pub alarm_interrupt_enabled: bool,
+    pub minute_interrupt_enabled: bool,
+    pub half_minute_interrupt_enabled: bool,
+    pub timer_interrupt_enabled: bool,
+    pub timer_enabled: bool,
+    /// An alarm event happened and was not cleared yet.
+    pub alarm_flag: bool,
+    /// A timer event happened and was not cleared yet.
+    pub timer_flag: bool,
+    pub offset_mode: OffsetMode,
+    /// Signed offset correction, in steps of [`OffsetMode::ppm_per_step`].
+    pub offset: i8,
+}
+
+impl Diagnostics {
+    /// Decode registers control 1 up to and including seconds, and timer mode.
+    pub(crate) fn decode(registers: [u8; 5], timer_mode: u8) -> Self {
+        let [control_1, control_2, offset, _ram, seconds] = registers;
+        let (offset_mode, offset) = codec::decode_offset(offset);
+        let flag = |value: u8, mask: u8| value & mask != 0;
+
+        Diagnostics {
+            running: !flag(control_1, BitFlags::STOP),
+            oscillator_stopped: flag(seconds, BitFlags::OS),
+            external_test: flag(control_1, BitFlags::EXT_TEST),
+            hour_mode: if flag(control_1, BitFlags::MODE_12_24) {
+                HourMode::H12
+            } else {
+                HourMode::H24
+            },
+            capacitor: if flag(control_1, BitFlags::CAP_SEL) {
+                CapacitorSelection::Pf12_5
+            } else {
+                CapacitorSelection::Pf7
+            },
+            clock_output: OutputFrequency::from_bits(control_2),
+            correction_interrupt_enabled: flag(control_1, BitFlags::CIE),
+            alarm_interrupt_enabled: flag(control_2, BitFlags::AIE),
+            minute_interrupt_enabled: flag(control_2, BitFlags::MI),
+            half_minute_interrupt_enabled: flag(control_2, BitFlags::HMI),
+            timer_interrupt_enabled: flag(timer_mode, BitFlags::TIE),
+            timer_enabled: flag(timer_mode, BitFlags::TE),
+            alarm_flag: flag(control_2, BitFlags::AF),
+            timer_flag: flag(control_2, BitFlags::TF),
+            offset_mode,
+            offset,
+        }
+    }
+
+    /// Offset correction in ppm.
+    pub fn offset_ppm(&self) -> f32 {
+        self.offset as f32 * self.offset_mode.ppm_per_step()
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read and decode all interesting chip state at once.
+    pub async fn diagnostics(&mut self) -> Result<Diagnostics, Error<E>> {
+        let mut registers = [0; 5];
+        self.read_bytes(Register::CONTROL_1, &mut registers).await?;
+        let timer_mode = self.read_register(Register::TIMER_MODE).await?;
+
+        Ok(codec::decode_diagnostics(registers, timer_mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_power_on_defaults() {
+        let diagnostics = Diagnostics::decode([0x00, 0x00, 0x00, 0x00, 0x80], 0x18);
+
+        assert!(diagnostics.running);
+        assert!(diagnostics.oscillator_stopped);
+        assert_eq!(HourMode::H24, diagnostics.hour_mode);
+        assert_eq!(OutputFrequency::Hz32768, diagnostics.clock_output);
+        assert!(!diagnostics.timer_enabled);
+        assert_eq!(0, diagnostics.offset);
+    }
+
+    #[test]
+    fn can_decode_negative_offset() {
+        let diagnostics = Diagnostics::decode([0x00, 0x00, 0b1111_1110, 0x00, 0x00], 0x00);
+
+        assert_eq!(OffsetMode::Coarse, diagnostics.offset_mode);
+        assert_eq!(-2, diagnostics.offset);
+    }
+}
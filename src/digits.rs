@@ -0,0 +1,96 @@
+//! Time as separate decimal digits, for seven-segment displays and LED matrices.
+//!
+//! The time registers hold BCD, one digit per nibble, so the digits come straight from the
+//! registers without decoding and splitting the values again:
+//!
+//! ```ignore
+//! let [h1, h0, m1, m0, _, _] = rtc.get_time_digits().await?;
+//! display.write([h1, h0, m1, m0].map(pcf85063a::digits::segments));
+//! ```
+
+use super::{Error, Register, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::Time;
+
+/// Segments `gfedcba` lit for the digits 0-9, bit 0 being segment a.
+pub const SEGMENTS: [u8; 10] = [
+    0b011_1111, 0b000_0110, 0b101_1011, 0b100_1111, 0b110_0110, 0b110_1101, 0b111_1101, 0b000_0111,
+    0b111_1111, 0b110_1111,
+];
+
+/// The seven-segment pattern of a digit [0-9], blank for other values.
+pub const fn segments(digit: u8) -> u8 {
+    if digit < 10 {
+        SEGMENTS[digit as usize]
+    } else {
+        0
+    }
+}
+
+/// Split a BCD byte into its tens and ones digits.
+pub const fn split_bcd(bcd: u8) -> [u8; 2] {
+    [bcd >> 4, bcd & 0xf]
+}
+
+/// Split a decimal value [0-99] into its tens and ones digits.
+pub const fn split(value: u8) -> [u8; 2] {
+    [value / 10 % 10, value % 10]
+}
+
+/// The digits of `time` as HHMMSS.
+pub const fn time_digits(time: Time) -> [u8; 6] {
+    let [h1, h0] = split(time.hour());
+    let [m1, m0] = split(time.minute());
+    let [s1, s0] = split(time.second());
+    [h1, h0, m1, m0, s1, s0]
+}
+
+/// The time registers as BCD, hours, minutes and seconds, with the flags masked off.
+fn time_bcd(registers: [u8; 3]) -> [u8; 3] {
+    let [seconds, minutes, hours] = registers;
+    [hours & 0x3f, minutes & 0x7f, seconds & 0x7f]
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the time as BCD bytes: hours, minutes and seconds, in 24-hour format.
+    ///
+    /// The values are not checked.
+    pub async fn get_time_bcd(&mut self) -> Result<[u8; 3], Error<E>> {
+        let mut data = [0; 3];
+        self.read_bytes(Register::SECONDS, &mut data).await?;
+        Ok(time_bcd(data))
+    }
+
+    /// Read the time as its six digits HHMMSS, in 24-hour format.
+    ///
+    /// The values are not checked.
+    pub async fn get_time_digits(&mut self) -> Result<[u8; 6], Error<E>> {
+        let [hours, minutes, seconds] = self.get_time_bcd().await?.map(split_bcd);
+        Ok([
+            hours[0], hours[1], minutes[0], minutes[1], seconds[0], seconds[1],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::time;
+
+    #[test]
+    fn splits_into_digits() {
+        assert_eq!([2, 3, 0, 5, 5, 9], time_digits(time!(23:05:59)));
+        // oscillator stop flag set
+        let [hours, minutes, seconds] = time_bcd([0b1101_1001, 0x05, 0x23]);
+        assert_eq!(
+            [[2, 3], [0, 5], [5, 9]],
+            [hours, minutes, seconds].map(split_bcd)
+        );
+        assert_eq!([0, 7], split(7));
+        assert_eq!(0b111_1111, segments(8));
+        assert_eq!(0, segments(10));
+    }
+}
@@ -0,0 +1,157 @@
+//! The offset register, and disciplining the clock onto a reference through it.
+//!
+//! Given periodic reference timestamps (e.g. hourly NTP), [`Discipline`] measures how fast the
+//! clock gains or loses against the reference, and trims the offset register a few steps at a
+//! time. The time itself is never stepped, so the clock converges onto the reference smoothly.
+
+use super::{codec, Error, OffsetMode, Register, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{Duration, PrimitiveDateTime};
+
+/// Smallest drift acted upon: the clock resolves whole seconds, so smaller changes of its
+/// error can be read-out noise.
+const MIN_DRIFT: Duration = Duration::seconds(2);
+
+/// Share of the current error that is removed over the next measurement span.
+const PHASE_DIVISOR: i32 = 4;
+
+/// Tracks the clock's error against a reference across calls to `discipline`.
+#[derive(Debug, Clone)]
+pub struct Discipline {
+    max_step: i8,
+    /// Reference time and clock error since the last adjustment
+    baseline: Option<(PrimitiveDateTime, Duration)>,
+}
+
+impl Discipline {
+    /// Create a discipline that changes the offset by at most `max_step` steps [1-63] at a
+    /// time.
+    pub const fn new(max_step: i8) -> Self {
+        Discipline {
+            max_step: if max_step < 1 {
+                1
+            } else if max_step > 63 {
+                63
+            } else {
+                max_step
+            },
+            baseline: None,
+        }
+    }
+
+    /// Feed the clock's `error` (ahead is positive) against `reference`, and return the change
+    /// of the offset in steps of `ppm_per_step`.
+    ///
+    /// Waits until the error has drifted by a couple of seconds since the last adjustment, so
+    /// the longer the interval between references, the sooner the clock is trimmed.
+    pub fn update(
+        &mut self,
+        reference: PrimitiveDateTime,
+        error: Duration,
+        ppm_per_step: f32,
+    ) -> i8 {
+        let Some((since, baseline_error)) = self.baseline else {
+            self.baseline = Some((reference, error));
+            return 0;
+        };
+        let span = (reference - since).whole_nanoseconds();
+        let excess = (error - baseline_error) + error / PHASE_DIVISOR;
+        if span <= 0 || excess.abs() < MIN_DRIFT {
+            return 0;
+        }
+
+        let ppb = excess.whole_nanoseconds() * 1_000_000_000 / span;
+        let ppb_per_step = (ppm_per_step * 1000.0) as i128;
+        // a clock that runs ahead needs a negative correction
+        let steps = -(ppb + ppb.signum() * ppb_per_step / 2) / ppb_per_step;
+        let steps = steps.clamp(-self.max_step as i128, self.max_step as i128) as i8;
+        if steps != 0 {
+            self.baseline = Some((reference, error));
+        }
+        steps
+    }
+
+    /// Forget the measurements, e.g. after deliberately setting the time.
+    pub fn reset(&mut self) {
+        self.baseline = None;
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the offset correction mode and the signed offset [-64 to 63] in steps of
+    /// [`OffsetMode::ppm_per_step`].
+    pub async fn get_offset(&mut self) -> Result<(OffsetMode, i8), Error<E>> {
+        let value = self.read_register(Register::OFFSET).await?;
+        Ok(codec::decode_offset(value))
+    }
+
+    /// Set the offset correction mode and the signed offset [-64 to 63]. Positive values make
+    /// the clock run faster.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the offset is out of range.
+    pub async fn set_offset(&mut self, mode: OffsetMode, offset: i8) -> Result<(), Error<E>> {
+        let value =
+            codec::encode_offset(mode, offset).ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        self.write_register(Register::OFFSET, value).await
+    }
+
+    /// Compare the clock with `reference`, and trim the offset register as `discipline`
+    /// decides. Returns the clock's error (ahead is positive) and the new offset.
+    ///
+    /// Pass the reference as close as possible to the start of a second, e.g. right after
+    /// `wait_for_second_change`, for the error to be accurate.
+    pub async fn discipline(
+        &mut self,
+        discipline: &mut Discipline,
+        reference: PrimitiveDateTime,
+    ) -> Result<(Duration, i8), Error<E>> {
+        let error = self.get_datetime().await? - reference;
+        let (mode, offset) = self.get_offset().await?;
+
+        let steps = discipline.update(reference, error, mode.ppm_per_step());
+        let new_offset = (offset + steps).clamp(-64, 63);
+        if new_offset != offset {
+            self.set_offset(mode, new_offset).await?;
+        }
+        Ok((error, new_offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn trims_against_drift() {
+        let mut discipline = Discipline::new(4);
+        let start = datetime!(2024-01-01 00:00:00);
+
+        assert_eq!(0, discipline.update(start, Duration::ZERO, 4.34));
+        // one second in an hour is not resolved yet
+        assert_eq!(
+            0,
+            discipline.update(start + Duration::hours(1), Duration::seconds(1), 4.34)
+        );
+        // 2 s ahead after 5 days is about 4.6 ppm fast, plus a quarter of the error
+        assert_eq!(
+            -1,
+            discipline.update(start + Duration::days(5), Duration::seconds(2), 4.34)
+        );
+    }
+
+    #[test]
+    fn limits_the_step() {
+        let mut discipline = Discipline::new(2);
+        let start = datetime!(2024-01-01 00:00:00);
+
+        discipline.update(start, Duration::ZERO, 4.34);
+        assert_eq!(
+            2,
+            discipline.update(start + Duration::hours(1), Duration::seconds(-10), 4.34)
+        );
+    }
+}
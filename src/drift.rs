@@ -0,0 +1,140 @@
+//! Detection of implausible RTC time jumps against an application monotonic clock.
+//!
+//! A glitching chip, an unexpected write by other firmware, or a battery brown-out all show up as
+//! RTC time advancing differently from the MCU's own monotonic clock. [`DriftMonitor`] compares
+//! the two between consecutive checks and reports the ones that disagree too much.
+
+use super::{Error, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{Duration, PrimitiveDateTime};
+
+/// A jump of RTC time that disagrees with the monotonic clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anomaly {
+    /// Time elapsed according to the RTC since the previous check.
+    pub rtc_elapsed: Duration,
+    /// Time elapsed according to the monotonic clock since the previous check.
+    pub monotonic_elapsed: Duration,
+}
+
+impl Anomaly {
+    /// How much further the RTC advanced than the monotonic clock (negative if less).
+    pub fn deviation(&self) -> Duration {
+        self.rtc_elapsed - self.monotonic_elapsed
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Anomaly {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Anomaly {{ rtc_elapsed: {} ms, monotonic_elapsed: {} ms }}",
+            self.rtc_elapsed.whole_milliseconds() as i64,
+            self.monotonic_elapsed.whole_milliseconds() as i64,
+        )
+    }
+}
+
+/// Compares elapsed RTC time with elapsed monotonic time between consecutive checks.
+#[derive(Debug, Clone)]
+pub struct DriftMonitor {
+    tolerance: Duration,
+    max_ppm: u32,
+    last: Option<(PrimitiveDateTime, u64)>,
+}
+
+impl DriftMonitor {
+    /// Create a monitor that accepts deviations up to `tolerance` plus `max_ppm` of the elapsed
+    /// time. As the RTC resolves whole seconds, the tolerance should be at least one second.
+    pub const fn new(tolerance: Duration, max_ppm: u32) -> Self {
+        DriftMonitor {
+            tolerance,
+            max_ppm,
+            last: None,
+        }
+    }
+
+    /// Compare `rtc` time with the monotonic clock `monotonic_ms` (in milliseconds).
+    ///
+    /// The first check only records a reference point. Every check becomes the reference point
+    /// for the next one, so a single jump is reported once.
+    pub fn check(&mut self, rtc: PrimitiveDateTime, monotonic_ms: u64) -> Result<(), Anomaly> {
+        let Some((last_rtc, last_ms)) = self.last.replace((rtc, monotonic_ms)) else {
+            return Ok(());
+        };
+
+        let anomaly = Anomaly {
+            rtc_elapsed: rtc - last_rtc,
+            monotonic_elapsed: Duration::milliseconds(monotonic_ms.wrapping_sub(last_ms) as i64),
+        };
+        // one ppm of a millisecond is a nanosecond
+        let allowed = self.tolerance
+            + Duration::nanoseconds(
+                (anomaly
+                    .monotonic_elapsed
+                    .whole_milliseconds()
+                    .unsigned_abs() as i64)
+                    .saturating_mul(self.max_ppm as i64),
+            );
+        if anomaly.deviation().abs() > allowed {
+            Err(anomaly)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Forget the reference point, e.g. after deliberately setting the time.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the time and check it against `monitor`, see [`DriftMonitor::check`].
+    ///
+    /// Returns the time read, and the anomaly found if any.
+    pub async fn check_drift(
+        &mut self,
+        monitor: &mut DriftMonitor,
+        monotonic_ms: u64,
+    ) -> Result<(PrimitiveDateTime, Option<Anomaly>), Error<E>> {
+        let now = self.get_datetime().await?;
+        Ok((now, monitor.check(now, monotonic_ms).err()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn accepts_consistent_time() {
+        let mut monitor = DriftMonitor::new(Duration::seconds(1), 100);
+
+        assert_eq!(Ok(()), monitor.check(datetime!(2024-02-28 23:59:00), 1_000));
+        assert_eq!(
+            Ok(()),
+            monitor.check(datetime!(2024-02-29 00:59:01), 3_601_000)
+        );
+    }
+
+    #[test]
+    fn reports_jumps_once() {
+        let mut monitor = DriftMonitor::new(Duration::seconds(1), 100);
+
+        monitor.check(datetime!(2024-01-01 12:00:00), 0).unwrap();
+        let anomaly = monitor
+            .check(datetime!(2024-01-01 12:00:00), 10_000)
+            .unwrap_err();
+        assert_eq!(Duration::seconds(-10), anomaly.deviation());
+        assert_eq!(
+            Ok(()),
+            monitor.check(datetime!(2024-01-01 12:00:10), 20_000)
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! A driver with its bus type erased, enabled with the `erased` feature.
+//!
+//! [`DynPCF85063`] is one concrete type whatever bus the chip is on, so RTCs on different buses
+//! fit in one device list or behind a plugin interface, without boxed futures. The bus is held
+//! as a `&mut dyn` [`ErasedBus`], which every blocking `embedded_hal::i2c::I2c` bus implements;
+//! async traits are not dyn-compatible, so async buses can not be erased this way. Bus errors
+//! are reduced to their [`ErrorKind`]:
+//!
+//! ```ignore
+//! let mut rtcs: [DynPCF85063; 2] = [
+//!     PCF85063::new(ErasedI2c::new(&mut i2c0)),
+//!     PCF85063::new(ErasedI2c::new(&mut bit_banged)),
+//! ];
+//! for rtc in &mut rtcs {
+//!     let now = rtc.get_datetime().await?;
+//! }
+//! ```
+
+use super::PCF85063;
+use embedded_hal_async::i2c::{self, ErrorKind, Operation, SevenBitAddress};
+
+/// A blocking I2C bus behind `dyn`, with its errors reduced to their kind.
+pub trait ErasedBus {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind>;
+}
+
+impl<B: embedded_hal::i2c::I2c> ErasedBus for B {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind> {
+        embedded_hal::i2c::I2c::transaction(self, address, operations)
+            .map_err(|error| i2c::Error::kind(&error))
+    }
+}
+
+/// An erased blocking bus, usable as the driver's async bus.
+pub struct ErasedI2c<'a> {
+    bus: &'a mut dyn ErasedBus,
+}
+
+impl<'a> ErasedI2c<'a> {
+    pub fn new(bus: &'a mut dyn ErasedBus) -> Self {
+        ErasedI2c { bus }
+    }
+}
+
+impl core::fmt::Debug for ErasedI2c<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ErasedI2c").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ErasedI2c<'_> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "ErasedI2c");
+    }
+}
+
+impl i2c::ErrorType for ErasedI2c<'_> {
+    type Error = ErrorKind;
+}
+
+impl i2c::I2c for ErasedI2c<'_> {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.bus.transaction(address, operations)
+    }
+}
+
+/// The driver on an erased bus.
+pub type DynPCF85063<'a> = PCF85063<ErasedI2c<'a>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_on::block_on;
+    use crate::{Error, Rtc};
+    use time::macros::datetime;
+
+    /// A bus returning the same bytes for every read.
+    struct Fixed([u8; 7]);
+
+    /// A bus where nothing answers.
+    struct Absent;
+
+    impl embedded_hal::i2c::ErrorType for Fixed {
+        type Error = ErrorKind;
+    }
+
+    impl embedded_hal::i2c::I2c for Fixed {
+        fn transaction(
+            &mut self,
+            _address: SevenBitAddress,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            for operation in operations {
+                if let Operation::Read(buffer) = operation {
+                    buffer.copy_from_slice(&self.0[..buffer.len()]);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl embedded_hal::i2c::ErrorType for Absent {
+        type Error = ErrorKind;
+    }
+
+    impl embedded_hal::i2c::I2c for Absent {
+        fn transaction(
+            &mut self,
+            _address: SevenBitAddress,
+            _operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            Err(ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Address))
+        }
+    }
+
+    async fn read_all(
+        rtcs: &mut [DynPCF85063<'_>],
+    ) -> [Result<time::PrimitiveDateTime, Error<ErrorKind>>; 2] {
+        let mut times = [Err(Error::Pin), Err(Error::Pin)];
+        for (time, rtc) in times.iter_mut().zip(rtcs) {
+            *time = Rtc::get_datetime(rtc).await;
+        }
+        times
+    }
+
+    #[test]
+    fn holds_different_buses_in_one_list() {
+        let mut fixed = Fixed([0x56, 0x34, 0x12, 0x29, 0x04, 0x02, 0x24]);
+        let mut absent = Absent;
+        let mut rtcs = [
+            PCF85063::new(ErasedI2c::new(&mut fixed)),
+            PCF85063::new(ErasedI2c::new(&mut absent)),
+        ];
+
+        let [fixed, absent] = block_on(read_all(&mut rtcs));
+        assert_eq!(datetime!(2024-02-29 12:34:56), fixed.unwrap());
+        assert!(matches!(
+            absent,
+            Err(Error::I2C(ErrorKind::NoAcknowledge(_)))
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! Falling back to an estimated time during bus faults, to keep timestamping alive.
+//!
+//! A [`LastKnownTime`] remembers the last time read successfully together with an application
+//! monotonic seconds counter. When reading the RTC fails with a bus error, the time is estimated
+//! from it and returned flagged as degraded, rather than as an error.
+//!
+//! ```ignore
+//! let mut last_known = LastKnownTime::new(3600);
+//!
+//! let now = rtc
+//!     .get_datetime_or_estimate(&mut last_known, embassy_time::Instant::now().as_secs())
+//!     .await?;
+//! if now.is_degraded() {
+//!     defmt::warn!("RTC unreachable, using estimated time");
+//! }
+//! ```
+
+use super::{Error, FineClock, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+/// A time read from the RTC, or estimated from the last one read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeReading {
+    /// Read from the RTC
+    Exact(PrimitiveDateTime),
+    /// Estimated after a bus error
+    Degraded(PrimitiveDateTime),
+}
+
+impl TimeReading {
+    pub fn datetime(self) -> PrimitiveDateTime {
+        match self {
+            TimeReading::Exact(datetime) | TimeReading::Degraded(datetime) => datetime,
+        }
+    }
+
+    pub fn is_degraded(self) -> bool {
+        matches!(self, TimeReading::Degraded(_))
+    }
+}
+
+/// The last time read successfully, and when that was on the monotonic clock.
+#[derive(Debug, Clone)]
+pub struct LastKnownTime {
+    /// The last time read, anchored to the monotonic clock in milliseconds
+    last: FineClock,
+    /// Longest time to estimate for after the last successful read, in seconds
+    max_age: u64,
+}
+
+impl LastKnownTime {
+    /// Keep estimating for at most `max_age` seconds after the last successful read.
+    pub const fn new(max_age: u64) -> Self {
+        LastKnownTime {
+            last: FineClock::new(),
+            max_age,
+        }
+    }
+
+    pub fn update(&mut self, datetime: PrimitiveDateTime, monotonic_seconds: u64) {
+        self.last
+            .anchor(datetime, monotonic_seconds.saturating_mul(1000));
+    }
+
+    /// Estimate the time at `monotonic_seconds`, `None` if there is no recent enough time.
+    pub fn estimate(&self, monotonic_seconds: u64) -> Option<PrimitiveDateTime> {
+        let monotonic_ms = monotonic_seconds.saturating_mul(1000);
+        if self.last.since_anchor(monotonic_ms)? > self.max_age.saturating_mul(1000) {
+            return None;
+        }
+        self.last.now(monotonic_ms)
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read date and time, falling back to an estimate from `last_known` on a bus error.
+    ///
+    /// Other errors, and bus errors without a recent enough time to estimate from, are
+    /// returned as usual.
+    pub async fn get_datetime_or_estimate(
+        &mut self,
+        last_known: &mut LastKnownTime,
+        monotonic_seconds: u64,
+    ) -> Result<TimeReading, Error<E>> {
+        match self.get_datetime().await {
+            Ok(datetime) => {
+                last_known.update(datetime, monotonic_seconds);
+                Ok(TimeReading::Exact(datetime))
+            }
+            Err(Error::I2C(e)) => match last_known.estimate(monotonic_seconds) {
+                Some(datetime) => Ok(TimeReading::Degraded(datetime)),
+                None => Err(Error::I2C(e)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn estimates_from_last_known_time() {
+        let mut last_known = LastKnownTime::new(60);
+        assert_eq!(None, last_known.estimate(100));
+
+        last_known.update(datetime!(2024-02-29 23:59:30), 100);
+        assert_eq!(
+            Some(datetime!(2024-03-01 00:00:15)),
+            last_known.estimate(145)
+        );
+        assert_eq!(None, last_known.estimate(161));
+        assert_eq!(None, last_known.estimate(99));
+    }
+}
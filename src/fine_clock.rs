@@ -0,0 +1,114 @@
+//! Millisecond timestamps, interpolating between the seconds of the RTC with a monotonic counter.
+//!
+//! The chip only resolves whole seconds. [`FineClock`] anchors the start of an RTC second to an
+//! application monotonic milliseconds counter (e.g. SysTick or `embassy_time::Instant`), and
+//! interpolates from there:
+//!
+//! ```ignore
+//! let mut clock = FineClock::new();
+//! let now_ms = || embassy_time::Instant::now().as_millis();
+//!
+//! rtc.anchor_fine_clock(&mut clock, &mut delay, now_ms).await?;
+//! let timestamp = clock.now(now_ms()).unwrap();
+//! ```
+//!
+//! The monotonic counter drifts against the RTC, so the clock should be anchored again now and
+//! then.
+//!
+//! `FineClock` is the one anchor of an RTC time to the monotonic counter in the driver:
+//! [`TimeKeeper`](crate::TimeKeeper), [`LastKnownTime`](crate::LastKnownTime) and the cache of
+//! `now_cached` are built on it.
+
+use super::{Error, PCF85063};
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+use time::{Duration, PrimitiveDateTime};
+
+/// Poll interval while looking for the start of a second, the resolution of the anchor.
+const ANCHOR_POLL_MS: u32 = 1;
+
+/// An RTC time anchored to a monotonic milliseconds counter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FineClock {
+    /// The start of an RTC second, and the monotonic time it was seen at.
+    anchor: Option<(PrimitiveDateTime, u64)>,
+}
+
+impl FineClock {
+    pub const fn new() -> Self {
+        FineClock { anchor: None }
+    }
+
+    /// Anchor the start of the second `datetime` to monotonic time `monotonic_ms`.
+    pub fn anchor(&mut self, datetime: PrimitiveDateTime, monotonic_ms: u64) {
+        self.anchor = Some((datetime, monotonic_ms));
+    }
+
+    /// Drop the anchor.
+    pub fn clear(&mut self) {
+        self.anchor = None;
+    }
+
+    /// The anchored time, `None` if the clock was never anchored.
+    pub fn anchored(&self) -> Option<PrimitiveDateTime> {
+        self.anchor.map(|(datetime, _)| datetime)
+    }
+
+    /// Milliseconds from the anchor to `monotonic_ms`, `None` if the clock was never anchored or
+    /// `monotonic_ms` lies before the anchor.
+    pub fn since_anchor(&self, monotonic_ms: u64) -> Option<u64> {
+        let (_, anchor_ms) = self.anchor?;
+        monotonic_ms.checked_sub(anchor_ms)
+    }
+
+    /// The time at `monotonic_ms` to the millisecond, `None` if the clock was never anchored or
+    /// `monotonic_ms` lies before the anchor.
+    pub fn now(&self, monotonic_ms: u64) -> Option<PrimitiveDateTime> {
+        let elapsed = i64::try_from(self.since_anchor(monotonic_ms)?).ok()?;
+        self.anchored()?
+            .checked_add(Duration::milliseconds(elapsed))
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Wait for the next second to start and anchor `clock` to it, with `now_ms` reading the
+    /// monotonic milliseconds counter.
+    ///
+    /// Takes up to a second; the anchor is accurate to about the duration of one register read.
+    pub async fn anchor_fine_clock(
+        &mut self,
+        clock: &mut FineClock,
+        delay: &mut impl DelayNs,
+        mut now_ms: impl FnMut() -> u64,
+    ) -> Result<(), Error<E>> {
+        self.wait_for_second_change(delay, ANCHOR_POLL_MS).await?;
+        let monotonic_ms = now_ms();
+        // the second just started, so this reads the same second
+        let datetime = self.get_datetime().await?;
+        clock.anchor(datetime, monotonic_ms);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn interpolates_milliseconds() {
+        let mut clock = FineClock::new();
+        assert_eq!(None, clock.now(1000));
+
+        clock.anchor(datetime!(2024-02-29 23:59:59), 1000);
+        assert_eq!(Some(datetime!(2024-02-29 23:59:59)), clock.now(1000));
+        assert_eq!(Some(datetime!(2024-03-01 00:00:00.250)), clock.now(2250));
+        assert_eq!(None, clock.now(999));
+        assert_eq!(Some(1250), clock.since_anchor(2250));
+
+        clock.clear();
+        assert_eq!(None, clock.anchored());
+    }
+}
@@ -0,0 +1,177 @@
+//! A bus wrapper that runs user code around every I2C transaction.
+//!
+//! Some boards put the RTC behind an I2C mux (e.g. a PCA954x), a level shifter with an enable
+//! pin, or a power-gated bus segment. Wrapping the bus in [`WithHooks`] lets the application
+//! prepare the bus before each transaction and release it afterwards:
+//!
+//! ```ignore
+//! struct SelectMuxChannel;
+//!
+//! impl<I2C: I2c> TransactionHooks<I2C> for SelectMuxChannel {
+//!     async fn before(&mut self, i2c: &mut I2C) -> Result<(), I2C::Error> {
+//!         i2c.write(PCA9546_ADDRESS, &[1 << RTC_CHANNEL]).await
+//!     }
+//! }
+//!
+//! let mut rtc = pcf85063a::PCF85063::new(WithHooks::new(i2c, SelectMuxChannel));
+//! ```
+
+use embedded_hal_async::i2c::{ErrorType, I2c, Operation, SevenBitAddress};
+
+/// Code to run around every I2C transaction of a [`WithHooks`] bus.
+///
+/// Both hooks get access to the bus itself, so they can for instance talk to a mux on the same
+/// bus. An error from a hook aborts the transaction and is returned in its place.
+#[allow(async_fn_in_trait)]
+pub trait TransactionHooks<I2C: ErrorType> {
+    /// Called before each transaction.
+    async fn before(&mut self, _i2c: &mut I2C) -> Result<(), I2C::Error> {
+        Ok(())
+    }
+
+    /// Called after each transaction, whether it succeeded or not.
+    async fn after(&mut self, _i2c: &mut I2C) -> Result<(), I2C::Error> {
+        Ok(())
+    }
+}
+
+/// I2C bus wrapper that calls [`TransactionHooks`] around every transaction.
+#[derive(Debug)]
+pub struct WithHooks<I2C, H> {
+    i2c: I2C,
+    hooks: H,
+}
+
+impl<I2C, H> WithHooks<I2C, H>
+where
+    I2C: I2c,
+    H: TransactionHooks<I2C>,
+{
+    /// Wrap `i2c`, calling `hooks` around every transaction.
+    pub const fn new(i2c: I2C, hooks: H) -> Self {
+        WithHooks { i2c, hooks }
+    }
+
+    /// Destroy the wrapper, returning the I2C bus and the hooks.
+    pub fn destroy(self) -> (I2C, H) {
+        (self.i2c, self.hooks)
+    }
+}
+
+impl<I2C, H> ErrorType for WithHooks<I2C, H>
+where
+    I2C: I2c,
+{
+    type Error = I2C::Error;
+}
+
+impl<I2C, H> I2c for WithHooks<I2C, H>
+where
+    I2C: I2c,
+    H: TransactionHooks<I2C>,
+{
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.hooks.before(&mut self.i2c).await?;
+        let result = self.i2c.transaction(address, operations).await;
+        let after = self.hooks.after(&mut self.i2c).await;
+
+        // the error of the transaction itself is the more interesting one
+        result.and(after)
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::simulator::{block_on, SimBus, SimError, Simulator};
+    use crate::{Error, Register, PCF85063};
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    /// Logs each hook call with the RAM byte the chip holds at that point
+    struct Recorder<'a> {
+        simulator: &'a Simulator,
+        log: &'a RefCell<Vec<(&'static str, u8)>>,
+        fail_before: bool,
+    }
+
+    impl Recorder<'_> {
+        fn record(&self, hook: &'static str) {
+            let ram = self.simulator.registers()[Register::RAM_BYTE as usize];
+            self.log.borrow_mut().push((hook, ram));
+        }
+    }
+
+    impl<'a> TransactionHooks<SimBus<'a>> for Recorder<'a> {
+        async fn before(&mut self, _i2c: &mut SimBus<'a>) -> Result<(), SimError> {
+            self.record("before");
+            if self.fail_before {
+                return Err(SimError::Interrupted);
+            }
+            Ok(())
+        }
+
+        async fn after(&mut self, _i2c: &mut SimBus<'a>) -> Result<(), SimError> {
+            self.record("after");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn runs_hooks_around_transaction() {
+        let simulator = Simulator::new();
+        let log = RefCell::new(Vec::new());
+        let hooks = Recorder {
+            simulator: &simulator,
+            log: &log,
+            fail_before: false,
+        };
+        let mut rtc = PCF85063::new(WithHooks::new(simulator.bus(), hooks));
+
+        block_on(rtc.write_ram_byte(0x5a)).unwrap();
+
+        assert_eq!(&[("before", 0), ("after", 0x5a)], &log.borrow()[..]);
+    }
+
+    #[test]
+    fn runs_after_hook_when_transaction_fails() {
+        let simulator = Simulator::new();
+        let log = RefCell::new(Vec::new());
+        let hooks = Recorder {
+            simulator: &simulator,
+            log: &log,
+            fail_before: false,
+        };
+        let mut i2c = WithHooks::new(simulator.bus(), hooks);
+
+        // nothing answers at this address
+        let result = block_on(i2c.write(0x42, &[Register::RAM_BYTE as u8, 0x5a]));
+
+        assert_eq!(Err(SimError::NoAcknowledge), result);
+        assert_eq!(&[("before", 0), ("after", 0)], &log.borrow()[..]);
+    }
+
+    #[test]
+    fn before_hook_error_aborts_transaction() {
+        let simulator = Simulator::new();
+        let log = RefCell::new(Vec::new());
+        let hooks = Recorder {
+            simulator: &simulator,
+            log: &log,
+            fail_before: true,
+        };
+        let mut rtc = PCF85063::new(WithHooks::new(simulator.bus(), hooks));
+
+        let result = block_on(rtc.write_ram_byte(0x5a));
+
+        assert!(matches!(result, Err(Error::I2C(SimError::Interrupted))));
+        assert_eq!(0, simulator.registers()[Register::RAM_BYTE as usize]);
+        assert_eq!(&[("before", 0)], &log.borrow()[..]);
+    }
+}
@@ -0,0 +1,77 @@
+//! Telling a PCF85063A apart from other chips answering at the same address.
+//!
+//! The PCF8563 and its clones use address 0x51 too, with a different register map: writing
+//! them as a PCF85063A silently sets the wrong registers. Two behaviors differ:
+//!
+//! - register 0x03 is the fully writable RAM byte on the PCF85063A, but the minutes on the
+//!   PCF8563, whose bit 7 does not hold a value;
+//! - a burst read wraps around to register 0x00 after 18 registers on the PCF85063A, and after
+//!   16 on the PCF8563.
+
+use super::{Error, Register, PCF85063};
+use embedded_hal_async::i2c::I2c;
+
+/// Registers read to see where the address wraps around.
+const WRAP_PROBE_LEN: usize = Register::ALL.len() + 1;
+/// Number of registers of the PCF8563.
+const PCF8563_REGISTERS: usize = 16;
+
+/// The chip found by [`PCF85063::identify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Chip {
+    Pcf85063a,
+    /// A PCF8563 or one of its clones
+    Pcf8563,
+    Unknown,
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Probe the chip at the device address.
+    ///
+    /// Writes the inverted RAM byte and restores it, so on a PCF8563 the minutes may be off by
+    /// one if they roll over in between. Call this at startup, before configuring the chip.
+    pub async fn identify(&mut self) -> Result<Chip, Error<E>> {
+        let original = self.read_ram_byte().await?;
+        self.write_ram_byte(!original).await?;
+        let read_back = self.read_ram_byte().await?;
+        self.write_ram_byte(original).await?;
+
+        let mut registers = [0; WRAP_PROBE_LEN];
+        self.read_bytes(Register::CONTROL_1, &mut registers).await?;
+
+        Ok(identify(read_back == !original, &registers))
+    }
+}
+
+fn identify(ram_writable: bool, registers: &[u8; WRAP_PROBE_LEN]) -> Chip {
+    let wraps_at = |n: usize| registers[n] == registers[0] && registers[n + 1] == registers[1];
+    if ram_writable && registers[Register::ALL.len()] == registers[0] {
+        Chip::Pcf85063a
+    } else if !ram_writable && wraps_at(PCF8563_REGISTERS) {
+        Chip::Pcf8563
+    } else {
+        Chip::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifies_by_wrap_around() {
+        let mut registers = [
+            0x00, 0x80, 0x00, 0x00, 0x12, 0x34, 0x05, 0x01, 0x06, 0x01, 0x25, 0x80, 0x80, 0x80,
+            0x80, 0x80, 0x00, 0x18, 0x00,
+        ];
+        assert_eq!(Chip::Pcf85063a, identify(true, &registers));
+        assert_eq!(Chip::Unknown, identify(false, &registers));
+
+        registers[16..].copy_from_slice(&[0x00, 0x80, 0x00]);
+        assert_eq!(Chip::Pcf8563, identify(false, &registers));
+    }
+}
@@ -0,0 +1,89 @@
+//! Telling whether this chip asserted a shared interrupt line.
+//!
+//! The INT output is open-drain, so boards often wire it together with other interrupt sources.
+//! When the line goes low, [`PCF85063::acknowledge_interrupt`] finds out whether this chip is
+//! (one of) the cause(s), and acknowledges only its own flags.
+
+use super::{BitFlags, Error, Register, PCF85063};
+use embedded_hal_async::i2c::I2c;
+
+/// The flags of this chip that assert the INT line
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Interrupts {
+    /// The alarm fired, with the alarm interrupt enabled
+    pub alarm: bool,
+    /// The timer or a (half) minute interrupt fired, with its interrupt enabled
+    pub timer: bool,
+}
+
+impl Interrupts {
+    /// Did this chip assert the interrupt line?
+    pub fn is_mine(&self) -> bool {
+        self.alarm || self.timer
+    }
+
+    /// Decode control 2 and timer mode.
+    fn decode(control_2: u8, timer_mode: u8) -> Self {
+        let timer_enabled =
+            control_2 & (BitFlags::MI | BitFlags::HMI) != 0 || timer_mode & BitFlags::TIE != 0;
+        Interrupts {
+            alarm: control_2 & BitFlags::AF != 0 && control_2 & BitFlags::AIE != 0,
+            timer: control_2 & BitFlags::TF != 0 && timer_enabled,
+        }
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Check which of this chip's enabled interrupts are pending, and acknowledge them.
+    ///
+    /// Flags of disabled interrupts do not drive the INT line, so they are left alone. If
+    /// nothing is returned as pending, the interrupt came from another device on the line.
+    pub async fn acknowledge_interrupt(&mut self) -> Result<Interrupts, Error<E>> {
+        let control_2 = self.read_register(Register::CONTROL_2).await?;
+        let timer_mode = self.read_register(Register::TIMER_MODE).await?;
+        let pending = Interrupts::decode(control_2, timer_mode);
+
+        if pending.is_mine() {
+            // flags are cleared by writing 0, writing 1 leaves them unchanged
+            let mut value = control_2 | BitFlags::AF | BitFlags::TF;
+            if pending.alarm {
+                value &= !BitFlags::AF;
+            }
+            if pending.timer {
+                value &= !BitFlags::TF;
+            }
+            self.write_register(Register::CONTROL_2, value).await?;
+        }
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_flags_of_disabled_interrupts() {
+        let flags = BitFlags::AF | BitFlags::TF;
+        assert!(!Interrupts::decode(flags, 0).is_mine());
+        assert_eq!(
+            Interrupts {
+                alarm: true,
+                timer: false
+            },
+            Interrupts::decode(flags | BitFlags::AIE, 0)
+        );
+        assert_eq!(
+            Interrupts {
+                alarm: false,
+                timer: true
+            },
+            Interrupts::decode(flags, BitFlags::TIE)
+        );
+        assert!(Interrupts::decode(BitFlags::TF | BitFlags::MI, 0).timer);
+    }
+}
@@ -0,0 +1,77 @@
+//! Getting and setting the time as jiff civil date and time, enabled with the `jiff` feature.
+//!
+//! Like the chip, `jiff::civil::DateTime` has no time zone; the conversion goes through the
+//! driver's `time` values, so the same range and leap year checks apply.
+
+use super::{DataError, Error, PCF85063};
+use ::jiff::civil;
+use embedded_hal_async::i2c::I2c;
+use time::{Date, Month, PrimitiveDateTime, Time};
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read date and time all at once, as a jiff civil date and time.
+    pub async fn get_civil_datetime(&mut self) -> Result<civil::DateTime, Error<E>> {
+        let datetime = self.get_datetime().await?;
+        to_civil(&datetime).ok_or(Error::Data(DataError::ComponentRange))
+    }
+
+    /// Set date and time all at once from a jiff civil date and time.
+    ///
+    /// The subsecond part is dropped. Subject to the same checks as `set_datetime`, and returns
+    /// whether the clock was written.
+    pub async fn set_civil_datetime(
+        &mut self,
+        datetime: civil::DateTime,
+    ) -> Result<bool, Error<E>> {
+        let datetime = from_civil(datetime)?;
+        self.set_datetime(&datetime).await
+    }
+}
+
+fn to_civil(datetime: &PrimitiveDateTime) -> Option<civil::DateTime> {
+    civil::DateTime::new(
+        i16::try_from(datetime.year()).ok()?,
+        u8::from(datetime.month()) as i8,
+        datetime.day() as i8,
+        datetime.hour() as i8,
+        datetime.minute() as i8,
+        datetime.second() as i8,
+        0,
+    )
+    .ok()
+}
+
+fn from_civil(datetime: civil::DateTime) -> Result<PrimitiveDateTime, time::error::ComponentRange> {
+    let date = Date::from_calendar_date(
+        datetime.year() as i32,
+        Month::try_from(datetime.month() as u8)?,
+        datetime.day() as u8,
+    )?;
+    let time = Time::from_hms(
+        datetime.hour() as u8,
+        datetime.minute() as u8,
+        datetime.second() as u8,
+    )?;
+    Ok(PrimitiveDateTime::new(date, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn can_convert_civil_datetimes() {
+        let datetime = datetime!(2024-02-29 23:30:59);
+        let civil = civil::date(2024, 2, 29).at(23, 30, 59, 0);
+        assert_eq!(Some(civil), to_civil(&datetime));
+        assert_eq!(Ok(datetime), from_civil(civil));
+        assert_eq!(
+            Ok(datetime),
+            from_civil(civil::date(2024, 2, 29).at(23, 30, 59, 999_999_999))
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Relative alarms, optionally spread over a jitter window.
+//!
+//! A fleet of devices that synced their clocks to the same server and then set the alarm a
+//! fixed delay ahead all wake, and transmit, in the same second. Passing a random number, from
+//! the device's RNG or a hash of its serial number, spreads the wakes over a window instead:
+//!
+//! ```ignore
+//! // report in an hour, give or take up to five minutes later
+//! let at = rtc
+//!     .alarm_in_with_jitter(Duration::from_secs(3600), Duration::from_secs(300), rng.next_u32())
+//!     .await?;
+//! rtc.arm_alarm_interrupt().await?;
+//! ```
+
+use super::{Error, UsageError, PCF85063};
+use core::time::Duration;
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+/// Furthest the alarm reaches: it matches on the day of the month.
+const ALARM_REACH: Duration = Duration::from_secs(28 * 24 * 3600);
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Set the alarm to `delay` from the current time, and return the time it is set to.
+    ///
+    /// Sub-second parts of `delay` are dropped. The alarm interrupt and flag are left alone.
+    /// Will return a 'UsageError::InvalidInputData' if `delay` is longer than 28 days.
+    pub async fn alarm_in(&mut self, delay: Duration) -> Result<PrimitiveDateTime, Error<E>> {
+        self.alarm_in_with_jitter(delay, Duration::ZERO, 0).await
+    }
+
+    /// Set the alarm to `delay` from the current time plus a jitter of up to `window`, and
+    /// return the time it is set to.
+    ///
+    /// The jitter is `random` reduced to whole seconds in `0..=window`, so the caller decides
+    /// where the randomness comes from. The alarm interrupt and flag are left alone. Will return
+    /// a 'UsageError::InvalidInputData' if `delay` and `window` together are longer than 28
+    /// days.
+    pub async fn alarm_in_with_jitter(
+        &mut self,
+        delay: Duration,
+        window: Duration,
+        random: u32,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        let offset = jittered(delay, window, random)
+            .filter(|offset| *offset <= ALARM_REACH)
+            .ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        let now = self.get_datetime().await?;
+        let at = time::Duration::try_from(offset)
+            .ok()
+            .and_then(|offset| now.checked_add(offset))
+            .ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        self.set_alarm_at(at).await?;
+        Ok(at)
+    }
+}
+
+/// `delay` plus `random` reduced to whole seconds in `0..=window`, in whole seconds; `None` if
+/// that overflows.
+fn jittered(delay: Duration, window: Duration, random: u32) -> Option<Duration> {
+    let jitter = u64::from(random) % window.as_secs().checked_add(1)?;
+    delay.as_secs().checked_add(jitter).map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_stays_in_window() {
+        let hour = Duration::from_secs(3600);
+        let window = Duration::from_secs(300);
+        for random in [0, 1, 299, 300, 301, u32::MAX] {
+            let offset = jittered(hour, window, random).unwrap();
+            assert!(offset >= hour && offset <= hour + window);
+        }
+        assert_eq!(Some(hour), jittered(hour, window, 301));
+        assert_eq!(
+            Some(hour),
+            jittered(hour + Duration::from_millis(999), Duration::ZERO, 12345)
+        );
+        assert_eq!(None, jittered(Duration::from_secs(u64::MAX), window, 1));
+        assert_eq!(None, jittered(hour, Duration::MAX, 1));
+    }
+}
@@ -0,0 +1,94 @@
+//! Julian Day and Modified Julian Day numbers, for code that counts days continuously rather
+//! than by the calendar, such as astronomy and GNSS.
+//!
+//! The Julian Date counts days from noon, the Modified Julian Date from midnight:
+//!
+//! ```
+//! use pcf85063a::julian;
+//! use time::macros::datetime;
+//!
+//! assert_eq!(2_451_545.0, julian::julian_date(&datetime!(2000-01-01 12:00:00)));
+//! assert_eq!(51_544.5, julian::modified_julian_date(&datetime!(2000-01-01 12:00:00)));
+//! ```
+
+use time::{Date, Duration, PrimitiveDateTime};
+
+/// Julian Day number of the day that Modified Julian Day 0 starts on, 1858-11-17.
+const MJD_EPOCH_JDN: i32 = 2_400_001;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// The Julian Day number of `date`, the day starting at its noon.
+pub fn julian_day_number(date: Date) -> i32 {
+    date.to_julian_day()
+}
+
+/// The Modified Julian Day of `date`, the day starting at its midnight.
+pub fn modified_julian_day(date: Date) -> i32 {
+    date.to_julian_day() - MJD_EPOCH_JDN
+}
+
+/// The date of Modified Julian Day `mjd`, `None` if out of range.
+pub fn from_modified_julian_day(mjd: i32) -> Option<Date> {
+    Date::from_julian_day(mjd.checked_add(MJD_EPOCH_JDN)?).ok()
+}
+
+/// The Julian Date of `datetime`, with the time as fraction of the day.
+pub fn julian_date(datetime: &PrimitiveDateTime) -> f64 {
+    modified_julian_date(datetime) + (MJD_EPOCH_JDN as f64 - 0.5)
+}
+
+/// The Modified Julian Date of `datetime`, with the time as fraction of the day.
+pub fn modified_julian_date(datetime: &PrimitiveDateTime) -> f64 {
+    let (hour, minute, second) = datetime.as_hms();
+    let seconds = hour as u32 * 3600 + minute as u32 * 60 + second as u32;
+    modified_julian_day(datetime.date()) as f64 + seconds as f64 / SECONDS_PER_DAY
+}
+
+/// The date and time of Julian Date `jd`, rounded to the second, `None` if out of range.
+pub fn from_julian_date(jd: f64) -> Option<PrimitiveDateTime> {
+    from_modified_julian_date(jd - (MJD_EPOCH_JDN as f64 - 0.5))
+}
+
+/// The date and time of Modified Julian Date `mjd`, rounded to the second, `None` if out of
+/// range.
+pub fn from_modified_julian_date(mjd: f64) -> Option<PrimitiveDateTime> {
+    if !mjd.is_finite() || mjd.abs() > i32::MAX as f64 {
+        return None;
+    }
+    let mut day = mjd as i32;
+    // the cast truncates toward zero
+    if day as f64 > mjd {
+        day -= 1;
+    }
+    let seconds = ((mjd - day as f64) * SECONDS_PER_DAY + 0.5) as i64;
+    from_modified_julian_day(day)?
+        .midnight()
+        .checked_add(Duration::seconds(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn can_convert_day_numbers() {
+        assert_eq!(0, modified_julian_day(date!(1858 - 11 - 17)));
+        assert_eq!(2_451_545, julian_day_number(date!(2000 - 01 - 01)));
+        assert_eq!(
+            Some(date!(2024 - 02 - 29)),
+            from_modified_julian_day(60_369)
+        );
+    }
+
+    #[test]
+    fn can_round_trip_dates() {
+        let datetime = datetime!(2024-02-29 23:59:59);
+        assert_eq!(Some(datetime), from_julian_date(julian_date(&datetime)));
+        assert_eq!(
+            Some(datetime!(1858-11-16 18:00:00)),
+            from_modified_julian_date(-0.25)
+        );
+        assert_eq!(None, from_modified_julian_date(f64::NAN));
+    }
+}
@@ -1,56 +1,379 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "access-log")]
+mod access_log;
 mod alarm;
+mod align;
+mod audit;
+pub mod bcd;
+#[cfg(any(
+    feature = "linux",
+    feature = "nb",
+    feature = "simulator",
+    all(test, any(feature = "erased", feature = "test-support"))
+))]
+mod block_on;
+mod cache;
+pub mod calendar;
+mod capture;
+mod clkout;
+pub mod codec;
+#[cfg(feature = "component-setters")]
+mod components;
+mod config;
+mod config_check;
+#[cfg(feature = "nb")]
+mod countdown;
+#[cfg(feature = "critical-section")]
+mod critical;
 mod datetime;
-
-use embedded_hal_async::i2c::I2c;
+mod diagnostics;
+pub mod digits;
+mod discipline;
+mod drift;
+#[cfg(feature = "erased")]
+mod erased;
+mod fallback;
+mod fine_clock;
+pub mod hooks;
+mod identify;
+mod interrupt;
+#[cfg(feature = "jiff")]
+mod jiff_civil;
+mod jitter;
+pub mod julian;
+#[cfg(feature = "linux")]
+pub mod linux;
+#[cfg(feature = "defmt-timestamp")]
+mod log_timestamp;
+#[doc(hidden)]
+pub mod macros;
+mod maintenance;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "test-support")]
+pub mod mock;
+pub mod names;
+#[cfg(feature = "embassy")]
+mod notify;
+mod outage;
+mod persist;
+mod power_loss;
+pub mod prelude;
+#[cfg(all(test, feature = "property-tests"))]
+mod proptests;
+mod ram;
+mod raw;
+mod recovery;
+mod rtc;
+mod schedule;
+mod scratch;
+mod self_test;
+#[cfg(feature = "embassy")]
+mod service;
+mod setup;
+#[cfg(feature = "embassy")]
+mod shared;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+mod status;
+mod stopwatch;
+pub mod strftime;
+#[cfg(feature = "sun")]
+pub mod sun;
+mod suspend;
+mod sync;
+mod tick;
+mod time_keeper;
+pub mod timeout;
+pub mod timer;
+pub mod tz;
+pub mod units;
+mod unix_clock;
+mod uptime;
+mod utc;
+mod wait;
+mod wake;
+
+use embedded_hal_async::i2c::{ErrorKind, I2c};
+use units::OutOfRange;
+
+#[cfg(feature = "access-log")]
+pub use access_log::{Access, Direction, ACCESS_LOG_LEN};
+pub use align::Boundary;
+pub use audit::{AuditLog, TimeChange, AUDIT_ENTRY_LEN};
+pub use capture::{CaptureClock, EdgeCounter};
+pub use clkout::{ClkoutGuard, ClkoutShare};
+pub use config::{Alarm, Calibration, Config, ALARM_LEN, CALIBRATION_LEN, CONFIG_LEN};
+#[cfg(feature = "nb")]
+pub use countdown::CountDown;
+#[cfg(feature = "critical-section")]
+pub use critical::CsRtc;
+pub use diagnostics::{Diagnostics, OffsetMode};
+pub use discipline::Discipline;
+pub use drift::{Anomaly, DriftMonitor};
+#[cfg(feature = "erased")]
+pub use erased::{DynPCF85063, ErasedBus, ErasedI2c};
+pub use fallback::{LastKnownTime, TimeReading};
+pub use fine_clock::FineClock;
+pub use identify::Chip;
+pub use interrupt::Interrupts;
+#[cfg(feature = "defmt-timestamp")]
+pub use log_timestamp::set_log_timestamp;
+pub use maintenance::Event;
+#[cfg(feature = "metrics")]
+pub use metrics::Metrics;
+#[cfg(feature = "embassy")]
+pub use notify::{AlarmNotifier, TimeBroadcast};
+pub use outage::{Outage, ALIVE_RECORD_LEN};
+pub use persist::{PersistError, Persisted, PersistentStorage, RECORD_LEN};
+pub use power_loss::PowerState;
+pub use raw::RawDateTime;
+pub use recovery::Recovery;
+pub use rtc::{CopyError, Rtc};
+pub use schedule::WakeState;
+pub use scratch::ScratchStorage;
+pub use self_test::SelfTestReport;
+#[cfg(feature = "embassy")]
+pub use service::{Command, Reply, RtcService};
+pub use setup::Setup;
+#[cfg(feature = "embassy")]
+pub use shared::SharedRtc;
+pub use status::ChipStatus;
+pub use stopwatch::Stopwatch;
+pub use suspend::{DriverState, DRIVER_STATE_LEN};
+pub use sync::{TimeSync, TIME_SYNC_LEN};
+pub use tick::Tick;
+pub use time_keeper::TimeKeeper;
+pub use unix_clock::UnixClock;
+pub use uptime::UPTIME_RECORD_LEN;
+pub use wake::{WakePlan, WakeTarget};
 
 /// All possible errors in this crate
+///
+/// Errors are grouped so that one match arm handles a whole category: bus and pin errors, errors
+/// in the use of the driver, and unexpected data or state of the chip.
+///
+/// [`Error::into_kind`] reduces bus errors to their `ErrorKind`, keeping large HAL error types
+/// out of the code handling them.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error<E> {
     /// I2C bus error
     I2C(E),
+    /// Error of the interrupt pin
+    Pin,
+    /// The driver was used in a way it does not support
+    Usage(UsageError),
+    /// The chip holds data or is in a state it should not be in
+    Data(DataError),
+}
+
+/// Errors in the use of the driver, as [`Error::Usage`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UsageError {
     /// Invalid input data
     InvalidInputData,
+    /// The registers are in use by an enabled alarm or timer
+    InUse,
+    /// Setting the time would move the clock back further than allowed
+    BackwardsJump,
+    /// A shared instance was used before it was given its bus
+    NotInitialized,
+}
+
+/// Unexpected register contents or chip state, as [`Error::Data`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum DataError {
     /// A time component was out of range
     ComponentRange,
+    /// The clock is not running
+    NotRunning,
+    /// The chip counted a February 29 in a year that is not a leap year, such as 2100
+    LeapYearMismatch,
+    /// The registers did not take their reset values after a software reset
+    ResetFailed,
+    /// The weekday register does not match the date
+    WeekdayMismatch,
+    /// Time or alarm registers holding invalid BCD digits or out of range values
+    InvalidRegisters(RegisterBytes),
+}
+
+/// The raw contents of the registers that failed to decode, for diagnosing whether the chip,
+/// the bus or other firmware wrote them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegisterBytes {
+    /// The first register read
+    pub first: Register,
+    len: u8,
+    data: [u8; 7],
+}
+
+impl RegisterBytes {
+    /// Up to seven values read from `first` onwards; further values are dropped.
+    pub fn new(first: Register, bytes: &[u8]) -> Self {
+        let len = bytes.len().min(7);
+        let mut data = [0; 7];
+        data[..len].copy_from_slice(&bytes[..len]);
+        RegisterBytes {
+            first,
+            len: len as u8,
+            data,
+        }
+    }
+
+    /// The values read, in address order.
+    pub fn bytes(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl<E> From<UsageError> for Error<E> {
+    fn from(error: UsageError) -> Self {
+        Error::Usage(error)
+    }
+}
+
+impl<E> From<DataError> for Error<E> {
+    fn from(error: DataError) -> Self {
+        Error::Data(error)
+    }
+}
+
+impl<E> Error<E> {
+    /// Convert the bus error with `f`, leaving the other errors as they are.
+    pub fn map_bus<F>(self, f: impl FnOnce(E) -> F) -> Error<F> {
+        match self {
+            Error::I2C(error) => Error::I2C(f(error)),
+            Error::Pin => Error::Pin,
+            Error::Usage(error) => Error::Usage(error),
+            Error::Data(error) => Error::Data(error),
+        }
+    }
+
+    /// Reduce the bus error to its `ErrorKind`.
+    pub fn into_kind(self) -> Error<ErrorKind>
+    where
+        E: embedded_hal_async::i2c::Error,
+    {
+        self.map_bus(|error| error.kind())
+    }
 }
 
+#[cfg(feature = "std")]
+impl<E: core::fmt::Debug> core::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: core::fmt::Debug> std::error::Error for Error<E> {}
+
 impl<E> From<time::error::ComponentRange> for Error<E> {
     fn from(_: time::error::ComponentRange) -> Self {
-        Self::ComponentRange
+        Error::Data(DataError::ComponentRange)
     }
 }
 
-pub struct Register;
-
-impl Register {
+/// Register addresses
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Register {
     // control and status registers
-    pub const CONTROL_1: u8 = 0x00;
-    pub const CONTROL_2: u8 = 0x01;
-    pub const OFFSET: u8 = 0x02;
-    pub const RAM_BYTE: u8 = 0x03;
+    CONTROL_1 = 0x00,
+    CONTROL_2 = 0x01,
+    OFFSET = 0x02,
+    RAM_BYTE = 0x03,
 
     // time and date registers
-    pub const SECONDS: u8 = 0x04;
-    pub const MINUTES: u8 = 0x05;
-    pub const HOURS: u8 = 0x06;
-    pub const DAYS: u8 = 0x07;
-    pub const WEEKDAYS: u8 = 0x08;
-    pub const MONTHS: u8 = 0x09;
-    pub const YEARS: u8 = 0x0A;
+    SECONDS = 0x04,
+    MINUTES = 0x05,
+    HOURS = 0x06,
+    DAYS = 0x07,
+    WEEKDAYS = 0x08,
+    MONTHS = 0x09,
+    YEARS = 0x0A,
 
     // alarm registers
-    pub const SECOND_ALARM: u8 = 0x0B;
-    pub const MINUTE_ALARM: u8 = 0x0C;
-    pub const HOUR_ALARM: u8 = 0x0D;
-    pub const DAY_ALARM: u8 = 0x0E;
-    pub const WEEKDAY_ALARM: u8 = 0x0F;
+    SECOND_ALARM = 0x0B,
+    MINUTE_ALARM = 0x0C,
+    HOUR_ALARM = 0x0D,
+    DAY_ALARM = 0x0E,
+    WEEKDAY_ALARM = 0x0F,
 
     // timer registers
-    pub const TIMER_VALUE: u8 = 0x10;
-    pub const TIMER_MODE: u8 = 0x11;
+    TIMER_VALUE = 0x10,
+    TIMER_MODE = 0x11,
+}
+
+impl Register {
+    /// All registers, in address order.
+    pub const ALL: [Register; 18] = [
+        Register::CONTROL_1,
+        Register::CONTROL_2,
+        Register::OFFSET,
+        Register::RAM_BYTE,
+        Register::SECONDS,
+        Register::MINUTES,
+        Register::HOURS,
+        Register::DAYS,
+        Register::WEEKDAYS,
+        Register::MONTHS,
+        Register::YEARS,
+        Register::SECOND_ALARM,
+        Register::MINUTE_ALARM,
+        Register::HOUR_ALARM,
+        Register::DAY_ALARM,
+        Register::WEEKDAY_ALARM,
+        Register::TIMER_VALUE,
+        Register::TIMER_MODE,
+    ];
+
+    /// Unused bits, which should be written as 0.
+    pub const fn reserved_bits(self) -> u8 {
+        match self {
+            Register::CONTROL_1 => 0b0100_1000,
+            Register::MINUTES => 0b1000_0000,
+            Register::HOURS | Register::DAYS => 0b1100_0000,
+            Register::WEEKDAYS => 0b1111_1000,
+            Register::MONTHS | Register::TIMER_MODE => 0b1110_0000,
+            Register::HOUR_ALARM | Register::DAY_ALARM => 0b0100_0000,
+            Register::WEEKDAY_ALARM => 0b0111_1000,
+            _ => 0,
+        }
+    }
+
+    /// The register `n` addresses further, `None` past the last register.
+    pub const fn offset(self, n: u8) -> Option<Register> {
+        let address = self as usize + n as usize;
+        if address < Self::ALL.len() {
+            Some(Self::ALL[address])
+        } else {
+            None
+        }
+    }
+}
+
+impl From<Register> for u8 {
+    fn from(register: Register) -> Self {
+        register as u8
+    }
+}
+
+impl TryFrom<u8> for Register {
+    type Error = OutOfRange;
+
+    /// Look up the register at an address.
+    fn try_from(address: u8) -> Result<Self, Self::Error> {
+        Register::CONTROL_1.offset(address).ok_or(OutOfRange)
+    }
 }
 
 pub struct BitFlags;
@@ -74,15 +397,33 @@ impl BitFlags {
     pub const AF: u8 = 0b0100_0000; // alarm flag
     pub const AIE: u8 = 0b1000_0000; // alarm interrupt enabled
 
+    // seconds
+    pub const OS: u8 = 0b1000_0000; // oscillator stop: clock integrity is not guaranteed
+
     pub const AE: u8 = 0b1000_0000; // alarm enable/disable for all five (s/m/h/d/wd) settings
+
+    // timer mode
+    pub const TI_TP: u8 = 0b0000_0001; // interrupt generation: pulse or permanent
+    pub const TIE: u8 = 0b0000_0010; // timer interrupt enable
+    pub const TE: u8 = 0b0000_0100; // timer enable
+    pub const TCF: u8 = 0b0001_1000; // timer clock frequency
 }
 
 const DEVICE_ADDRESS: u8 = 0b1010001;
 
+/// Pattern written to control 1 to trigger a software reset.
+const SOFTWARE_RESET: u8 = 0b0101_1000;
+
+/// Default year that the chip's year counter value 0 maps onto.
+const DEFAULT_BASE_YEAR: i32 = 2000;
+
 /// Two possible choices, used for various enable/disable bit flags
-#[allow(non_camel_case_types)]
+///
+/// Deprecated in favor of the `enable_*` functions taking a `bool`, which is `true` for enabled
+/// whether that sets or clears the bit: for the alarm fields, `On` cleared the AE bit.
+#[deprecated(note = "use the `enable_*` functions taking a `bool`")]
+#[allow(deprecated, non_camel_case_types)]
 #[derive(Copy, Clone, Debug)]
-#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Control {
     /// Enable some feature, eg. timer
     On,
@@ -90,27 +431,150 @@ pub enum Control {
     Off,
 }
 
+#[allow(deprecated)]
+impl From<Control> for bool {
+    fn from(status: Control) -> Self {
+        matches!(status, Control::On)
+    }
+}
+
+// the derive would use the deprecated variants outside the reach of an `allow`
+#[cfg(feature = "defmt")]
+#[allow(deprecated)]
+impl defmt::Format for Control {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Control::On => defmt::write!(f, "On"),
+            Control::Off => defmt::write!(f, "Off"),
+        }
+    }
+}
+
 /// PCF8563 driver
-#[derive(Debug, Default)]
+#[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PCF85063<I2C> {
     /// The concrete I2C device implementation.
     i2c: I2C,
+    /// The year that year counter value 0 maps onto.
+    base_year: i32,
+    /// Largest backwards jump allowed by `set_datetime`, if limited.
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    backwards_limit: Option<time::Duration>,
+    /// Smallest correction written by `set_datetime`, if limited.
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    write_threshold: Option<time::Duration>,
+    /// Whether `get_datetime` checks the weekday against the date.
+    check_weekday: bool,
+    /// Date and time served by `now_cached`, anchored to the monotonic time it was read at.
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    cached: FineClock,
+    /// Bus traffic counters.
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
+    /// Most recent register accesses.
+    #[cfg(feature = "access-log")]
+    access_log: access_log::AccessLog,
+}
+
+impl<I2C> PCF85063<I2C> {
+    /// Create a new instance of the PCF8563 driver.
+    ///
+    /// This is a `const fn`, so with a bus that can be constructed in a const context the
+    /// driver can be placed in a `static` directly.
+    pub const fn new(i2c: I2C) -> Self {
+        Self::with_base_year(i2c, DEFAULT_BASE_YEAR)
+    }
+
+    /// Create a new instance of the PCF8563 driver, mapping the chip's year counter 0-99 onto
+    /// the years `base_year` up to and including `base_year + 99`.
+    ///
+    /// The chip treats every year with a counter divisible by four as a leap year, so
+    /// `base_year` must be divisible by four (e.g. 1972, 2000 or 2020) for February 29 to line
+    /// up; otherwise `i2c` is handed back. A century year that is not a leap year can still fall
+    /// in the window, see [`leap_year_mismatch`](Self::leap_year_mismatch).
+    pub const fn new_with_base_year(i2c: I2C, base_year: i32) -> Result<Self, I2C> {
+        if base_year % 4 != 0 {
+            return Err(i2c);
+        }
+        Ok(Self::with_base_year(i2c, base_year))
+    }
+
+    /// Create a new instance with a base year known to be divisible by four.
+    pub(crate) const fn with_base_year(i2c: I2C, base_year: i32) -> Self {
+        PCF85063 {
+            i2c,
+            base_year,
+            backwards_limit: None,
+            write_threshold: None,
+            check_weekday: false,
+            cached: FineClock::new(),
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::new(),
+            #[cfg(feature = "access-log")]
+            access_log: access_log::AccessLog::new(),
+        }
+    }
+
+    /// The year that the chip's year counter value 0 maps onto.
+    pub const fn base_year(&self) -> i32 {
+        self.base_year
+    }
+
+    /// Lend out the I2C bus, e.g. to talk to other devices on it or perform custom
+    /// transactions, without destroying the driver.
+    ///
+    /// Traffic on the returned bus is not seen by the driver, so it is not counted or logged.
+    /// It may change the time, so the time cached by `now_cached` is dropped.
+    pub fn bus_mut(&mut self) -> &mut I2C {
+        self.cached.clear();
+        &mut self.i2c
+    }
+
+    /// Run `f` with the I2C bus, see [`bus_mut`](Self::bus_mut).
+    pub fn with_bus<R>(&mut self, f: impl FnOnce(&mut I2C) -> R) -> R {
+        f(self.bus_mut())
+    }
+}
+
+impl<I2C: Default> Default for PCF85063<I2C> {
+    fn default() -> Self {
+        Self::new(I2C::default())
+    }
 }
 
 impl<I2C, E> PCF85063<I2C>
 where
     I2C: I2c<Error = E>,
 {
-    /// Create a new instance of the PCF8563 driver.
-    pub fn new(i2c: I2C) -> Self {
-        PCF85063 { i2c }
+    /// Reset the RTC with the software reset sequence, and check that the control and offset
+    /// registers came back at their reset values.
+    ///
+    /// Will return a 'DataError::ResetFailed' if they did not.
+    pub async fn reset(&mut self) -> Result<(), Error<E>> {
+        self.perform_software_reset().await?;
+
+        let mut data = [0xff; 3];
+        self.read_bytes(Register::CONTROL_1, &mut data).await?;
+        if data != [0; 3] {
+            return Err(Error::Data(DataError::ResetFailed));
+        }
+        Ok(())
     }
 
-    /// Reset the RTC
-    pub async fn reset(&mut self) -> Result<(), Error<E>> {
-        self.set_register_bit_flag(Register::CONTROL_1, BitFlags::SR)
-            .await
+    /// Reset the RTC as `reset` does, then write the configuration staged by `configure`, so
+    /// the chip comes back in a known state.
+    ///
+    /// ```ignore
+    /// rtc.reset_with(|setup| setup.capacitor(CapacitorSelection::Pf12_5))
+    ///     .await?;
+    /// ```
+    pub async fn reset_with(
+        &mut self,
+        configure: impl for<'a> FnOnce(Setup<'a, I2C>) -> Setup<'a, I2C>,
+    ) -> Result<(), Error<E>> {
+        self.reset().await?;
+        configure(self.setup()).commit().await
     }
 
     /// Destroy driver instance, return I2C bus instance.
@@ -118,34 +582,83 @@ where
         self.i2c
     }
 
-    /// Let the device reset itself
+    /// Let the device reset itself, by writing the software reset pattern to control 1.
+    ///
+    /// Unlike `reset`, the result is not checked.
     pub async fn perform_software_reset(&mut self) -> Result<(), Error<E>> {
-        self.write_register(Register::CONTROL_1, 0b01011000).await
+        // the pattern sets reserved bits
+        self.write_bytes_unchecked(&[Register::CONTROL_1 as u8, SOFTWARE_RESET])
+            .await
     }
 
     /// Write to a register.
-    pub async fn write_register(&mut self, register: u8, data: u8) -> Result<(), Error<E>> {
-        let payload: [u8; 2] = [register, data];
-        self.i2c
-            .write(DEVICE_ADDRESS, &payload)
-            .await
-            .map_err(Error::I2C)
+    pub async fn write_register(&mut self, register: Register, data: u8) -> Result<(), Error<E>> {
+        let payload: [u8; 2] = [register.into(), data];
+        self.write_bytes(&payload).await
     }
 
     /// Read from a register.
-    pub async fn read_register(&mut self, register: u8) -> Result<u8, Error<E>> {
+    pub async fn read_register(&mut self, register: Register) -> Result<u8, Error<E>> {
         let mut data = [0];
-        self.i2c
-            .write_read(DEVICE_ADDRESS, &[register], &mut data)
-            .await
-            .map_err(Error::I2C)
-            .and(Ok(data[0]))
+        self.read_bytes(register, &mut data).await.and(Ok(data[0]))
+    }
+
+    /// Write a burst of bytes; the first byte is the address of the first register.
+    ///
+    /// With the `reserved-bit-checks` feature, this panics if the values set reserved bits.
+    async fn write_bytes(&mut self, payload: &[u8]) -> Result<(), Error<E>> {
+        #[cfg(feature = "reserved-bit-checks")]
+        if let Some((register, value)) = reserved_bits_set(payload) {
+            panic!("writing {value:#04x} to {register:?} sets reserved bits");
+        }
+        self.write_bytes_unchecked(payload).await
+    }
+
+    /// Write a burst of bytes without checking reserved bits, for the software reset pattern.
+    ///
+    /// All writes to the device go through here.
+    async fn write_bytes_unchecked(&mut self, payload: &[u8]) -> Result<(), Error<E>> {
+        self.cached.clear();
+        let result = self.i2c.write(DEVICE_ADDRESS, payload).await;
+
+        #[cfg(feature = "metrics")]
+        self.metrics.record(payload.len(), 0, result.is_ok());
+
+        #[cfg(feature = "access-log")]
+        if let Some((register, values)) = payload
+            .split_first()
+            .and_then(|(&address, values)| Some((Register::try_from(address).ok()?, values)))
+        {
+            self.access_log
+                .record(register, Direction::Write, values, result.is_ok());
+        }
+
+        result.map_err(Error::I2C)
+    }
+
+    /// Read a burst of consecutive registers, starting at `register`.
+    ///
+    /// All reads from the device go through here.
+    async fn read_bytes(&mut self, register: Register, data: &mut [u8]) -> Result<(), Error<E>> {
+        let result = self
+            .i2c
+            .write_read(DEVICE_ADDRESS, &[register.into()], data)
+            .await;
+
+        #[cfg(feature = "metrics")]
+        self.metrics.record(1, data.len(), result.is_ok());
+
+        #[cfg(feature = "access-log")]
+        self.access_log
+            .record(register, Direction::Read, data, result.is_ok());
+
+        result.map_err(Error::I2C)
     }
 
     /// Check if specific bits are set.
     pub async fn is_register_bit_flag_high(
         &mut self,
-        address: u8,
+        address: Register,
         bitmask: u8,
     ) -> Result<bool, Error<E>> {
         let data = self.read_register(address).await?;
@@ -155,7 +668,7 @@ where
     /// Set specific bits.
     pub async fn set_register_bit_flag(
         &mut self,
-        address: u8,
+        address: Register,
         bitmask: u8,
     ) -> Result<(), Error<E>> {
         let data = self.read_register(address).await?;
@@ -169,7 +682,7 @@ where
     /// Clear specific bits.
     pub async fn clear_register_bit_flag(
         &mut self,
-        address: u8,
+        address: Register,
         bitmask: u8,
     ) -> Result<(), Error<E>> {
         let data = self.read_register(address).await?;
@@ -181,19 +694,6 @@ where
     }
 }
 
-impl<I2C, E> PCF85063<I2C>
-where
-    I2C: I2c<Error = E>,
-{
-    pub async fn read_ram_byte(&mut self) -> Result<u8, Error<E>> {
-        self.read_register(Register::RAM_BYTE).await
-    }
-
-    pub async fn write_ram_byte(&mut self, byte: u8) -> Result<(), Error<E>> {
-        self.write_register(Register::RAM_BYTE, byte).await
-    }
-}
-
 impl<I2C, E> PCF85063<I2C>
 where
     I2C: I2c<Error = E>,
@@ -207,12 +707,68 @@ where
         self.clear_register_bit_flag(Register::CONTROL_1, BitFlags::STOP)
             .await
     }
+
+    /// Is the clock actually running?
+    ///
+    /// That is: the clock is not stopped, not in external clock test mode, and the oscillator
+    /// has not stopped since the oscillator stop flag was last cleared.
+    pub async fn is_running(&mut self) -> Result<bool, Error<E>> {
+        // control 1 up to and including seconds, in one go
+        let mut data = [0; 5];
+        self.read_bytes(Register::CONTROL_1, &mut data).await?;
+
+        Ok(data[0] & (BitFlags::STOP | BitFlags::EXT_TEST) == 0 && data[4] & BitFlags::OS == 0)
+    }
+
+    /// Stop the clock, run `f`, and start the clock again.
+    ///
+    /// The clock is started again even if `f` fails; the error of `f` takes precedence over
+    /// that of starting the clock.
+    ///
+    /// # Cancellation
+    ///
+    /// Stopping and starting the clock are separate transactions. If the returned future is
+    /// dropped in between, the clock is left stopped; call [`start_clock`](Self::start_clock)
+    /// to resume it.
+    pub async fn with_clock_stopped<T>(
+        &mut self,
+        f: impl AsyncFnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        self.stop_clock().await?;
+        let result = f(self).await;
+        let started = self.start_clock().await;
+        let value = result?;
+        started.map(|_| value)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Hour format of the hours registers (MODE_12_24 bit)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HourMode {
+    /// 24-hour format, the power-on default
+    #[default]
+    H24,
+    /// 12-hour format with AM/PM flag
+    H12,
+}
+
+/// Internal oscillator load capacitance (CAP_SEL bit)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CapacitorSelection {
+    /// 7 pF, the power-on default
+    #[default]
+    Pf7,
+    /// 12.5 pF
+    Pf12_5,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum OutputFrequency {
+    #[default]
     Hz32768 = 0b000,
     Hz16384 = 0b001,
     Hz8192 = 0b010,
@@ -223,16 +779,30 @@ pub enum OutputFrequency {
     Hz0 = 0b111,
 }
 
-impl Default for OutputFrequency {
-    fn default() -> Self {
-        OutputFrequency::Hz32768
-    }
-}
-
 impl OutputFrequency {
     pub const fn bits(self) -> u8 {
         self as u8
     }
+
+    /// The frequency in Hz, 0 for `Hz0` which turns CLKOUT off.
+    pub const fn hz(self) -> u32 {
+        match self {
+            OutputFrequency::Hz32768 => 32768,
+            OutputFrequency::Hz16384 => 16384,
+            OutputFrequency::Hz8192 => 8192,
+            OutputFrequency::Hz4096 => 4096,
+            OutputFrequency::Hz2048 => 2048,
+            OutputFrequency::Hz1024 => 1024,
+            OutputFrequency::Hz1 => 1,
+            OutputFrequency::Hz0 => 0,
+        }
+    }
+
+    /// Decode the COF bits of control 2; other bits are ignored.
+    pub const fn from_bits(bits: u8) -> Self {
+        // all eight values of the three COF bits are valid variants
+        unsafe { core::mem::transmute::<u8, OutputFrequency>(bits & BitFlags::COF) }
+    }
 }
 
 impl<I2C, E> PCF85063<I2C>
@@ -240,9 +810,9 @@ where
     I2C: I2c<Error = E>,
 {
     pub async fn read_clock_output_frequency(&mut self) -> Result<OutputFrequency, Error<E>> {
-        let value = self.read_register(Register::CONTROL_2).await? & BitFlags::COF;
+        let value = self.read_register(Register::CONTROL_2).await?;
 
-        Ok(unsafe { core::mem::transmute(value) })
+        Ok(OutputFrequency::from_bits(value))
     }
 
     pub async fn write_clock_output_frequency(
@@ -250,55 +820,71 @@ where
         freq: OutputFrequency,
     ) -> Result<(), Error<E>> {
         let value = self.read_register(Register::CONTROL_2).await?;
-        let cleared = value ^ BitFlags::COF;
+        let cleared = value & !BitFlags::COF;
         let set = cleared | freq as u8;
 
         self.write_register(Register::CONTROL_2, set).await
     }
 }
 
-/// Convert the Binary Coded Decimal value to decimal (only the lowest 7 bits).
-fn decode_bcd(input: u8) -> u8 {
-    let digits: u8 = input & 0xf;
-    let tens: u8 = (input >> 4) & 0x7;
-    10 * tens + digits
-}
-
-/// Convert the decimal value to Binary Coded Decimal.
-fn encode_bcd(input: u8) -> u8 {
-    let digits: u8 = input % 10;
-    let tens: u8 = input / 10;
-    let tens = tens << 4;
-    tens + digits
+/// The first register in a write payload whose value sets reserved bits, with that value.
+#[cfg(feature = "reserved-bit-checks")]
+fn reserved_bits_set(payload: &[u8]) -> Option<(Register, u8)> {
+    let (&address, values) = payload.split_first()?;
+    let first = Register::try_from(address).ok()?;
+    values.iter().enumerate().find_map(|(i, &value)| {
+        let register = first.offset(i as u8)?;
+        (value & register.reserved_bits() != 0).then_some((register, value))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "reserved-bit-checks")]
+    #[test]
+    fn finds_reserved_bits_in_writes() {
+        assert_eq!(
+            None,
+            reserved_bits_set(&[0x04, 0x80, 0x59, 0x23, 0x31, 0x06])
+        );
+        assert_eq!(
+            Some((Register::WEEKDAYS, 0x08)),
+            reserved_bits_set(&[0x06, 0x23, 0x31, 0x08])
+        );
+        assert_eq!(
+            Some((Register::CONTROL_1, SOFTWARE_RESET)),
+            reserved_bits_set(&[0x00, SOFTWARE_RESET])
+        );
+    }
+
+    #[test]
+    fn can_convert_register_addresses() {
+        for (address, register) in Register::ALL.into_iter().enumerate() {
+            assert_eq!(address as u8, u8::from(register));
+            assert_eq!(Ok(register), Register::try_from(address as u8));
+        }
+        assert_eq!(Err(OutOfRange), Register::try_from(0x12));
+        assert_eq!(Some(Register::YEARS), Register::SECONDS.offset(6));
+        assert_eq!(None, Register::TIMER_VALUE.offset(2));
+    }
+
     #[test]
-    fn can_convert_decode_bcd() {
-        assert_eq!(0, decode_bcd(0b0000_0000));
-        assert_eq!(1, decode_bcd(0b0000_0001));
-        assert_eq!(9, decode_bcd(0b0000_1001));
-        assert_eq!(10, decode_bcd(0b0001_0000));
-        assert_eq!(11, decode_bcd(0b0001_0001));
-        assert_eq!(19, decode_bcd(0b0001_1001));
-        assert_eq!(20, decode_bcd(0b0010_0000));
-        assert_eq!(21, decode_bcd(0b0010_0001));
-        assert_eq!(59, decode_bcd(0b0101_1001));
+    fn rejects_base_years_out_of_leap_year_step() {
+        assert!(PCF85063::new_with_base_year((), 1970).is_err());
+        assert!(PCF85063::new_with_base_year((), 2001).is_err());
+        let rtc = PCF85063::new_with_base_year((), 1972).unwrap();
+        assert_eq!(1972, rtc.base_year());
     }
 
     #[test]
-    fn can_convert_encode_bcd() {
-        assert_eq!(0b0000_0000, encode_bcd(0));
-        assert_eq!(0b0000_0001, encode_bcd(1));
-        assert_eq!(0b0000_1001, encode_bcd(9));
-        assert_eq!(0b0001_0000, encode_bcd(10));
-        assert_eq!(0b0001_0001, encode_bcd(11));
-        assert_eq!(0b0001_1001, encode_bcd(19));
-        assert_eq!(0b0010_0000, encode_bcd(20));
-        assert_eq!(0b0010_0001, encode_bcd(21));
-        assert_eq!(0b0101_1001, encode_bcd(59));
+    fn reduces_bus_errors_to_their_kind() {
+        let error: Error<ErrorKind> = Error::<ErrorKind>::I2C(ErrorKind::Bus).into_kind();
+        assert!(matches!(error, Error::I2C(ErrorKind::Bus)));
+        let error: Error<()> = Error::<ErrorKind>::Pin.map_bus(|_| ());
+        assert!(matches!(error, Error::Pin));
+        let error = Error::<ErrorKind>::Data(DataError::NotRunning).into_kind();
+        assert!(matches!(error, Error::Data(DataError::NotRunning)));
     }
 }
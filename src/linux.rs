@@ -0,0 +1,78 @@
+//! Talking to the chip from Linux through `/dev/i2c-*`, enabled with the `linux` feature.
+//!
+//! For single-board computers carrying this RTC, such as Raspberry Pi HATs. The driver's
+//! futures complete as soon as the blocking bus transfers do, so [`block_on`] suffices to run
+//! them:
+//!
+//! ```ignore
+//! let bus = LinuxI2c::open("/dev/i2c-1")?;
+//! let mut rtc = PCF85063::new(bus);
+//! let now = block_on(rtc.get_datetime())?;
+//! ```
+//!
+//! The `pcf85063a-cli` binary built with this feature reads and sets the time, dumps the registers
+//! and sets the offset correction.
+
+extern crate std;
+
+pub use super::block_on::block_on;
+use embedded_hal_async::i2c::{self, ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+use i2cdev::core::{I2CMessage, I2CTransfer};
+use i2cdev::linux::{LinuxI2CBus, LinuxI2CError, LinuxI2CMessage};
+use std::path::Path;
+use std::vec::Vec;
+
+/// Error of a [`LinuxI2c`] transfer
+#[derive(Debug)]
+pub struct Error(pub LinuxI2CError);
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl i2c::Error for Error {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// A Linux I2C bus, as the bus of the driver
+pub struct LinuxI2c {
+    bus: LinuxI2CBus,
+}
+
+impl LinuxI2c {
+    /// Open the bus at `path`, e.g. `/dev/i2c-1`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(LinuxI2c {
+            bus: LinuxI2CBus::new(path).map_err(Error)?,
+        })
+    }
+}
+
+impl ErrorType for LinuxI2c {
+    type Error = Error;
+}
+
+impl I2c for LinuxI2c {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut messages: Vec<LinuxI2CMessage> = operations
+            .iter_mut()
+            .map(|operation| match operation {
+                Operation::Read(buffer) => LinuxI2CMessage::read(buffer),
+                Operation::Write(bytes) => LinuxI2CMessage::write(bytes),
+            })
+            .map(|message| message.with_address(address.into()))
+            .collect();
+        self.bus.transfer(&mut messages).map_err(Error)?;
+        Ok(())
+    }
+}
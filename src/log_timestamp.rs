@@ -0,0 +1,60 @@
+//! The RTC as defmt timestamp source, enabled with the `defmt-timestamp` feature.
+//!
+//! This feature registers the global defmt timestamp, so log lines carry wall-clock time instead
+//! of ticks since boot. Only one crate in the firmware may do so: do not enable this feature when
+//! the application defines its own `defmt::timestamp!`.
+//!
+//! Reading the chip from the logger is not possible, so the timestamp is a cached Unix time in
+//! whole seconds, refreshed by calling [`PCF85063::update_log_timestamp`] (e.g. once per second
+//! from a maintenance task), or [`set_log_timestamp`] with a time from elsewhere.
+
+use super::{Error, PCF85063};
+use core::sync::atomic::{AtomicU32, Ordering};
+use embedded_hal_async::i2c::I2c;
+
+/// Cached Unix time in seconds, 0 until first set.
+static UNIX_SECONDS: AtomicU32 = AtomicU32::new(0);
+
+defmt::timestamp!(
+    "{=u64:iso8601s}",
+    UNIX_SECONDS.load(Ordering::Relaxed) as u64
+);
+
+/// Set the defmt log timestamp to `unix_seconds`.
+pub fn set_log_timestamp(unix_seconds: u32) {
+    UNIX_SECONDS.store(unix_seconds, Ordering::Relaxed);
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the time, assuming the chip keeps UTC, and use it as defmt log timestamp.
+    pub async fn update_log_timestamp(&mut self) -> Result<(), Error<E>> {
+        let unix_seconds = self.get_datetime().await?.assume_utc().unix_timestamp();
+        set_log_timestamp(unix_seconds.clamp(0, u32::MAX as i64) as u32);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use time::macros::datetime;
+
+    #[test]
+    fn caches_unix_time_of_chip() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 23:59:59)).await?;
+            rtc.update_log_timestamp().await
+        })
+        .unwrap();
+        assert_eq!(1_709_251_199, UNIX_SECONDS.load(Ordering::Relaxed));
+
+        set_log_timestamp(42);
+        assert_eq!(42, UNIX_SECONDS.load(Ordering::Relaxed));
+    }
+}
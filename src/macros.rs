@@ -0,0 +1,83 @@
+//! Literal macros for alarm times and dates, validated at compile time.
+
+#[doc(hidden)]
+pub mod __private {
+    pub use time;
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    pub const fn checked_time(hour: u8, minute: u8, second: u8) -> Time {
+        match Time::from_hms(hour, minute, second) {
+            Ok(time) => time,
+            Err(_) => panic!("invalid time"),
+        }
+    }
+
+    pub const fn checked_datetime(year: i32, month: u8, day: u8, time: Time) -> PrimitiveDateTime {
+        let month = match month {
+            1 => Month::January,
+            2 => Month::February,
+            3 => Month::March,
+            4 => Month::April,
+            5 => Month::May,
+            6 => Month::June,
+            7 => Month::July,
+            8 => Month::August,
+            9 => Month::September,
+            10 => Month::October,
+            11 => Month::November,
+            12 => Month::December,
+            _ => panic!("invalid month"),
+        };
+        match Date::from_calendar_date(year, month, day) {
+            Ok(date) => PrimitiveDateTime::new(date, time),
+            Err(_) => panic!("invalid date"),
+        }
+    }
+}
+
+/// An alarm time `hh:mm` or `hh:mm:ss`, as a `time::Time` checked at compile time.
+///
+/// ```
+/// let wake = pcf85063a::alarm!(07:30);
+/// # assert_eq!(time::macros::time!(07:30), wake);
+/// ```
+///
+/// ```compile_fail
+/// let nope = pcf85063a::alarm!(24:00);
+/// ```
+#[macro_export]
+macro_rules! alarm {
+    ($hour:literal : $minute:literal) => {
+        $crate::alarm!($hour:$minute:0)
+    };
+    ($hour:literal : $minute:literal : $second:literal) => {{
+        const TIME: $crate::macros::__private::time::Time =
+            $crate::macros::__private::checked_time($hour, $minute, $second);
+        TIME
+    }};
+}
+
+/// A date and time `yyyy-mm-dd hh:mm:ss`, as a `time::PrimitiveDateTime` checked at compile
+/// time.
+///
+/// ```
+/// let new_year = pcf85063a::at!(2025-01-01 00:00:00);
+/// # assert_eq!(time::macros::datetime!(2025-01-01 00:00:00), new_year);
+/// ```
+///
+/// ```compile_fail
+/// let nope = pcf85063a::at!(2025-02-29 00:00:00);
+/// ```
+#[macro_export]
+macro_rules! at {
+    ($year:literal - $month:literal - $day:literal $hour:literal : $minute:literal : $second:literal) => {{
+        const DATETIME: $crate::macros::__private::time::PrimitiveDateTime =
+            $crate::macros::__private::checked_datetime(
+                $year,
+                $month,
+                $day,
+                $crate::macros::__private::checked_time($hour, $minute, $second),
+            );
+        DATETIME
+    }};
+}
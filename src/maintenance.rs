@@ -0,0 +1,258 @@
+//! A turnkey driver task: servicing interrupts, re-arming the alarm and checking integrity.
+//!
+//! [`PCF85063::run`] owns the driver for the lifetime of the task and reports everything that
+//! happens to a callback:
+//!
+//! ```ignore
+//! let error = rtc
+//!     .run(&mut int_pin, &mut Delay, 60_000, |event| match event {
+//!         Event::Alarm(now) => Some(now + Duration::hours(1)), // hourly
+//!         _ => None,
+//!     })
+//!     .await;
+//! ```
+
+use super::{Error, PCF85063};
+use core::future::{poll_fn, Future};
+use core::pin::{pin, Pin};
+use core::task::Poll;
+use embedded_hal_async::{delay::DelayNs, digital::Wait, i2c::I2c};
+use time::PrimitiveDateTime;
+
+/// Something that happened, as reported by [`PCF85063::run`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The alarm fired; the date and time it was handled at
+    Alarm(PrimitiveDateTime),
+    /// The timer or a (half) minute interrupt fired
+    Timer,
+    /// The periodic check found the clock stopped, or its integrity lost
+    NotRunning,
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Service the chip until an error occurs, reporting events to `on_event`.
+    ///
+    /// Waits on the INT pin for the enabled interrupts, which are acknowledged before being
+    /// reported; for an alarm, `on_event` returns when the alarm should fire next, if ever:
+    /// with `None`, the alarms are disabled.
+    /// Every `check_interval_ms`, the clock is checked to be running; interrupts serviced in
+    /// between do not postpone the check.
+    ///
+    /// The INT line may be shared with other devices: interrupts that are not this chip's are
+    /// ignored until the line is released. A pin error is returned as 'Error::Pin'.
+    pub async fn run(
+        &mut self,
+        int: &mut impl Wait,
+        delay: &mut impl DelayNs,
+        check_interval_ms: u32,
+        mut on_event: impl FnMut(Event) -> Option<PrimitiveDateTime>,
+    ) -> Error<E> {
+        loop {
+            let mut check = pin!(delay.delay_ms(check_interval_ms));
+            loop {
+                match race(int.wait_for_low(), check.as_mut()).await {
+                    Some(Ok(())) => {}
+                    Some(Err(_)) => return Error::Pin,
+                    None => break,
+                }
+                if let Err(e) = self.service(int, &mut on_event).await {
+                    return e;
+                }
+                if is_done(check.as_mut()).await {
+                    break;
+                }
+            }
+            match self.is_running().await {
+                Ok(true) => {}
+                Ok(false) => {
+                    on_event(Event::NotRunning);
+                }
+                Err(e) => return e,
+            }
+        }
+    }
+
+    /// Acknowledge and report the interrupts pending with the INT pin low.
+    async fn service(
+        &mut self,
+        int: &mut impl Wait,
+        on_event: &mut impl FnMut(Event) -> Option<PrimitiveDateTime>,
+    ) -> Result<(), Error<E>> {
+        let pending = self.acknowledge_interrupt().await?;
+        if !pending.is_mine() {
+            return int.wait_for_high().await.map_err(|_| Error::Pin);
+        }
+        if pending.alarm {
+            let now = self.get_datetime().await?;
+            match on_event(Event::Alarm(now)) {
+                Some(next) => self.set_alarm_at(next).await?,
+                None => self.disable_all_alarms().await?,
+            }
+        }
+        if pending.timer {
+            on_event(Event::Timer);
+        }
+        Ok(())
+    }
+}
+
+/// Run `future`, unless `timeout` completes first.
+async fn race<T>(future: impl Future<Output = T>, timeout: impl Future<Output = ()>) -> Option<T> {
+    let mut future = pin!(future);
+    let mut timeout = pin!(timeout);
+
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        if timeout.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+/// Poll `future` once, telling whether it completed; it must not be polled after that.
+async fn is_done(mut future: Pin<&mut impl Future<Output = ()>>) -> bool {
+    poll_fn(|cx| Poll::Ready(future.as_mut().poll(cx).is_ready())).await
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, SimError, SimPin, Simulator};
+    use core::time::Duration;
+    use embedded_hal::digital::ErrorType;
+    use time::macros::datetime;
+
+    /// A delay that waits for the simulator's virtual time to pass, without passing it itself
+    struct Deadline<'a>(&'a Simulator);
+
+    impl Deadline<'_> {
+        async fn wait(&mut self, duration: Duration) {
+            let deadline = self.0.elapsed() + duration;
+            poll_fn(|_| {
+                if self.0.elapsed() >= deadline {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await
+        }
+    }
+
+    impl DelayNs for Deadline<'_> {
+        async fn delay_ns(&mut self, ns: u32) {
+            self.wait(Duration::from_nanos(ns.into())).await
+        }
+
+        async fn delay_ms(&mut self, ms: u32) {
+            self.wait(Duration::from_millis(ms.into())).await
+        }
+    }
+
+    /// The INT pin, failing after `left` more waits for it to go low
+    struct CountedPin<'a> {
+        pin: SimPin<'a>,
+        left: u32,
+    }
+
+    impl ErrorType for CountedPin<'_> {
+        type Error = SimError;
+    }
+
+    impl Wait for CountedPin<'_> {
+        async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_high().await
+        }
+
+        async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+            self.left = self.left.checked_sub(1).ok_or(SimError::NeverAsserted)?;
+            self.pin.wait_for_low().await
+        }
+
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_rising_edge().await
+        }
+
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_falling_edge().await
+        }
+
+        async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_any_edge().await
+        }
+    }
+
+    #[test]
+    fn checks_integrity_between_minute_ticks() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let (mut ticks, mut not_running) = (0, 0);
+        let error = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:00)).await?;
+            rtc.enable_minute_interrupt(true).await?;
+            simulator.brown_out(Duration::from_millis(10));
+            Ok::<_, Error<_>>(
+                rtc.run(
+                    &mut CountedPin {
+                        pin: simulator.int_pin(),
+                        left: 5,
+                    },
+                    &mut Deadline(&simulator),
+                    150_000,
+                    |event| {
+                        match event {
+                            Event::Timer => ticks += 1,
+                            Event::NotRunning => not_running += 1,
+                            Event::Alarm(_) => {}
+                        }
+                        None
+                    },
+                )
+                .await,
+            )
+        })
+        .unwrap();
+
+        assert!(matches!(error, Error::Pin));
+        assert_eq!(5, ticks);
+        assert_eq!(1, not_running);
+    }
+
+    #[test]
+    fn disables_alarm_without_next() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let mut alarms = 0;
+        let error = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:00)).await?;
+            rtc.set_alarm_at(datetime!(2024-02-29 12:00:30)).await?;
+            rtc.arm_alarm_interrupt().await?;
+            Ok::<_, Error<_>>(
+                rtc.run(
+                    &mut simulator.int_pin(),
+                    &mut simulator.delay(),
+                    60_000,
+                    |event| {
+                        alarms += matches!(event, Event::Alarm(_)) as u32;
+                        assert_eq!(1, alarms, "the alarm fired again");
+                        None
+                    },
+                )
+                .await,
+            )
+        })
+        .unwrap();
+
+        // the pin never went low again, within the 64 days the simulator waits
+        assert!(matches!(error, Error::Pin));
+        assert_eq!(1, alarms);
+    }
+}
@@ -0,0 +1,91 @@
+//! Bus traffic counters, enabled with the `metrics` feature.
+//!
+//! Every I2C transaction the driver performs is counted, so applications can see how much bus
+//! traffic (and therefore power and latency) their RTC usage costs.
+//!
+//! There is no retry counter: the driver never retries a failed transaction itself, but returns
+//! the bus error. Retries by the application show up as further transactions (and errors).
+
+use super::PCF85063;
+
+/// A snapshot of the driver's bus traffic counters.
+///
+/// All counters saturate instead of wrapping around.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Metrics {
+    /// Number of I2C transactions started, successful or not.
+    pub transactions: u32,
+    /// Number of I2C transactions that returned a bus error.
+    pub errors: u32,
+    /// Number of bytes written to the bus, including register addresses.
+    pub bytes_written: u32,
+    /// Number of bytes read from the bus.
+    pub bytes_read: u32,
+}
+
+impl Metrics {
+    pub(crate) const fn new() -> Self {
+        Metrics {
+            transactions: 0,
+            errors: 0,
+            bytes_written: 0,
+            bytes_read: 0,
+        }
+    }
+
+    /// Account for a single transaction.
+    pub(crate) fn record(&mut self, written: usize, read: usize, ok: bool) {
+        self.transactions = self.transactions.saturating_add(1);
+        if !ok {
+            self.errors = self.errors.saturating_add(1);
+        }
+        self.bytes_written = self.bytes_written.saturating_add(written as u32);
+        self.bytes_read = self.bytes_read.saturating_add(read as u32);
+    }
+}
+
+impl<I2C> PCF85063<I2C> {
+    /// Get a snapshot of the bus traffic counters.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    /// Reset all bus traffic counters to zero.
+    pub fn reset_metrics(&mut self) {
+        self.metrics = Metrics::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_transactions_and_errors() {
+        let mut metrics = Metrics::new();
+        metrics.record(2, 0, true);
+        metrics.record(1, 7, false);
+
+        assert_eq!(
+            Metrics {
+                transactions: 2,
+                errors: 1,
+                bytes_written: 3,
+                bytes_read: 7,
+            },
+            metrics
+        );
+    }
+
+    #[test]
+    fn counters_saturate() {
+        let mut metrics = Metrics {
+            transactions: u32::MAX,
+            ..Metrics::new()
+        };
+        metrics.record(1, 1, true);
+
+        assert_eq!(u32::MAX, metrics.transactions);
+    }
+}
@@ -0,0 +1,135 @@
+//! The I2C transactions of common driver operations, for testing code that uses the driver
+//! with `embedded-hal-mock`, enabled with the `test-support` feature.
+//!
+//! ```ignore
+//! use embedded_hal_mock::eh1::i2c::Mock;
+//! use pcf85063a::mock;
+//!
+//! let expectations = [
+//!     mock::get_datetime(&datetime!(2024-02-29 12:00:00), 2000),
+//!     mock::clear_alarm_flag(0b1100_0000),
+//! ]
+//! .concat();
+//! let mut i2c = Mock::new(&expectations);
+//! let mut rtc = PCF85063::new(i2c.clone());
+//!
+//! code_under_test(&mut rtc).await;
+//! i2c.done();
+//! ```
+//!
+//! The transactions are those of a driver with the default settings: no backwards limit, and
+//! no weekday check.
+
+extern crate std;
+
+use super::{codec, BitFlags, Register, DEVICE_ADDRESS};
+use embedded_hal_mock::eh1::i2c::Transaction;
+use std::{vec, vec::Vec};
+use time::PrimitiveDateTime;
+
+/// The I2C address of the chip.
+pub const ADDRESS: u8 = DEVICE_ADDRESS;
+
+/// `read_register` of `register`, which holds `value`.
+pub fn read_register(register: Register, value: u8) -> Vec<Transaction> {
+    vec![Transaction::write_read(
+        ADDRESS,
+        vec![register as u8],
+        vec![value],
+    )]
+}
+
+/// `write_register` of `value` to `register`.
+pub fn write_register(register: Register, value: u8) -> Vec<Transaction> {
+    vec![Transaction::write(ADDRESS, vec![register as u8, value])]
+}
+
+/// The time and date registers holding `datetime`, with the year counter relative to
+/// `base_year`.
+///
+/// Panics if `datetime` lies outside the hundred years starting at `base_year`.
+fn datetime_registers(datetime: &PrimitiveDateTime, base_year: i32) -> [u8; 7] {
+    codec::encode_datetime(datetime, base_year)
+        .expect("datetime outside the range of the base year")
+}
+
+/// `get_datetime`, returning `datetime`.
+///
+/// Panics if `datetime` lies outside the hundred years starting at `base_year`.
+pub fn get_datetime(datetime: &PrimitiveDateTime, base_year: i32) -> Vec<Transaction> {
+    vec![Transaction::write_read(
+        ADDRESS,
+        vec![Register::SECONDS as u8],
+        datetime_registers(datetime, base_year).to_vec(),
+    )]
+}
+
+/// `set_datetime` to `datetime`.
+///
+/// Panics if `datetime` lies outside the hundred years starting at `base_year`.
+pub fn set_datetime(datetime: &PrimitiveDateTime, base_year: i32) -> Vec<Transaction> {
+    let mut payload = vec![Register::SECONDS as u8];
+    payload.extend_from_slice(&datetime_registers(datetime, base_year));
+    vec![Transaction::write(ADDRESS, payload)]
+}
+
+/// `get_alarm_flag`, with control 2 holding `control_2`.
+pub fn get_alarm_flag(control_2: u8) -> Vec<Transaction> {
+    read_register(Register::CONTROL_2, control_2)
+}
+
+/// `clear_alarm_flag`, with control 2 holding `control_2`; it is only written if the flag is
+/// set.
+pub fn clear_alarm_flag(control_2: u8) -> Vec<Transaction> {
+    let mut transactions = read_register(Register::CONTROL_2, control_2);
+    if control_2 & BitFlags::AF != 0 {
+        transactions.extend(write_register(
+            Register::CONTROL_2,
+            control_2 & !BitFlags::AF,
+        ));
+    }
+    transactions
+}
+
+/// `arm_alarm_interrupt`, with control 2 holding `control_2`.
+pub fn arm_alarm_interrupt(control_2: u8) -> Vec<Transaction> {
+    let mut transactions = read_register(Register::CONTROL_2, control_2);
+    transactions.extend(write_register(
+        Register::CONTROL_2,
+        (control_2 | BitFlags::AIE | BitFlags::TF) & !BitFlags::AF,
+    ));
+    transactions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_on::block_on;
+    use crate::PCF85063;
+    use embedded_hal_mock::eh1::i2c::Mock;
+    use time::macros::datetime;
+
+    #[test]
+    fn matches_driver_transactions() {
+        let datetime = datetime!(2024-02-29 23:59:30);
+        let expectations = [
+            get_datetime(&datetime, 2000),
+            set_datetime(&datetime, 2000),
+            get_alarm_flag(0b0100_0000),
+            clear_alarm_flag(0b0100_1000),
+            clear_alarm_flag(0b0000_1000),
+            arm_alarm_interrupt(0b0100_0000),
+        ]
+        .concat();
+        let mut i2c = Mock::new(&expectations);
+        let mut rtc = PCF85063::new(i2c.clone());
+
+        assert_eq!(datetime, block_on(rtc.get_datetime()).unwrap());
+        block_on(rtc.set_datetime(&datetime)).unwrap();
+        assert!(block_on(rtc.get_alarm_flag()).unwrap());
+        block_on(rtc.clear_alarm_flag()).unwrap();
+        block_on(rtc.clear_alarm_flag()).unwrap();
+        block_on(rtc.arm_alarm_interrupt()).unwrap();
+        i2c.done();
+    }
+}
@@ -0,0 +1,126 @@
+//! Month and weekday names, so clock displays do not need their own lookup tables.
+//!
+//! English names are built in; other languages are supplied as [`Names`] tables:
+//!
+//! ```
+//! use pcf85063a::names::Names;
+//! use time::{Month, Weekday};
+//!
+//! const DUTCH: Names = Names::new(
+//!     ["jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+//!     ["ma", "di", "wo", "do", "vr", "za", "zo"],
+//! );
+//!
+//! assert_eq!("Mar", Names::ENGLISH_SHORT.month(Month::March));
+//! assert_eq!("do", DUTCH.weekday(Weekday::Thursday));
+//! ```
+
+use core::fmt;
+use time::{Date, Month, Weekday};
+
+/// Names of the months, January first, and of the weekdays, Monday first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Names {
+    months: [&'static str; 12],
+    weekdays: [&'static str; 7],
+}
+
+impl Names {
+    /// English abbreviations: "Jan", "Mon".
+    pub const ENGLISH_SHORT: Names = Names::new(
+        [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ],
+        ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    );
+
+    /// Full English names: "January", "Monday".
+    pub const ENGLISH: Names = Names::new(
+        [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+        [
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+        ],
+    );
+
+    /// Names from tables of the months, January first, and the weekdays, Monday first.
+    pub const fn new(months: [&'static str; 12], weekdays: [&'static str; 7]) -> Self {
+        Names { months, weekdays }
+    }
+
+    pub const fn month(&self, month: Month) -> &'static str {
+        self.months[month as usize - 1]
+    }
+
+    pub const fn weekday(&self, weekday: Weekday) -> &'static str {
+        self.weekdays[weekday.number_days_from_monday() as usize]
+    }
+
+    /// Write `date` as weekday, day, month and year, e.g. "Thu 29 Feb 2024".
+    pub fn write_date(&self, w: &mut impl fmt::Write, date: Date) -> fmt::Result {
+        write!(
+            w,
+            "{} {} {} {}",
+            self.weekday(date.weekday()),
+            date.day(),
+            self.month(date.month()),
+            date.year()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    struct Buffer {
+        bytes: [u8; 32],
+        len: usize,
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn can_write_dates() {
+        let mut buffer = Buffer {
+            bytes: [0; 32],
+            len: 0,
+        };
+        Names::ENGLISH_SHORT
+            .write_date(&mut buffer, date!(2024 - 02 - 29))
+            .unwrap();
+        assert_eq!(b"Thu 29 Feb 2024", &buffer.bytes[..buffer.len]);
+
+        assert_eq!("December", Names::ENGLISH.month(Month::December));
+        assert_eq!("Sunday", Names::ENGLISH.weekday(Weekday::Sunday));
+    }
+}
@@ -0,0 +1,233 @@
+//! Fan-out of the alarm and the time to several tasks, enabled with the `embassy` feature.
+//!
+//! The chip has a single alarm, and only one task can wait on the INT pin. That task publishes
+//! each alarm; every subscriber (a UI task, a logger, a power manager) receives it:
+//!
+//! ```ignore
+//! static ALARM: AlarmNotifier<CriticalSectionRawMutex, 1, 3> = AlarmNotifier::new();
+//!
+//! // in the task that owns the driver
+//! loop {
+//!     ALARM.wait_and_publish(&mut rtc, &mut int_pin).await?;
+//! }
+//!
+//! // in each subscribing task
+//! let mut alarms = ALARM.subscriber().unwrap();
+//! let fired_at = alarms.next_message_pure().await;
+//! ```
+//!
+//! Likewise [`TimeBroadcast`] keeps a cached, minute-fresh time that display and logging tasks
+//! read without touching the bus.
+
+use super::{Error, Tick, PCF85063};
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::pubsub::{self, PubSubChannel, Subscriber};
+use embassy_sync::watch::{Receiver, Watch};
+use embedded_hal_async::{digital::Wait, i2c::I2c};
+use time::PrimitiveDateTime;
+
+/// Publishes the date and time of each alarm to up to `SUBS` subscribers, each of which can
+/// lag `CAP` alarms behind before missing one.
+pub struct AlarmNotifier<M: RawMutex, const CAP: usize, const SUBS: usize> {
+    channel: PubSubChannel<M, PrimitiveDateTime, CAP, SUBS, 0>,
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize> Default for AlarmNotifier<M, CAP, SUBS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: RawMutex, const CAP: usize, const SUBS: usize> AlarmNotifier<M, CAP, SUBS> {
+    pub const fn new() -> Self {
+        AlarmNotifier {
+            channel: PubSubChannel::new(),
+        }
+    }
+
+    /// A new subscriber, or an error if all `SUBS` are taken.
+    pub fn subscriber(
+        &self,
+    ) -> Result<Subscriber<'_, M, PrimitiveDateTime, CAP, SUBS, 0>, pubsub::Error> {
+        self.channel.subscriber()
+    }
+
+    /// Notify all subscribers of an alarm at `datetime`.
+    ///
+    /// A subscriber that is `CAP` alarms behind misses the oldest one.
+    pub fn publish(&self, datetime: PrimitiveDateTime) {
+        self.channel
+            .immediate_publisher()
+            .publish_immediate(datetime);
+    }
+
+    /// Wait for the alarm on the INT pin, acknowledge it, and notify all subscribers of the
+    /// time it was acknowledged at, which is also returned.
+    ///
+    /// The alarm interrupt must be enabled. The other interrupts of the chip are acknowledged
+    /// and not published; an interrupt from another device on the INT line is waited out. The
+    /// INT pin is active low; a pin error is returned as 'Error::Pin'.
+    pub async fn wait_and_publish<I2C, E>(
+        &self,
+        rtc: &mut PCF85063<I2C>,
+        int: &mut impl Wait,
+    ) -> Result<PrimitiveDateTime, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+    {
+        loop {
+            int.wait_for_low().await.map_err(|_| Error::Pin)?;
+            let pending = rtc.acknowledge_interrupt().await?;
+            if pending.alarm {
+                break;
+            }
+            if !pending.is_mine() {
+                int.wait_for_high().await.map_err(|_| Error::Pin)?;
+            }
+        }
+
+        let datetime = rtc.get_datetime().await?;
+        self.publish(datetime);
+        Ok(datetime)
+    }
+}
+
+/// The current date and time, updated at the start of every minute, for up to `N` receivers.
+pub struct TimeBroadcast<M: RawMutex, const N: usize> {
+    watch: Watch<M, PrimitiveDateTime, N>,
+}
+
+impl<M: RawMutex, const N: usize> Default for TimeBroadcast<M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: RawMutex, const N: usize> TimeBroadcast<M, N> {
+    pub const fn new() -> Self {
+        TimeBroadcast {
+            watch: Watch::new(),
+        }
+    }
+
+    /// The most recently broadcast date and time, `None` before the first broadcast.
+    ///
+    /// The seconds are only updated once per minute, at the start of the minute.
+    pub fn latest(&self) -> Option<PrimitiveDateTime> {
+        self.watch.try_get()
+    }
+
+    /// A new receiver to await updates, or `None` if all `N` are taken.
+    pub fn receiver(&self) -> Option<Receiver<'_, M, PrimitiveDateTime, N>> {
+        self.watch.receiver()
+    }
+
+    /// Broadcast the date and time now and at the start of every minute, until an error
+    /// occurs.
+    ///
+    /// This enables the minute interrupt and waits for it on the INT pin, see
+    /// `wait_for_minute_tick`.
+    pub async fn run<I2C, E>(&self, rtc: &mut PCF85063<I2C>, int: &mut impl Wait) -> Error<E>
+    where
+        I2C: I2c<Error = E>,
+    {
+        loop {
+            match rtc.get_datetime().await {
+                Ok(datetime) => self.watch.sender().send(datetime),
+                Err(e) => return e,
+            }
+            if let Err(e) = rtc.wait_for_minute_tick(int, Tick::Minute).await {
+                return e;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, SimError, SimPin, Simulator};
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use embedded_hal::digital::ErrorType;
+    use time::macros::datetime;
+
+    /// The INT pin, failing after `left` more waits for it to go low
+    struct CountedPin<'a> {
+        pin: SimPin<'a>,
+        left: u32,
+    }
+
+    impl ErrorType for CountedPin<'_> {
+        type Error = SimError;
+    }
+
+    impl Wait for CountedPin<'_> {
+        async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_high().await
+        }
+
+        async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+            self.left = self.left.checked_sub(1).ok_or(SimError::NeverAsserted)?;
+            self.pin.wait_for_low().await
+        }
+
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_rising_edge().await
+        }
+
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_falling_edge().await
+        }
+
+        async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+            self.pin.wait_for_any_edge().await
+        }
+    }
+
+    #[test]
+    fn publishes_only_alarms() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let notifier = AlarmNotifier::<NoopRawMutex, 1, 1>::new();
+        let mut alarms = notifier.subscriber().unwrap();
+
+        let fired_at = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:10)).await?;
+            rtc.set_alarm_at(datetime!(2024-02-29 12:02:30)).await?;
+            rtc.arm_alarm_interrupt().await?;
+            rtc.enable_minute_interrupt(true).await?;
+            notifier
+                .wait_and_publish(&mut rtc, &mut simulator.int_pin())
+                .await
+        })
+        .unwrap();
+
+        assert_eq!(datetime!(2024-02-29 12:02:30), fired_at);
+        assert_eq!(Some(fired_at), alarms.try_next_message_pure());
+        assert_eq!(None, alarms.try_next_message_pure());
+    }
+
+    #[test]
+    fn broadcasts_every_minute() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let broadcast = TimeBroadcast::<NoopRawMutex, 1>::new();
+        let mut receiver = broadcast.receiver().unwrap();
+        let mut int = CountedPin {
+            pin: simulator.int_pin(),
+            left: 3,
+        };
+
+        assert_eq!(None, broadcast.latest());
+        let error = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:10))
+                .await
+                .unwrap();
+            broadcast.run(&mut rtc, &mut int).await
+        });
+
+        assert!(matches!(error, Error::Pin));
+        assert_eq!(Some(datetime!(2024-02-29 12:03:00)), broadcast.latest());
+        assert_eq!(Some(datetime!(2024-02-29 12:03:00)), receiver.try_changed());
+    }
+}
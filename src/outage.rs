@@ -0,0 +1,113 @@
+//! Estimating how long the device was without main power.
+//!
+//! While running, the firmware periodically records a "last seen alive" time in persistent
+//! storage. At boot, the time elapsed since that record is the length of the outage, give or
+//! take the recording interval, as long as the clock kept running on its backup supply.
+
+use super::persist::{crc8, PersistError, PersistentStorage};
+use super::{DataError, Error, RawDateTime, Register, RegisterBytes, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{Duration, PrimitiveDateTime};
+
+/// Number of bytes taken by a "last seen alive" record.
+pub const ALIVE_RECORD_LEN: usize = 10;
+
+const MAGIC: u8 = 0xa1;
+
+/// Outcome of the outage estimate at boot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outage {
+    /// Time since the device was last seen alive
+    Lasted(Duration),
+    /// The clock stopped as well, so the length of the outage is unknown
+    ClockStopped,
+    /// Nothing was recorded yet, or the record is corrupted
+    NoRecord,
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Record the current time as "last seen alive" at `address` in `storage`, returning it.
+    ///
+    /// Call this periodically; the interval bounds the accuracy of the estimate, and should be
+    /// chosen with the write endurance of the storage in mind.
+    pub async fn record_alive<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+    ) -> Result<PrimitiveDateTime, PersistError<Error<E>, S::Error>> {
+        let now = self.get_datetime().await.map_err(PersistError::Rtc)?;
+        storage
+            .write(address, &alive_record(now))
+            .await
+            .map_err(PersistError::Storage)?;
+        Ok(now)
+    }
+
+    /// Estimate how long the device was without main power, from the record at `address` in
+    /// `storage`.
+    pub async fn estimate_outage<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+    ) -> Result<Outage, PersistError<Error<E>, S::Error>> {
+        let mut data = [0; 7];
+        self.read_bytes(Register::SECONDS, &mut data)
+            .await
+            .map_err(PersistError::Rtc)?;
+        let invalid = || {
+            let bytes = RegisterBytes::new(Register::SECONDS, &data);
+            PersistError::Rtc(Error::Data(DataError::InvalidRegisters(bytes)))
+        };
+        let raw = RawDateTime::try_from(data).map_err(|_| invalid())?;
+        if raw.oscillator_stopped {
+            return Ok(Outage::ClockStopped);
+        }
+        let now = raw.to_datetime(self.base_year).map_err(|_| invalid())?;
+
+        let mut record = [0; ALIVE_RECORD_LEN];
+        storage
+            .read(address, &mut record)
+            .await
+            .map_err(PersistError::Storage)?;
+        Ok(match last_alive(&record) {
+            Some(last_alive) => Outage::Lasted(now - last_alive),
+            None => Outage::NoRecord,
+        })
+    }
+}
+
+/// Encode a "last seen alive" record.
+fn alive_record(datetime: PrimitiveDateTime) -> [u8; ALIVE_RECORD_LEN] {
+    let mut record = [0; ALIVE_RECORD_LEN];
+    record[0] = MAGIC;
+    record[1..9].copy_from_slice(&datetime.assume_utc().unix_timestamp().to_le_bytes());
+    record[9] = crc8(&record[..9]);
+    record
+}
+
+/// Decode a "last seen alive" record, `None` if it is blank or corrupted.
+fn last_alive(record: &[u8; ALIVE_RECORD_LEN]) -> Option<PrimitiveDateTime> {
+    if record[0] != MAGIC || record[9] != crc8(&record[..9]) {
+        return None;
+    }
+    let mut timestamp = [0; 8];
+    timestamp.copy_from_slice(&record[1..9]);
+    let datetime = time::OffsetDateTime::from_unix_timestamp(i64::from_le_bytes(timestamp)).ok()?;
+    Some(PrimitiveDateTime::new(datetime.date(), datetime.time()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn can_round_trip_alive_records() {
+        let datetime = datetime!(2024-02-29 23:30:59);
+        assert_eq!(Some(datetime), last_alive(&alive_record(datetime)));
+        assert_eq!(None, last_alive(&[0xff; ALIVE_RECORD_LEN]));
+    }
+}
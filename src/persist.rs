@@ -0,0 +1,304 @@
+//! Persisting calibration, the last synchronization and the recurring alarm to external
+//! storage, such as an EEPROM or a flash page.
+//!
+//! The offset register, the time of the last synchronization with a reference and the alarm
+//! are lost along with the backup supply; saving them elsewhere lets firmware restore them at
+//! boot. The alarm is the chip's recurrence rule: a fixed minute and second with the other
+//! fields disabled fires every hour, for example. The record carries a checksum, so a blank or
+//! corrupted storage area is detected rather than restored.
+
+use super::{Alarm, Error, Register, ALARM_LEN, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+/// Byte-addressable persistent storage.
+///
+/// The methods match those of `embedded_storage::Storage`, which implements this trait when
+/// the `embedded-storage` feature is enabled.
+#[allow(async_fn_in_trait)]
+pub trait PersistentStorage {
+    type Error;
+
+    /// Read `bytes.len()` bytes starting at `offset`.
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Write `bytes` starting at `offset`, erasing as needed.
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "embedded-storage")]
+impl<S: embedded_storage::Storage> PersistentStorage for S {
+    type Error = S::Error;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        embedded_storage::ReadStorage::read(self, offset, bytes)
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        embedded_storage::Storage::write(self, offset, bytes)
+    }
+}
+
+/// Number of bytes taken by a saved record.
+pub const RECORD_LEN: usize = 21;
+
+const MAGIC: u8 = 0x85;
+const VERSION: u8 = 1;
+const HAS_LAST_SYNC: u8 = 0b0000_0001;
+const HAS_ALARM: u8 = 0b0000_0010;
+
+/// The values saved to, and restored from, persistent storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Persisted {
+    /// Contents of the offset register
+    pub offset: u8,
+    /// When the clock was last set from a reference
+    pub last_sync: Option<PrimitiveDateTime>,
+    /// The alarm, `None` when no field is enabled
+    pub alarm: Option<Alarm>,
+}
+
+impl Persisted {
+    /// Encode as a record with a checksum.
+    ///
+    /// An alarm with a field out of range cannot be restored, and is left out.
+    pub fn to_record(&self) -> [u8; RECORD_LEN] {
+        let mut record = [0; RECORD_LEN];
+        record[0] = MAGIC;
+        record[1] = VERSION;
+        record[2] = self.offset;
+        if let Some(last_sync) = self.last_sync {
+            record[3] = HAS_LAST_SYNC;
+            record[4..12].copy_from_slice(&last_sync.assume_utc().unix_timestamp().to_le_bytes());
+        }
+        if let Some(alarm) = self.alarm.and_then(|alarm| alarm.to_bytes()) {
+            record[3] |= HAS_ALARM;
+            record[12..20].copy_from_slice(&alarm);
+        }
+        record[20] = crc8(&record[..20]);
+        record
+    }
+
+    /// Decode a record, `None` if it is blank, corrupted or of another version.
+    pub fn from_record(record: &[u8; RECORD_LEN]) -> Option<Self> {
+        if record[0] != MAGIC || record[1] != VERSION || record[20] != crc8(&record[..20]) {
+            return None;
+        }
+        let last_sync = if record[3] & HAS_LAST_SYNC != 0 {
+            let mut timestamp = [0; 8];
+            timestamp.copy_from_slice(&record[4..12]);
+            let datetime =
+                OffsetDateTime::from_unix_timestamp(i64::from_le_bytes(timestamp)).ok()?;
+            Some(PrimitiveDateTime::new(datetime.date(), datetime.time()))
+        } else {
+            None
+        };
+        let alarm = if record[3] & HAS_ALARM != 0 {
+            let mut alarm = [0; ALARM_LEN];
+            alarm.copy_from_slice(&record[12..20]);
+            Some(Alarm::from_bytes(&alarm)?)
+        } else {
+            None
+        };
+        Some(Persisted {
+            offset: record[2],
+            last_sync,
+            alarm,
+        })
+    }
+}
+
+/// Error of saving or restoring persisted values.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PersistError<R, S> {
+    /// Accessing the chip failed
+    Rtc(R),
+    /// Accessing the storage failed
+    Storage(S),
+    /// The storage holds no valid record
+    Corrupt,
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Save the offset register, the time of the last synchronization and the alarm to
+    /// `storage`, as a record of `RECORD_LEN` bytes at `address`.
+    pub async fn save_settings<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+        last_sync: Option<PrimitiveDateTime>,
+    ) -> Result<(), PersistError<Error<E>, S::Error>> {
+        let offset = self
+            .read_register(Register::OFFSET)
+            .await
+            .map_err(PersistError::Rtc)?;
+        let alarm = self.get_alarm().await.map_err(PersistError::Rtc)?;
+        let enabled = [
+            alarm.seconds,
+            alarm.minutes,
+            alarm.hours,
+            alarm.day,
+            alarm.weekday,
+        ]
+        .iter()
+        .any(Option::is_some);
+        let record = Persisted {
+            offset,
+            last_sync,
+            alarm: enabled.then_some(alarm),
+        }
+        .to_record();
+        storage
+            .write(address, &record)
+            .await
+            .map_err(PersistError::Storage)
+    }
+
+    /// Restore the offset register and the alarm from the record at `address` in `storage`,
+    /// and return the saved values.
+    ///
+    /// The alarm is only written when one was saved, along with its interrupt enable bit. Will
+    /// return a 'PersistError::Corrupt' if there is no valid record; the chip is left unchanged
+    /// then.
+    pub async fn restore_settings<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+    ) -> Result<Persisted, PersistError<Error<E>, S::Error>> {
+        let mut record = [0; RECORD_LEN];
+        storage
+            .read(address, &mut record)
+            .await
+            .map_err(PersistError::Storage)?;
+        let persisted = Persisted::from_record(&record).ok_or(PersistError::Corrupt)?;
+        self.write_register(Register::OFFSET, persisted.offset)
+            .await
+            .map_err(PersistError::Rtc)?;
+        if let Some(alarm) = &persisted.alarm {
+            self.set_alarm(alarm).await.map_err(PersistError::Rtc)?;
+        }
+        Ok(persisted)
+    }
+}
+
+/// CRC-8 with polynomial 0x07.
+pub(crate) fn crc8(data: &[u8]) -> u8 {
+    let mut crc: u8 = 0;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn can_round_trip_records() {
+        let persisted = Persisted {
+            offset: 0x85,
+            last_sync: Some(datetime!(2024-02-29 23:30:59)),
+            alarm: Some(Alarm {
+                seconds: Some(0),
+                minutes: Some(15),
+                interrupt: true,
+                ..Alarm::default()
+            }),
+        };
+        let record = persisted.to_record();
+        assert_eq!(Some(persisted), Persisted::from_record(&record));
+
+        let persisted = Persisted {
+            offset: 0,
+            last_sync: None,
+            alarm: None,
+        };
+        assert_eq!(
+            Some(persisted),
+            Persisted::from_record(&persisted.to_record())
+        );
+    }
+
+    #[test]
+    fn rejects_blank_and_corrupted_records() {
+        assert_eq!(None, Persisted::from_record(&[0xff; RECORD_LEN]));
+
+        let mut record = Persisted {
+            offset: 3,
+            last_sync: None,
+            alarm: None,
+        }
+        .to_record();
+        record[2] = 4;
+        assert_eq!(None, Persisted::from_record(&record));
+    }
+
+    #[test]
+    fn computes_crc8() {
+        assert_eq!(0xf4, crc8(b"123456789"));
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod storage_tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use core::convert::Infallible;
+
+    struct Eeprom([u8; 64]);
+
+    impl PersistentStorage for Eeprom {
+        type Error = Infallible;
+
+        async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            let offset = offset as usize;
+            self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn restores_alarm_after_power_loss() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let mut eeprom = Eeprom([0xff; 64]);
+        let hourly = Alarm {
+            seconds: Some(0),
+            minutes: Some(30),
+            interrupt: true,
+            ..Alarm::default()
+        };
+
+        block_on(async {
+            rtc.set_alarm(&hourly).await.unwrap();
+            rtc.save_settings(&mut eeprom, 8, None).await.unwrap();
+        });
+        simulator.power_loss();
+        let (persisted, alarm) = block_on(async {
+            let persisted = rtc.restore_settings(&mut eeprom, 8).await.unwrap();
+            (persisted, rtc.get_alarm().await.unwrap())
+        });
+
+        assert_eq!(Some(hourly), persisted.alarm);
+        assert_eq!(hourly, alarm);
+    }
+}
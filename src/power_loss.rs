@@ -0,0 +1,73 @@
+//! Telling a full power loss apart from a clock that merely stopped.
+//!
+//! When both the main and the backup supply dropped out, the chip comes back with all registers
+//! at their power-on defaults, and any configuration must be written again. When only the
+//! oscillator stopped, e.g. during a brown-out, the time is invalid but the configuration was
+//! retained. A chip configured to exactly the power-on defaults cannot be told apart from one
+//! that lost power, which is harmless: writing the configuration again changes nothing.
+
+use super::{BitFlags, Error, Register, PCF85063};
+use embedded_hal_async::i2c::I2c;
+
+/// Power-on defaults of control 1 up to and including the RAM byte.
+const CONTROL_DEFAULTS: [u8; 4] = [0x00, 0x00, 0x00, 0x00];
+/// Power-on defaults of the five alarm registers, the timer value and timer mode.
+const ALARM_TIMER_DEFAULTS: [u8; 7] = [0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x18];
+
+/// What the chip went through, as returned by [`PCF85063::power_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerState {
+    /// The oscillator kept running: time and configuration are valid.
+    Intact,
+    /// The oscillator stopped, but the configuration was retained: only the time must be set.
+    TimeInvalid,
+    /// All registers hold their power-on defaults: time and configuration must be set.
+    PowerLost,
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Find out whether the chip lost power, reading all registers in one transaction.
+    pub async fn power_state(&mut self) -> Result<PowerState, Error<E>> {
+        let mut registers = [0; Register::ALL.len()];
+        self.read_bytes(Register::CONTROL_1, &mut registers).await?;
+        Ok(power_state(&registers))
+    }
+}
+
+fn power_state(registers: &[u8; Register::ALL.len()]) -> PowerState {
+    if registers[Register::SECONDS as usize] & BitFlags::OS == 0 {
+        return PowerState::Intact;
+    }
+    // the time registers count on after a reset, so only the others are compared
+    let control = &registers[..=Register::RAM_BYTE as usize];
+    let alarm_timer = &registers[Register::SECOND_ALARM as usize..];
+    if control == CONTROL_DEFAULTS && alarm_timer == ALARM_TIMER_DEFAULTS {
+        PowerState::PowerLost
+    } else {
+        PowerState::TimeInvalid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_power_on_defaults() {
+        let mut registers = [
+            0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x01, 0x06, 0x01, 0x00, 0x80, 0x80, 0x80,
+            0x80, 0x80, 0x00, 0x18,
+        ];
+        assert_eq!(PowerState::PowerLost, power_state(&registers));
+
+        registers[Register::CONTROL_2 as usize] = BitFlags::AIE;
+        assert_eq!(PowerState::TimeInvalid, power_state(&registers));
+
+        registers[Register::SECONDS as usize] = 0x12;
+        assert_eq!(PowerState::Intact, power_state(&registers));
+    }
+}
@@ -0,0 +1,14 @@
+//! The commonly used items of this crate, for a single glob import.
+//!
+//! ```
+//! use pcf85063a::prelude::*;
+//! ```
+
+pub use crate::timer::TimerClock;
+pub use crate::units::{DayOfMonth, Hours, Minutes, Seconds};
+pub use crate::{alarm, at};
+pub use crate::{
+    CapacitorSelection, Chip, ChipStatus, DataError, Error, Event, HourMode, Interrupts,
+    OutputFrequency, PersistentStorage, PowerState, Rtc, Setup, Tick, UsageError, WakeTarget,
+    PCF85063,
+};
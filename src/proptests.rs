@@ -0,0 +1,109 @@
+//! Property tests of the round trips through the register encoding and the simulated chip, run
+//! with the `property-tests` feature.
+//!
+//! The generated dates and times lean towards the boundaries where BCD and masking mistakes
+//! show: the last seconds of a day, the ends of months and the wrap of the year counter.
+
+use crate::simulator::{block_on, Simulator};
+use crate::{bcd, OffsetMode, RawDateTime, PCF85063};
+use core::time::Duration;
+use proptest::prelude::*;
+use time::{Date, Month, PrimitiveDateTime, Time};
+
+/// Dates and times within the hundred years from 2000, mostly near a boundary.
+fn datetime() -> impl Strategy<Value = PrimitiveDateTime> {
+    let year = prop_oneof![2000..=2099, Just(2099)];
+    let day = prop_oneof![1..=31u8, 28..=31u8];
+    let second_of_day = prop_oneof![0..86_400u32, 86_340..86_400u32];
+    (year, 1..=12u8, day, second_of_day).prop_filter_map(
+        "no such date",
+        |(year, month, day, second)| {
+            let date = Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()?;
+            let time = Time::from_hms(
+                (second / 3600) as u8,
+                (second / 60 % 60) as u8,
+                (second % 60) as u8,
+            )
+            .ok()?;
+            Some(PrimitiveDateTime::new(date, time))
+        },
+    )
+}
+
+/// `datetime` advanced by `seconds`, as the chip counts: the year counter wraps after 99.
+fn counted(datetime: PrimitiveDateTime, seconds: u32) -> PrimitiveDateTime {
+    let next = datetime + time::Duration::seconds(seconds.into());
+    if next.year() > 2099 {
+        next.replace_year(next.year() - 100).unwrap()
+    } else {
+        next
+    }
+}
+
+proptest! {
+    #[test]
+    fn bcd_round_trips(value in 0..=99u8) {
+        prop_assert_eq!(value, bcd::decode(bcd::encode(value)));
+        prop_assert_eq!(Some(value), bcd::decode_checked(bcd::encode(value)));
+    }
+
+    #[test]
+    fn registers_round_trip(datetime in datetime(), base_year in -100..=100i32) {
+        let base_year = 2000 + base_year;
+        let raw = RawDateTime::from_datetime(&datetime, base_year);
+        prop_assume!(raw.is_ok());
+        let bytes = <[u8; 7]>::from(raw.unwrap());
+        let decoded = RawDateTime::try_from(bytes).unwrap();
+        prop_assert_eq!(decoded, RawDateTime::from_bytes_unchecked(bytes));
+        prop_assert_eq!(datetime, decoded.to_datetime(base_year).unwrap());
+    }
+}
+
+proptest! {
+    // counting days of virtual time second by second is slow
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn chip_keeps_and_counts_time(datetime in datetime(), seconds in 0..200_000u32) {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        rtc.set_weekday_check(true);
+
+        block_on(async {
+            rtc.set_datetime(&datetime).await.unwrap();
+            prop_assert_eq!(datetime, rtc.get_datetime().await.unwrap());
+
+            simulator.advance(Duration::from_secs(seconds.into()));
+            let expected = counted(datetime, seconds);
+            // the weekday counts on across the wrap, where the chip's calendar is off
+            if expected.year() >= datetime.year() {
+                prop_assert_eq!(expected, rtc.get_datetime().await.unwrap());
+            } else {
+                prop_assert_eq!(expected, rtc.get_datetime_unchecked().await.unwrap());
+            }
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn alarm_and_offset_round_trip(
+        at in datetime(),
+        coarse in any::<bool>(),
+        offset in -64..=63i8,
+    ) {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let mode = if coarse { OffsetMode::Coarse } else { OffsetMode::Normal };
+
+        block_on(async {
+            rtc.set_alarm_at(at).await.unwrap();
+            prop_assert_eq!(at.time(), rtc.get_alarm_time().await.unwrap());
+            prop_assert_eq!(Some(at.day()), rtc.get_alarm_day().await.unwrap());
+            prop_assert_eq!(None, rtc.get_alarm_weekday().await.unwrap());
+
+            rtc.set_offset(mode, offset).await.unwrap();
+            prop_assert_eq!((mode, offset), rtc.get_offset().await.unwrap());
+            Ok(())
+        })?;
+    }
+}
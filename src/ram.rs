@@ -0,0 +1,211 @@
+//! Access to the single battery-backed RAM byte.
+//!
+//! In practice the byte is shared between several small flags and counters, so besides reading
+//! and writing it whole, individual bits and bit fields can be read and modified. A field is
+//! described by a mask of contiguous bits, e.g. `0b0011_0000` for a two-bit field; values are
+//! given and returned shifted down, so that field holds values 0 to 3.
+//!
+//! On top of that, a field can hold a boot counter or a wake reason: an application enum that
+//! tells stateless deep-sleep firmware what to resume after an RTC wake.
+
+use super::{codec, Error, Register, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    pub async fn read_ram_byte(&mut self) -> Result<u8, Error<E>> {
+        self.read_register(Register::RAM_BYTE).await
+    }
+
+    pub async fn write_ram_byte(&mut self, byte: u8) -> Result<(), Error<E>> {
+        self.write_register(Register::RAM_BYTE, byte).await
+    }
+
+    /// Read date and time and the RAM byte in one transaction, for waking from deep sleep.
+    ///
+    /// The RAM byte directly precedes the time registers, so this costs one burst of eight
+    /// bytes instead of two transactions. The date and time are checked as with
+    /// `get_datetime`.
+    pub async fn get_datetime_and_ram(&mut self) -> Result<(PrimitiveDateTime, u8), Error<E>> {
+        let mut data = [0; 8];
+        self.read_bytes(Register::RAM_BYTE, &mut data).await?;
+
+        let [ram, time @ ..] = data;
+        let datetime = codec::decode_datetime(time, self.base_year, self.check_weekday)
+            .map_err(Error::Data)?;
+        Ok((datetime, ram))
+    }
+
+    /// Read bit `n` [0-7] of the RAM byte.
+    pub async fn ram_bit(&mut self, n: u8) -> Result<bool, Error<E>> {
+        Ok(self.ram_bits(bit_mask(n)?).await? != 0)
+    }
+
+    /// Set bit `n` [0-7] of the RAM byte, keeping the other bits unchanged.
+    pub async fn set_ram_bit(&mut self, n: u8, value: bool) -> Result<(), Error<E>> {
+        self.set_ram_bits(bit_mask(n)?, value as u8).await
+    }
+
+    /// Read the field of the RAM byte selected by `mask`.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the mask is empty or not contiguous.
+    pub async fn ram_bits(&mut self, mask: u8) -> Result<u8, Error<E>> {
+        if !is_field_mask(mask) {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        let byte = self.read_ram_byte().await?;
+        Ok(extract_field(byte, mask))
+    }
+
+    /// Write `value` into the field of the RAM byte selected by `mask`, keeping the other bits
+    /// unchanged.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the mask is empty or not contiguous, or if
+    /// the value does not fit in the field.
+    pub async fn set_ram_bits(&mut self, mask: u8, value: u8) -> Result<(), Error<E>> {
+        if !is_field_mask(mask) {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        let byte = self.read_ram_byte().await?;
+        let updated =
+            insert_field(byte, mask, value).ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        if updated != byte {
+            self.write_ram_byte(updated).await?;
+        }
+        Ok(())
+    }
+
+    /// Increment the boot counter kept in the field of the RAM byte selected by `mask`,
+    /// returning the new count.
+    ///
+    /// The counter wraps around to zero once the field is full, e.g. after 15 for a four-bit
+    /// field; the other bits of the RAM byte are left unchanged.
+    pub async fn increment_boot_counter(&mut self, mask: u8) -> Result<u8, Error<E>> {
+        if !is_field_mask(mask) {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        let byte = self.read_ram_byte().await?;
+        let count = increment_field(byte, mask);
+        self.write_ram_byte(count).await?;
+        Ok(extract_field(count, mask))
+    }
+
+    /// Reset the boot counter kept in the field of the RAM byte selected by `mask` to zero.
+    pub async fn reset_boot_counter(&mut self, mask: u8) -> Result<(), Error<E>> {
+        self.set_ram_bits(mask, 0).await
+    }
+
+    /// Record the reason for going to sleep, or the action to take on wake, in the field of the
+    /// RAM byte selected by `mask`.
+    ///
+    /// The value 0 is reserved for "nothing recorded", so the reason must convert to a non-zero
+    /// value that fits in the field; otherwise a 'UsageError::InvalidInputData' is returned.
+    pub async fn set_wake_reason<R: Into<u8>>(
+        &mut self,
+        mask: u8,
+        reason: R,
+    ) -> Result<(), Error<E>> {
+        match reason.into() {
+            0 => Err(Error::Usage(UsageError::InvalidInputData)),
+            value => self.set_ram_bits(mask, value).await,
+        }
+    }
+
+    /// Retrieve the wake reason recorded in the field of the RAM byte selected by `mask`, and
+    /// clear it so it is not seen again on a later wake.
+    ///
+    /// Returns `None` if nothing was recorded, or the stored value is not a valid `R`.
+    pub async fn take_wake_reason<R: TryFrom<u8>>(
+        &mut self,
+        mask: u8,
+    ) -> Result<Option<R>, Error<E>> {
+        let value = self.ram_bits(mask).await?;
+        if value == 0 {
+            return Ok(None);
+        }
+        self.set_ram_bits(mask, 0).await?;
+        Ok(R::try_from(value).ok())
+    }
+}
+
+/// The mask selecting only bit `n`.
+fn bit_mask<E>(n: u8) -> Result<u8, Error<E>> {
+    if n > 7 {
+        return Err(Error::Usage(UsageError::InvalidInputData));
+    }
+    Ok(1 << n)
+}
+
+/// Is `mask` a non-empty run of contiguous bits?
+pub(crate) fn is_field_mask(mask: u8) -> bool {
+    if mask == 0 {
+        return false;
+    }
+    let shifted = mask >> mask.trailing_zeros();
+    shifted & shifted.wrapping_add(1) == 0
+}
+
+/// Get the field selected by `mask` out of `byte`, shifted down.
+pub(crate) fn extract_field(byte: u8, mask: u8) -> u8 {
+    (byte & mask) >> mask.trailing_zeros()
+}
+
+/// Replace the field selected by `mask` in `byte` by `value`, if the value fits.
+pub(crate) fn insert_field(byte: u8, mask: u8, value: u8) -> Option<u8> {
+    let shifted = value << mask.trailing_zeros();
+    if shifted >> mask.trailing_zeros() != value || shifted & !mask != 0 {
+        return None;
+    }
+    Some((byte & !mask) | shifted)
+}
+
+/// Increment the field selected by `mask` in `byte`, wrapping around within the field.
+fn increment_field(byte: u8, mask: u8) -> u8 {
+    let max = mask >> mask.trailing_zeros();
+    let next = match extract_field(byte, mask) {
+        value if value == max => 0,
+        value => value + 1,
+    };
+    (byte & !mask) | (next << mask.trailing_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_field_masks() {
+        assert!(is_field_mask(0b0000_0001));
+        assert!(is_field_mask(0b0011_1000));
+        assert!(is_field_mask(0b1111_1111));
+        assert!(!is_field_mask(0b0000_0000));
+        assert!(!is_field_mask(0b0101_0000));
+    }
+
+    #[test]
+    fn can_extract_and_insert_fields() {
+        assert_eq!(0b11, extract_field(0b1010_0110, 0b0000_0110));
+        assert_eq!(0b101, extract_field(0b1010_0110, 0b1110_0000));
+
+        assert_eq!(
+            Some(0b1011_1110),
+            insert_field(0b1010_0110, 0b0001_1000, 0b11)
+        );
+        assert_eq!(
+            Some(0b0110_0110),
+            insert_field(0b1010_0110, 0b1110_0000, 0b011)
+        );
+        assert_eq!(None, insert_field(0b1010_0110, 0b0001_1000, 0b100));
+        assert_eq!(None, insert_field(0b1010_0110, 0b1000_0000, 0b10));
+    }
+
+    #[test]
+    fn counter_wraps_within_field() {
+        assert_eq!(0b1001_0110, increment_field(0b1000_0110, 0b0011_0000));
+        assert_eq!(0b1000_0110, increment_field(0b1011_0110, 0b0011_0000));
+        assert_eq!(0b0000_0000, increment_field(0b1111_1111, 0b1111_1111));
+    }
+}
@@ -0,0 +1,176 @@
+//! Decoding and encoding of the seven time and date registers, without a driver instance.
+//!
+//! Useful for bytes obtained by other means, such as DMA transfers or logged bus traces.
+
+use super::units::OutOfRange;
+use super::{bcd, BitFlags};
+use time::{Date, PrimitiveDateTime, Time};
+
+/// The contents of the time and date registers, seconds up to and including years.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RawDateTime {
+    /// The oscillator stop flag: clock integrity is not guaranteed.
+    pub oscillator_stopped: bool,
+    pub seconds: u8,
+    pub minutes: u8,
+    /// Hours in 24-hour format.
+    pub hours: u8,
+    pub day: u8,
+    /// Day of the week, 0 is Sunday.
+    pub weekday: u8,
+    pub month: u8,
+    /// Year counter 0-99, relative to the base year.
+    pub year: u8,
+}
+
+/// Decode a BCD register value, checking the digits and the range.
+fn decode(value: u8, mask: u8, range: core::ops::RangeInclusive<u8>) -> Result<u8, OutOfRange> {
+    let decoded = bcd::decode_checked(value & mask).ok_or(OutOfRange)?;
+    if !range.contains(&decoded) {
+        return Err(OutOfRange);
+    }
+    Ok(decoded)
+}
+
+impl TryFrom<[u8; 7]> for RawDateTime {
+    type Error = OutOfRange;
+
+    /// Decode register values, checking that each holds valid BCD within its range.
+    fn try_from(data: [u8; 7]) -> Result<Self, Self::Error> {
+        Ok(RawDateTime {
+            oscillator_stopped: data[0] & BitFlags::OS != 0,
+            seconds: decode(data[0], 0x7f, 0..=59)?,
+            minutes: decode(data[1], 0x7f, 0..=59)?,
+            hours: decode(data[2], 0x3f, 0..=23)?,
+            day: decode(data[3], 0x3f, 1..=31)?,
+            weekday: decode(data[4], 0x07, 0..=6)?,
+            month: decode(data[5], 0x1f, 1..=12)?,
+            year: decode(data[6], 0xff, 0..=99)?,
+        })
+    }
+}
+
+impl From<RawDateTime> for [u8; 7] {
+    fn from(raw: RawDateTime) -> Self {
+        let os = if raw.oscillator_stopped {
+            BitFlags::OS
+        } else {
+            0
+        };
+        [
+            bcd::encode(raw.seconds) | os,
+            bcd::encode(raw.minutes),
+            bcd::encode(raw.hours),
+            bcd::encode(raw.day),
+            bcd::encode(raw.weekday),
+            bcd::encode(raw.month),
+            bcd::encode(raw.year),
+        ]
+    }
+}
+
+impl RawDateTime {
+    /// Decode register values without checking the digits and ranges.
+    ///
+    /// Only for trusted values, such as registers just written by this driver; invalid values
+    /// decode to garbage.
+    pub const fn from_bytes_unchecked(data: [u8; 7]) -> Self {
+        RawDateTime {
+            oscillator_stopped: data[0] & BitFlags::OS != 0,
+            seconds: bcd::decode_masked(data[0], 0x7f),
+            minutes: bcd::decode_masked(data[1], 0x7f),
+            hours: bcd::decode_masked(data[2], 0x3f),
+            day: bcd::decode_masked(data[3], 0x3f),
+            weekday: data[4] & 0x07,
+            month: bcd::decode_masked(data[5], 0x1f),
+            year: bcd::decode(data[6]),
+        }
+    }
+
+    /// Convert to a date and time, with year counter 0 at `base_year`.
+    pub fn to_datetime(
+        &self,
+        base_year: i32,
+    ) -> Result<PrimitiveDateTime, time::error::ComponentRange> {
+        Ok(PrimitiveDateTime::new(
+            Date::from_calendar_date(
+                base_year + self.year as i32,
+                self.month.try_into()?,
+                self.day,
+            )?,
+            Time::from_hms(self.hours, self.minutes, self.seconds)?,
+        ))
+    }
+
+    /// Convert from a date and time, with year counter 0 at `base_year`.
+    ///
+    /// Fails if the year is not within the hundred years starting at the base year.
+    pub fn from_datetime(datetime: &PrimitiveDateTime, base_year: i32) -> Result<Self, OutOfRange> {
+        let year = datetime.year() - base_year;
+        if !(0..=99).contains(&year) {
+            return Err(OutOfRange);
+        }
+        Ok(RawDateTime {
+            oscillator_stopped: false,
+            seconds: datetime.second(),
+            minutes: datetime.minute(),
+            hours: datetime.hour(),
+            day: datetime.day(),
+            weekday: datetime.weekday().number_days_from_sunday(),
+            month: datetime.month().into(),
+            year: year as u8,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn can_round_trip_registers() {
+        let bytes = [0x80 | 0x59, 0x30, 0x23, 0x29, 0x04, 0x02, 0x24];
+        let raw = RawDateTime::try_from(bytes).unwrap();
+
+        assert!(raw.oscillator_stopped);
+        assert_eq!(
+            datetime!(2024-02-29 23:30:59),
+            raw.to_datetime(2000).unwrap()
+        );
+        assert_eq!(bytes, <[u8; 7]>::from(raw));
+    }
+
+    #[test]
+    fn unchecked_decode_matches_checked() {
+        let bytes = [0x59, 0x30, 0x23, 0x29, 0x04, 0x02, 0x24];
+        assert_eq!(
+            RawDateTime::try_from(bytes).unwrap(),
+            RawDateTime::from_bytes_unchecked(bytes)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_bcd() {
+        assert_eq!(
+            Err(OutOfRange),
+            RawDateTime::try_from([0x0a, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00])
+        );
+        assert_eq!(
+            Err(OutOfRange),
+            RawDateTime::try_from([0x00, 0x00, 0x24, 0x01, 0x00, 0x01, 0x00])
+        );
+    }
+
+    #[test]
+    fn can_convert_from_datetime() {
+        let raw = RawDateTime::from_datetime(&datetime!(2099-12-31 00:00:00), 2000).unwrap();
+        assert_eq!(99, raw.year);
+        assert_eq!(4, raw.weekday);
+        assert_eq!(
+            Err(OutOfRange),
+            RawDateTime::from_datetime(&datetime!(2100-01-01 00:00:00), 2000)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Getting a misbehaving chip back into a known state without a reboot.
+//!
+//! A glitch on the bus or supply can leave the chip in a state where transactions keep failing
+//! or settings read back wrong. Once the application has seen this happen a few times in a row,
+//! [`PCF85063::recover`] salvages what can still be read, resets the chip, checks that it is
+//! the expected chip, and writes the configuration kept by the application and the salvaged
+//! settings back:
+//!
+//! ```ignore
+//! if failures >= 3 {
+//!     let recovery = rtc.recover(&config).await?;
+//!     if recovery.time_lost {
+//!         request_time_sync();
+//!     }
+//!     failures = 0;
+//! }
+//! ```
+
+use super::{Chip, Config, DataError, Error, HourMode, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+
+/// What [`PCF85063::recover`] carried over the reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Recovery {
+    /// The date and time could not be read or were invalid, and are left invalid
+    pub time_lost: bool,
+    /// The alarm could not be read, and is left disabled
+    pub alarm_lost: bool,
+    /// The offset correction could not be read, and is left at zero
+    pub calibration_lost: bool,
+    /// The RAM byte could not be read, and is left at zero
+    pub ram_lost: bool,
+    /// The clock was found running afterwards
+    pub running: bool,
+}
+
+impl Recovery {
+    /// Nothing was lost, and the clock is running.
+    pub fn is_complete(&self) -> bool {
+        !(self.time_lost || self.alarm_lost || self.calibration_lost || self.ram_lost)
+            && self.running
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Reset the chip and restore it: `config` is written, and the date and time, alarm,
+    /// offset correction and RAM byte are written back if they could be read before the reset.
+    ///
+    /// The date and time written back are those read before the reset, so the clock loses the
+    /// fraction of a second the recovery takes. The countdown timer is left disabled. Will
+    /// return a 'DataError::ResetFailed' if the registers did not take their reset values, or
+    /// the chip does not identify as a PCF85063A afterwards, and a 'UsageError::InvalidInputData'
+    /// without resetting the chip if `set_config` would reject `config`.
+    pub async fn recover(&mut self, config: &Config) -> Result<Recovery, Error<E>> {
+        if config.hour_mode == HourMode::H12 {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        let time = self.get_valid_datetime().await.ok().flatten();
+        let alarm = self.get_alarm().await.ok();
+        let calibration = self.get_calibration().await.ok();
+        let ram = self.read_ram_byte().await.ok();
+
+        self.reset().await?;
+        if self.identify().await? != Chip::Pcf85063a {
+            return Err(Error::Data(DataError::ResetFailed));
+        }
+        self.set_config(config).await?;
+
+        if let Some(time) = time {
+            self.set_datetime_forced(&time).await?;
+        }
+        if let Some(alarm) = alarm {
+            self.set_alarm(&alarm).await?;
+        }
+        if let Some(calibration) = calibration {
+            self.set_calibration(&calibration).await?;
+        }
+        if let Some(ram) = ram {
+            self.write_ram_byte(ram).await?;
+        }
+
+        Ok(Recovery {
+            time_lost: time.is_none(),
+            alarm_lost: alarm.is_none(),
+            calibration_lost: calibration.is_none(),
+            ram_lost: ram.is_none(),
+            running: self.is_running().await?,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use crate::{Alarm, Calibration, CapacitorSelection, OffsetMode};
+    use time::macros::datetime;
+
+    #[test]
+    fn restores_settings_over_reset() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let config = Config {
+            capacitor: CapacitorSelection::Pf12_5,
+            alarm_interrupt: true,
+            minute_interrupt: true,
+            ..Config::default()
+        };
+        let alarm = Alarm {
+            seconds: Some(0),
+            minutes: Some(30),
+            interrupt: true,
+            ..Alarm::default()
+        };
+
+        let recovery = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:00)).await?;
+            rtc.set_alarm(&alarm).await?;
+            rtc.set_calibration(&Calibration {
+                mode: OffsetMode::Coarse,
+                offset: -7,
+            })
+            .await?;
+            rtc.write_ram_byte(0x5a).await?;
+            rtc.recover(&config).await
+        })
+        .unwrap();
+
+        assert!(recovery.is_complete());
+        assert_eq!(
+            datetime!(2024-02-29 12:00:00),
+            simulator.datetime().unwrap()
+        );
+        let restored = block_on(async {
+            Ok::<_, Error<_>>((
+                rtc.get_config().await?,
+                rtc.get_alarm().await?,
+                rtc.get_calibration().await?.offset,
+                rtc.read_ram_byte().await?,
+            ))
+        })
+        .unwrap();
+        assert_eq!((config, alarm, -7, 0x5a), restored);
+    }
+}
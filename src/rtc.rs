@@ -0,0 +1,119 @@
+//! A minimal real-time clock abstraction, to move time between this chip and other clocks.
+
+use super::{Error, PCF85063};
+use core::future::Future;
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+/// A clock that can be read and set.
+#[allow(async_fn_in_trait)]
+pub trait Rtc {
+    type Error;
+
+    /// Read date and time.
+    async fn get_datetime(&mut self) -> Result<PrimitiveDateTime, Self::Error>;
+
+    /// Set date and time.
+    async fn set_datetime(&mut self, datetime: &PrimitiveDateTime) -> Result<(), Self::Error>;
+}
+
+impl<I2C, E> Rtc for PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    type Error = Error<E>;
+
+    async fn get_datetime(&mut self) -> Result<PrimitiveDateTime, Self::Error> {
+        PCF85063::get_datetime(self).await
+    }
+
+    async fn set_datetime(&mut self, datetime: &PrimitiveDateTime) -> Result<(), Self::Error> {
+        PCF85063::set_datetime(self, datetime).await.map(|_| ())
+    }
+}
+
+/// Error of copying the time from one clock to another.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CopyError<S, T> {
+    /// Reading the source clock failed
+    Source(S),
+    /// Setting the target clock failed
+    Target(T),
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the time from this chip and set it on another clock, returning the copied time.
+    pub async fn copy_time_to<R: Rtc>(
+        &mut self,
+        target: &mut R,
+    ) -> Result<PrimitiveDateTime, CopyError<Error<E>, R::Error>> {
+        let datetime = self.get_datetime().await.map_err(CopyError::Source)?;
+        target
+            .set_datetime(&datetime)
+            .await
+            .map_err(CopyError::Target)?;
+        Ok(datetime)
+    }
+
+    /// Read the time from this chip and pass it to `set`, e.g. to set a clock that does not
+    /// implement [`Rtc`]. Returns the copied time.
+    pub async fn copy_time_with<F, Fut, T>(
+        &mut self,
+        set: F,
+    ) -> Result<PrimitiveDateTime, CopyError<Error<E>, T>>
+    where
+        F: FnOnce(PrimitiveDateTime) -> Fut,
+        Fut: Future<Output = Result<(), T>>,
+    {
+        let datetime = self.get_datetime().await.map_err(CopyError::Source)?;
+        set(datetime).await.map_err(CopyError::Target)?;
+        Ok(datetime)
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use time::macros::datetime;
+
+    /// Set and read back a clock through the trait only.
+    async fn set_and_get<R: Rtc>(
+        clock: &mut R,
+        datetime: PrimitiveDateTime,
+    ) -> Result<PrimitiveDateTime, R::Error> {
+        clock.set_datetime(&datetime).await?;
+        clock.get_datetime().await
+    }
+
+    #[test]
+    fn drives_driver_through_trait() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let now = block_on(set_and_get(&mut rtc, datetime!(2024-02-29 23:59:59))).unwrap();
+        assert_eq!(datetime!(2024-02-29 23:59:59), now);
+        assert_eq!(Some(now), simulator.datetime());
+    }
+
+    #[test]
+    fn copies_time_to_other_clock() {
+        let source = Simulator::new();
+        let target = Simulator::new();
+        let mut rtc = PCF85063::new(source.bus());
+        let mut other = PCF85063::new(target.bus());
+
+        let copied = block_on(async {
+            Rtc::set_datetime(&mut rtc, &datetime!(2025-06-30 12:00:00))
+                .await
+                .map_err(CopyError::Source)?;
+            rtc.copy_time_to(&mut other).await
+        })
+        .unwrap();
+        assert_eq!(datetime!(2025-06-30 12:00:00), copied);
+        assert_eq!(Some(copied), target.datetime());
+    }
+}
@@ -0,0 +1,97 @@
+//! Bookkeeping of a scheduled wake that survives a reboot.
+//!
+//! The wake time goes into the alarm registers, and its state into a two-bit field of the RAM
+//! byte. After any reset of the microcontroller, `wake_state` tells whether the wake is still
+//! ahead, is due (this boot is the wake, or it was missed while the firmware was down), or was
+//! already handled.
+
+use super::{BitFlags, Error, Register, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+/// Values of the state field in the RAM byte.
+const SCHEDULED: u8 = 1;
+const HANDLED: u8 = 2;
+
+/// State of the scheduled wake, as reconstructed from the chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WakeState {
+    /// No wake was scheduled
+    Idle,
+    /// The wake time is still ahead
+    Pending,
+    /// The wake time passed and the wake was not handled yet
+    Due,
+    /// The wake was handled with `complete_wake`
+    Handled,
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Schedule a wake at `at`, recording its state in the field of the RAM byte selected by
+    /// `mask` (at least two bits).
+    ///
+    /// The alarm matches on the day of the month, so the wake must be within a month. This
+    /// clears the alarm flag and enables the alarm interrupt.
+    pub async fn schedule_wake(&mut self, at: PrimitiveDateTime, mask: u8) -> Result<(), Error<E>> {
+        if mask.count_ones() < 2 {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        // a wake that is interrupted while being scheduled is not reported as pending
+        self.set_ram_bits(mask, 0).await?;
+
+        self.set_alarm_at(at).await?;
+        self.arm_alarm_interrupt().await?;
+
+        self.set_ram_bits(mask, SCHEDULED).await
+    }
+
+    /// Reconstruct the state of the wake scheduled with the same `mask`.
+    pub async fn wake_state(&mut self, mask: u8) -> Result<WakeState, Error<E>> {
+        let field = self.ram_bits(mask).await?;
+        let control_2 = self.read_register(Register::CONTROL_2).await?;
+        Ok(wake_state(
+            field,
+            control_2 & BitFlags::AF != 0,
+            control_2 & BitFlags::AIE != 0,
+        ))
+    }
+
+    /// Mark the wake scheduled with the same `mask` as handled, and disable the alarm so it
+    /// does not fire again a month later.
+    pub async fn complete_wake(&mut self, mask: u8) -> Result<(), Error<E>> {
+        self.disable_all_alarms().await?;
+        self.enable_alarm_interrupt(false).await?;
+        self.clear_alarm_flag().await?;
+        self.set_ram_bits(mask, HANDLED).await
+    }
+}
+
+/// The wake state given the RAM field and the alarm flag and interrupt enable bits.
+fn wake_state(field: u8, alarm_flag: bool, interrupt_enabled: bool) -> WakeState {
+    match field {
+        SCHEDULED if alarm_flag => WakeState::Due,
+        SCHEDULED if interrupt_enabled => WakeState::Pending,
+        HANDLED => WakeState::Handled,
+        // an unknown value, or the alarm was disabled behind our back
+        _ => WakeState::Idle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconstructs_wake_state() {
+        assert_eq!(WakeState::Idle, wake_state(0, true, true));
+        assert_eq!(WakeState::Pending, wake_state(SCHEDULED, false, true));
+        assert_eq!(WakeState::Due, wake_state(SCHEDULED, true, false));
+        assert_eq!(WakeState::Idle, wake_state(SCHEDULED, false, false));
+        assert_eq!(WakeState::Handled, wake_state(HANDLED, false, false));
+        assert_eq!(WakeState::Idle, wake_state(3, false, true));
+    }
+}
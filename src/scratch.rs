@@ -0,0 +1,230 @@
+//! Battery-backed scratch storage in otherwise unused registers.
+//!
+//! Besides the RAM byte, the five alarm registers are free while the alarm is disabled, and the
+//! timer value register is free while the timer is disabled. [`ScratchStorage`] exposes these
+//! seven registers as one small persistent memory. Not all of their bits are stored: the
+//! usable bits per offset are
+//!
+//! | offset | register      | usable bits   | available when                        |
+//! |--------|---------------|---------------|---------------------------------------|
+//! | 0      | RAM byte      | `0b1111_1111` | always                                |
+//! | 1      | seconds alarm | `0b0111_1111` | all AE bits set, alarm interrupt off  |
+//! | 2      | minutes alarm | `0b0111_1111` | all AE bits set, alarm interrupt off  |
+//! | 3      | hours alarm   | `0b0011_1111` | all AE bits set, alarm interrupt off  |
+//! | 4      | days alarm    | `0b0011_1111` | all AE bits set, alarm interrupt off  |
+//! | 5      | weekday alarm | `0b0000_0111` | all AE bits set, alarm interrupt off  |
+//! | 6      | timer value   | `0b1111_1111` | timer (TE) off                        |
+//!
+//! The AE bits of the alarm registers are kept set, so the alarm stays disabled and the stored
+//! values never fire it. An alarm with any field enabled is in use, even with its interrupt off,
+//! as when polling the alarm flag.
+//!
+//! Accesses touching registers that are in use fail with `UsageError::InUse`, and writes setting
+//! bits that are not stored with `UsageError::InvalidInputData`.
+
+use super::{BitFlags, Error, Register, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+
+/// Persistent scratch storage over the RAM byte and the unused alarm and timer registers.
+///
+/// Obtained through [`PCF85063::scratch_storage`].
+#[derive(Debug)]
+pub struct ScratchStorage<'a, I2C> {
+    rtc: &'a mut PCF85063<I2C>,
+}
+
+/// Total number of registers of scratch storage.
+const CAPACITY: usize = 7;
+/// Offsets backed by the alarm registers.
+const ALARM: core::ops::Range<usize> = 1..6;
+/// Offset backed by the timer value register.
+const TIMER: usize = 6;
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Use the RAM byte and the unused alarm and timer registers as scratch storage.
+    pub fn scratch_storage(&mut self) -> ScratchStorage<'_, I2C> {
+        ScratchStorage { rtc: self }
+    }
+}
+
+impl<I2C, E> ScratchStorage<'_, I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Total number of registers of scratch storage, see [`usable_bits`](Self::usable_bits).
+    pub const CAPACITY: usize = CAPACITY;
+
+    /// The bits stored at `offset`, `None` past the end of the storage.
+    pub const fn usable_bits(offset: usize) -> Option<u8> {
+        match offset {
+            0 | TIMER => Some(0xff),
+            1..TIMER => Some(!(register(offset).reserved_bits() | BitFlags::AE)),
+            _ => None,
+        }
+    }
+
+    /// Read `buffer.len()` bytes, starting at `offset`.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the range does not fit in the storage, and
+    /// a 'UsageError::InUse' if it touches registers in use by the alarm or timer.
+    pub async fn read(&mut self, offset: usize, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        self.check_available(offset, buffer.len()).await?;
+
+        let mut offset = offset;
+        let mut buffer = buffer;
+        if offset == 0 && !buffer.is_empty() {
+            buffer[0] = self.rtc.read_ram_byte().await?;
+            buffer = &mut buffer[1..];
+            offset = 1;
+        }
+        if !buffer.is_empty() {
+            self.rtc.read_bytes(register(offset), buffer).await?;
+        }
+        for (i, value) in buffer.iter_mut().enumerate() {
+            *value &= Self::usable_bits(offset + i).unwrap_or(0);
+        }
+        Ok(())
+    }
+
+    /// Write `data`, starting at `offset`.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the range does not fit in the storage or a
+    /// value sets bits that are not stored, and a 'UsageError::InUse' if it touches registers in
+    /// use by the alarm or timer.
+    pub async fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), Error<E>> {
+        let unusable = data.iter().enumerate().any(|(i, &value)| {
+            Self::usable_bits(offset.saturating_add(i)).is_some_and(|usable| value & !usable != 0)
+        });
+        if unusable {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        self.check_available(offset, data.len()).await?;
+
+        let mut offset = offset;
+        let mut data = data;
+        if offset == 0 && !data.is_empty() {
+            self.rtc.write_ram_byte(data[0]).await?;
+            data = &data[1..];
+            offset = 1;
+        }
+        if !data.is_empty() {
+            let mut payload = [0; CAPACITY];
+            payload[0] = register(offset).into();
+            payload[1..=data.len()].copy_from_slice(data);
+            for (i, value) in payload[1..=data.len()].iter_mut().enumerate() {
+                if ALARM.contains(&(offset + i)) {
+                    *value |= BitFlags::AE;
+                }
+            }
+            self.rtc.write_bytes(&payload[..=data.len()]).await?;
+        }
+        Ok(())
+    }
+
+    /// Check that the range is within bounds and its registers are not in use.
+    async fn check_available(&mut self, offset: usize, len: usize) -> Result<(), Error<E>> {
+        let end = offset
+            .checked_add(len)
+            .ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        if end > CAPACITY {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        if offset < ALARM.end && end > ALARM.start {
+            let mut alarm = [0; ALARM.end - ALARM.start];
+            self.rtc
+                .read_bytes(Register::SECOND_ALARM, &mut alarm)
+                .await?;
+            let enabled = alarm.iter().any(|value| value & BitFlags::AE == 0);
+            if enabled
+                || self
+                    .rtc
+                    .is_register_bit_flag_high(Register::CONTROL_2, BitFlags::AIE)
+                    .await?
+            {
+                return Err(Error::Usage(UsageError::InUse));
+            }
+        }
+        if end > TIMER
+            && self
+                .rtc
+                .is_register_bit_flag_high(Register::TIMER_MODE, BitFlags::TE)
+                .await?
+        {
+            return Err(Error::Usage(UsageError::InUse));
+        }
+        Ok(())
+    }
+}
+
+/// The register backing a (non-zero) offset; offsets 1 to 6 map onto consecutive registers.
+const fn register(offset: usize) -> Register {
+    Register::ALL[Register::SECOND_ALARM as usize + offset - ALARM.start]
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, SimBus, Simulator};
+
+    #[test]
+    fn stores_usable_bits() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let data: [u8; CAPACITY] =
+            core::array::from_fn(|i| ScratchStorage::<SimBus>::usable_bits(i).unwrap());
+        assert_eq!([0xff, 0x7f, 0x7f, 0x3f, 0x3f, 0x07, 0xff], data);
+
+        let mut read = [0; CAPACITY];
+        block_on(async {
+            let mut scratch = rtc.scratch_storage();
+            scratch.write(0, &data).await?;
+            scratch.read(0, &mut read).await
+        })
+        .unwrap();
+        assert_eq!(data, read);
+
+        let mut read = [0; 2];
+        block_on(async {
+            let mut scratch = rtc.scratch_storage();
+            scratch.write(4, &[0x12, 0x05]).await?;
+            scratch.read(4, &mut read).await
+        })
+        .unwrap();
+        assert_eq!([0x12, 0x05], read);
+    }
+
+    #[test]
+    fn rejects_bits_that_are_not_stored() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let result = block_on(rtc.scratch_storage().write(2, &[0xff, 0x40]));
+        assert!(matches!(
+            result,
+            Err(Error::Usage(UsageError::InvalidInputData))
+        ));
+        assert_eq!(0x80, simulator.registers()[Register::MINUTE_ALARM as usize]);
+    }
+
+    #[test]
+    fn keeps_alarm_without_interrupt() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let at = time::macros::datetime!(2024-02-29 12:30:00);
+        let result = block_on(async {
+            // polled for, with the alarm interrupt off
+            rtc.set_alarm_at(at).await?;
+            let mut scratch = rtc.scratch_storage();
+            scratch.write(0, &[0x5a]).await?;
+            scratch.write(1, &[0x11]).await
+        });
+
+        assert!(matches!(result, Err(Error::Usage(UsageError::InUse))));
+        let registers = simulator.registers();
+        assert_eq!(0x5a, registers[Register::RAM_BYTE as usize]);
+        assert_eq!(0x00, registers[Register::SECOND_ALARM as usize]);
+        assert_eq!(0x30, registers[Register::MINUTE_ALARM as usize]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Power-on self test.
+
+use super::{BitFlags, Error, Register, PCF85063};
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+
+/// Outcome of [`PCF85063::self_test`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestReport {
+    /// Test patterns written to the RAM byte were read back intact.
+    pub bus_ok: bool,
+    /// The oscillator stop flag is clear, and the seconds advanced while waiting.
+    pub oscillator_running: bool,
+    /// The clock is not stopped or in test mode, unused bits read as zero, and the time
+    /// registers hold a valid date and time.
+    pub registers_sane: bool,
+}
+
+impl SelfTestReport {
+    /// Did every check pass?
+    pub fn passed(&self) -> bool {
+        self.bus_ok && self.oscillator_running && self.registers_sane
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Check that the chip is present and keeping time.
+    ///
+    /// This takes a little over a second, as it waits for the seconds to advance. The RAM byte is
+    /// overwritten with test patterns and restored afterwards. Bus errors are returned as errors,
+    /// failed checks are reported in the [`SelfTestReport`].
+    pub async fn self_test(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<SelfTestReport, Error<E>> {
+        let ram = self.read_ram_byte().await?;
+        let mut bus_ok = true;
+        for pattern in [0b0101_0101, 0b1010_1010] {
+            self.write_ram_byte(pattern).await?;
+            bus_ok &= self.read_ram_byte().await? == pattern;
+        }
+        self.write_ram_byte(ram).await?;
+
+        let control_1 = self.read_register(Register::CONTROL_1).await?;
+        let datetime_valid = match self.get_datetime().await {
+            Err(Error::I2C(e)) => return Err(Error::I2C(e)),
+            result => result.is_ok(),
+        };
+        // the reserved bits always read as zero
+        let unexpected = Register::CONTROL_1.reserved_bits() | BitFlags::STOP | BitFlags::EXT_TEST;
+        let registers_sane = control_1 & unexpected == 0 && datetime_valid;
+
+        let before = self.read_register(Register::SECONDS).await?;
+        delay.delay_ms(1100).await;
+        let after = self.read_register(Register::SECONDS).await?;
+        let oscillator_running = after & BitFlags::OS == 0 && after != before;
+
+        Ok(SelfTestReport {
+            bus_ok,
+            oscillator_running,
+            registers_sane,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, SimBus, SimError, Simulator};
+    use embedded_hal_async::i2c::{ErrorType, Operation, SevenBitAddress};
+    use time::macros::datetime;
+
+    /// The simulator's bus, with bit 3 of control 1 stuck high
+    struct StuckBit<'a>(SimBus<'a>);
+
+    impl ErrorType for StuckBit<'_> {
+        type Error = SimError;
+    }
+
+    impl I2c for StuckBit<'_> {
+        async fn transaction(
+            &mut self,
+            address: SevenBitAddress,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            self.0.transaction(address, operations).await?;
+            if let [Operation::Write([0x00]), Operation::Read(buffer), ..] = operations {
+                buffer[0] |= 0b0000_1000;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn passes_on_healthy_chip() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let report = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:00)).await?;
+            rtc.write_ram_byte(0x5a).await?;
+            rtc.self_test(&mut simulator.delay()).await
+        })
+        .unwrap();
+
+        assert!(report.passed());
+        assert_eq!(0x5a, simulator.registers()[Register::RAM_BYTE as usize]);
+    }
+
+    #[test]
+    fn fails_on_stuck_reserved_bit() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(StuckBit(simulator.bus()));
+        let report = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:00)).await?;
+            rtc.self_test(&mut simulator.delay()).await
+        })
+        .unwrap();
+
+        assert_eq!(
+            SelfTestReport {
+                bus_ok: true,
+                oscillator_running: true,
+                registers_sane: false,
+            },
+            report
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! A service that owns the driver and handles commands from any number of tasks, enabled with
+//! the `embassy` feature.
+//!
+//! Embassy tasks cannot be generic, so the task that runs the service is written by the
+//! application:
+//!
+//! ```ignore
+//! static RTC: RtcService<CriticalSectionRawMutex, I2cError> = RtcService::new();
+//!
+//! #[embassy_executor::task]
+//! async fn rtc_task(mut rtc: PCF85063<I2c<'static, Async>>) {
+//!     RTC.run(&mut rtc).await
+//! }
+//!
+//! // from any other task
+//! let now = RTC.get_datetime().await?;
+//! ```
+
+use super::{Error, PCF85063};
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::mutex::Mutex;
+use embedded_hal_async::i2c::I2c;
+use time::{Duration, PrimitiveDateTime, Time};
+
+/// A command for the service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    GetDateTime,
+    SetDateTime(PrimitiveDateTime),
+    SetAlarmTime(Time),
+    /// Set the date and time if it is a second or more off the reference.
+    Sync(PrimitiveDateTime),
+}
+
+/// The successful result of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    DateTime(PrimitiveDateTime),
+    Done,
+    /// The correction applied by a sync, zero if the clock was left alone.
+    Synced(Duration),
+}
+
+/// Command channel to a task that owns the driver.
+///
+/// Commands and replies carry a sequence number, so a reply left behind by a cancelled request
+/// is told apart from the reply to the next one.
+pub struct RtcService<M: RawMutex, E> {
+    commands: Channel<M, (u8, Command), 1>,
+    replies: Channel<M, (u8, Result<Reply, Error<E>>), 1>,
+    /// Held by a client from sending its command until receiving the reply; the sequence
+    /// number of the last command sent.
+    client: Mutex<M, u8>,
+}
+
+impl<M: RawMutex, E> Default for RtcService<M, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: RawMutex, E> RtcService<M, E> {
+    pub const fn new() -> Self {
+        RtcService {
+            commands: Channel::new(),
+            replies: Channel::new(),
+            client: Mutex::new(0),
+        }
+    }
+
+    /// Handle commands with `rtc`, forever.
+    pub async fn run<I2C>(&self, rtc: &mut PCF85063<I2C>) -> !
+    where
+        I2C: I2c<Error = E>,
+    {
+        loop {
+            let (sequence, command) = self.commands.receive().await;
+            let reply = match command {
+                Command::GetDateTime => rtc.get_datetime().await.map(Reply::DateTime),
+                Command::SetDateTime(datetime) => {
+                    rtc.set_datetime(&datetime).await.map(|_| Reply::Done)
+                }
+                Command::SetAlarmTime(time) => rtc.set_alarm_time(time).await.map(|_| Reply::Done),
+                Command::Sync(reference) => sync(rtc, reference).await.map(Reply::Synced),
+            };
+            self.replies.send((sequence, reply)).await;
+        }
+    }
+
+    /// Send a command to the service and wait for its reply.
+    ///
+    /// # Cancellation
+    ///
+    /// Dropping this future after the command was sent does not cancel the command: the
+    /// service still carries it out, and the next request discards its reply.
+    pub async fn request(&self, command: Command) -> Result<Reply, Error<E>> {
+        let mut sequence = self.client.lock().await;
+        *sequence = sequence.wrapping_add(1);
+        self.commands.send((*sequence, command)).await;
+        loop {
+            let (replied, reply) = self.replies.receive().await;
+            if replied == *sequence {
+                return reply;
+            }
+        }
+    }
+
+    /// Read date and time.
+    pub async fn get_datetime(&self) -> Result<PrimitiveDateTime, Error<E>> {
+        match self.request(Command::GetDateTime).await? {
+            Reply::DateTime(datetime) => Ok(datetime),
+            _ => unreachable!("replies are matched to their command"),
+        }
+    }
+
+    /// Set date and time.
+    pub async fn set_datetime(&self, datetime: PrimitiveDateTime) -> Result<(), Error<E>> {
+        self.request(Command::SetDateTime(datetime))
+            .await
+            .map(|_| ())
+    }
+
+    /// Set the alarm seconds, minutes and hours.
+    pub async fn set_alarm_time(&self, time: Time) -> Result<(), Error<E>> {
+        self.request(Command::SetAlarmTime(time)).await.map(|_| ())
+    }
+
+    /// Set date and time to `reference` if the clock is a second or more off, and return the
+    /// correction.
+    pub async fn sync(&self, reference: PrimitiveDateTime) -> Result<Duration, Error<E>> {
+        match self.request(Command::Sync(reference)).await? {
+            Reply::Synced(correction) => Ok(correction),
+            _ => unreachable!("replies are matched to their command"),
+        }
+    }
+}
+
+async fn sync<I2C, E>(
+    rtc: &mut PCF85063<I2C>,
+    reference: PrimitiveDateTime,
+) -> Result<Duration, Error<E>>
+where
+    I2C: I2c<Error = E>,
+{
+    let correction = reference - rtc.get_datetime().await?;
+    if correction.abs() < Duration::SECOND {
+        return Ok(Duration::ZERO);
+    }
+    if !rtc.set_datetime(&reference).await? {
+        return Ok(Duration::ZERO);
+    }
+    Ok(correction)
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{SimError, Simulator};
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use time::macros::datetime;
+
+    #[test]
+    fn discards_reply_of_cancelled_request() {
+        let service = RtcService::<NoopRawMutex, SimError>::new();
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let mut run = pin!(service.run(&mut rtc));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        {
+            // sends its command, then is dropped before the reply
+            let mut cancelled = pin!(service.set_datetime(datetime!(2024-02-29 12:00:00)));
+            assert!(cancelled.as_mut().poll(&mut cx).is_pending());
+        }
+
+        let mut get = pin!(service.get_datetime());
+        let datetime = loop {
+            if let Poll::Ready(reply) = get.as_mut().poll(&mut cx) {
+                break reply.unwrap();
+            }
+            let _ = run.as_mut().poll(&mut cx);
+        };
+        assert_eq!(datetime!(2024-02-29 12:00:00), datetime);
+    }
+}
@@ -0,0 +1,203 @@
+//! Staging configuration changes and committing them in as few transactions as possible.
+//!
+//! ```ignore
+//! rtc.setup()
+//!     .hour_mode(HourMode::H24)
+//!     .clkout(OutputFrequency::Hz0)
+//!     .alarm(alarm!(07:30))
+//!     .alarm_interrupt(true)
+//!     .commit()
+//!     .await?;
+//! ```
+//!
+//! Settings in adjacent registers are merged: each run of consecutive staged registers is
+//! written in one burst, preceded by one burst read if bits of it are to be kept.
+
+use super::{
+    bcd, codec, BitFlags, CapacitorSelection, Config, Error, HourMode, OffsetMode, OutputFrequency,
+    Register, UsageError, PCF85063,
+};
+use core::ops::Range;
+use embedded_hal_async::i2c::I2c;
+use time::Time;
+
+const REGISTERS: usize = Register::ALL.len();
+
+/// Staged configuration changes, see [`PCF85063::setup`].
+#[must_use = "nothing is written until `commit` is awaited"]
+pub struct Setup<'a, I2C> {
+    rtc: &'a mut PCF85063<I2C>,
+    /// Bits to change per register
+    masks: [u8; REGISTERS],
+    /// New values of those bits
+    values: [u8; REGISTERS],
+    invalid: bool,
+}
+
+impl<I2C> PCF85063<I2C> {
+    /// Start staging configuration changes, to be written with `commit`.
+    pub fn setup(&mut self) -> Setup<'_, I2C> {
+        Setup {
+            rtc: self,
+            masks: [0; REGISTERS],
+            values: [0; REGISTERS],
+            invalid: false,
+        }
+    }
+}
+
+impl<I2C, E> Setup<'_, I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    fn stage(mut self, register: Register, mask: u8, value: u8) -> Self {
+        let i = register as usize;
+        self.masks[i] |= mask;
+        self.values[i] = (self.values[i] & !mask) | (value & mask);
+        self
+    }
+
+    fn flag(self, register: Register, mask: u8, on: bool) -> Self {
+        self.stage(register, mask, if on { mask } else { 0 })
+    }
+
+    /// Set the hour format. The driver reads and writes the hours in 24-hour format, so
+    /// `HourMode::H12` makes `commit` return a 'UsageError::InvalidInputData'.
+    pub fn hour_mode(mut self, mode: HourMode) -> Self {
+        if mode == HourMode::H12 {
+            self.invalid = true;
+            return self;
+        }
+        self.flag(Register::CONTROL_1, BitFlags::MODE_12_24, false)
+    }
+
+    pub fn capacitor(self, capacitor: CapacitorSelection) -> Self {
+        let on = capacitor == CapacitorSelection::Pf12_5;
+        self.flag(Register::CONTROL_1, BitFlags::CAP_SEL, on)
+    }
+
+    /// Set the CLKOUT frequency; `OutputFrequency::Hz0` turns it off.
+    pub fn clkout(self, frequency: OutputFrequency) -> Self {
+        self.stage(Register::CONTROL_2, BitFlags::COF, frequency.bits())
+    }
+
+    /// Enable or disable the alarm interrupt; also accepts the deprecated `Control`.
+    pub fn alarm_interrupt(self, enabled: impl Into<bool>) -> Self {
+        self.flag(Register::CONTROL_2, BitFlags::AIE, enabled.into())
+    }
+
+    /// Enable or disable the minute interrupt; also accepts the deprecated `Control`.
+    pub fn minute_interrupt(self, enabled: impl Into<bool>) -> Self {
+        self.flag(Register::CONTROL_2, BitFlags::MI, enabled.into())
+    }
+
+    /// Enable or disable the half minute interrupt.
+    pub fn half_minute_interrupt(self, enabled: bool) -> Self {
+        self.flag(Register::CONTROL_2, BitFlags::HMI, enabled)
+    }
+
+    /// Set all of the configuration in control 1 and control 2.
+    pub fn config(self, config: &Config) -> Self {
+        self.hour_mode(config.hour_mode)
+            .capacitor(config.capacitor)
+            .clkout(config.clock_output)
+            .alarm_interrupt(config.alarm_interrupt)
+            .minute_interrupt(config.minute_interrupt)
+            .half_minute_interrupt(config.half_minute_interrupt)
+    }
+
+    /// Set the offset correction; an offset outside [-64 to 63] makes `commit` return a
+    /// 'UsageError::InvalidInputData'.
+    pub fn offset(mut self, mode: OffsetMode, offset: i8) -> Self {
+        match codec::encode_offset(mode, offset) {
+            Some(value) => self.stage(Register::OFFSET, 0xff, value),
+            None => {
+                self.invalid = true;
+                self
+            }
+        }
+    }
+
+    /// Set the alarm to `time`, enabling the seconds, minutes and hours; the day and weekday
+    /// settings are left unchanged.
+    pub fn alarm(self, time: Time) -> Self {
+        self.stage(Register::SECOND_ALARM, 0xff, bcd::encode(time.second()))
+            .stage(Register::MINUTE_ALARM, 0xff, bcd::encode(time.minute()))
+            .stage(Register::HOUR_ALARM, 0xff, bcd::encode(time.hour()))
+    }
+
+    /// Write the staged changes.
+    pub async fn commit(self) -> Result<(), Error<E>> {
+        if self.invalid {
+            return Err(Error::Usage(UsageError::InvalidInputData));
+        }
+        let mut from = 0;
+        while let Some(run) = next_run(&self.masks, from) {
+            from = run.end;
+            let len = run.len();
+
+            let mut data = [0; REGISTERS];
+            if self.masks[run.clone()].iter().any(|&mask| mask != 0xff) {
+                self.rtc
+                    .read_bytes(Register::ALL[run.start], &mut data[..len])
+                    .await?;
+            }
+            let mut payload = [0; REGISTERS + 1];
+            payload[0] = run.start as u8;
+            for (i, register) in run.clone().enumerate() {
+                let mask = self.masks[register];
+                payload[i + 1] = (data[i] & !mask) | (self.values[register] & mask);
+            }
+            if run.contains(&(Register::CONTROL_2 as usize)) {
+                // flags are cleared by writing 0, writing 1 leaves them unchanged
+                payload[Register::CONTROL_2 as usize - run.start + 1] |=
+                    BitFlags::AF | BitFlags::TF;
+            }
+            self.rtc.write_bytes(&payload[..=len]).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The next run of consecutive staged registers, starting the search at `from`.
+fn next_run(masks: &[u8; REGISTERS], from: usize) -> Option<Range<usize>> {
+    let start = (from..REGISTERS).find(|&i| masks[i] != 0)?;
+    let end = (start..REGISTERS)
+        .find(|&i| masks[i] == 0)
+        .unwrap_or(REGISTERS);
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_runs_of_staged_registers() {
+        let mut masks = [0; REGISTERS];
+        masks[0] = BitFlags::MODE_12_24;
+        masks[1] = BitFlags::COF;
+        masks[0x0b] = 0xff;
+        masks[0x11] = BitFlags::TE;
+
+        assert_eq!(Some(0..2), next_run(&masks, 0));
+        assert_eq!(Some(0x0b..0x0c), next_run(&masks, 2));
+        assert_eq!(Some(0x11..0x12), next_run(&masks, 0x0c));
+        assert_eq!(None, next_run(&masks, 0x12));
+    }
+
+    #[cfg(feature = "simulator")]
+    #[test]
+    fn rejects_12_hour_mode() {
+        use crate::simulator::{block_on, Simulator};
+
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let result = block_on(rtc.setup().hour_mode(HourMode::H12).commit());
+        assert!(matches!(
+            result,
+            Err(Error::Usage(UsageError::InvalidInputData))
+        ));
+        assert_eq!(0, simulator.registers()[Register::CONTROL_1 as usize]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Sharing the driver between tasks through `&self`, enabled with the `embassy` feature.
+//!
+//! [`SharedRtc`] keeps the driver behind an async mutex, so it can sit in a `static` and be
+//! used from several tasks without threading `&mut` through all of them:
+//!
+//! ```ignore
+//! static RTC: StaticCell<SharedRtc<CriticalSectionRawMutex, I2c>> = StaticCell::new();
+//! let rtc: &'static _ = RTC.init(SharedRtc::new(PCF85063::new(i2c)));
+//!
+//! // in any task
+//! let now = rtc.get_datetime().await?;
+//! rtc.lock().await.set_alarm_at(now + Duration::minutes(5)).await?;
+//! ```
+//!
+//! With `CriticalSectionRawMutex` the tasks may run in different executors or interrupt
+//! priorities; `NoopRawMutex` suffices within one executor.
+
+use super::{Error, PCF85063};
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::mutex::{Mutex, MutexGuard};
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+/// The driver behind an async mutex
+pub struct SharedRtc<M: RawMutex, I2C> {
+    rtc: Mutex<M, PCF85063<I2C>>,
+}
+
+impl<M: RawMutex, I2C> SharedRtc<M, I2C> {
+    pub const fn new(rtc: PCF85063<I2C>) -> Self {
+        SharedRtc {
+            rtc: Mutex::new(rtc),
+        }
+    }
+
+    /// Wait for exclusive access to the driver, for the calls without a shortcut here or for
+    /// several calls without other tasks in between.
+    pub async fn lock(&self) -> MutexGuard<'_, M, PCF85063<I2C>> {
+        self.rtc.lock().await
+    }
+
+    /// Destroy the wrapper, returning the driver.
+    pub fn into_inner(self) -> PCF85063<I2C> {
+        self.rtc.into_inner()
+    }
+}
+
+impl<M, I2C, E> SharedRtc<M, I2C>
+where
+    M: RawMutex,
+    I2C: I2c<Error = E>,
+{
+    /// Read date and time all at once.
+    pub async fn get_datetime(&self) -> Result<PrimitiveDateTime, Error<E>> {
+        self.lock().await.get_datetime().await
+    }
+
+    /// Set date and time all at once, returning whether the clock was written.
+    pub async fn set_datetime(&self, datetime: &PrimitiveDateTime) -> Result<bool, Error<E>> {
+        self.lock().await.set_datetime(datetime).await
+    }
+
+    /// Set the alarm to fire at `at`, within a month.
+    pub async fn set_alarm_at(&self, at: PrimitiveDateTime) -> Result<(), Error<E>> {
+        self.lock().await.set_alarm_at(at).await
+    }
+
+    /// Clear the alarm flag.
+    pub async fn clear_alarm_flag(&self) -> Result<(), Error<E>> {
+        self.lock().await.clear_alarm_flag().await
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use crate::{BitFlags, Register};
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use time::macros::datetime;
+
+    #[test]
+    fn serves_several_users_by_reference() {
+        let simulator = Simulator::new();
+        let shared: SharedRtc<NoopRawMutex, _> = SharedRtc::new(PCF85063::new(simulator.bus()));
+        let (setter, reader) = (&shared, &shared);
+
+        block_on(async {
+            setter.set_datetime(&datetime!(2024-02-29 23:59:59)).await?;
+            setter.set_alarm_at(datetime!(2024-03-01 00:01:00)).await
+        })
+        .unwrap();
+        simulator.advance(core::time::Duration::from_secs(61));
+        let now = block_on(reader.get_datetime()).unwrap();
+        assert_eq!(datetime!(2024-03-01 00:01:00), now);
+
+        let flags = block_on(async {
+            let mut rtc = reader.lock().await;
+            let before = rtc.read_register(Register::CONTROL_2).await?;
+            drop(rtc);
+            reader.clear_alarm_flag().await?;
+            let after = reader
+                .lock()
+                .await
+                .read_register(Register::CONTROL_2)
+                .await?;
+            Ok::<_, Error<_>>((before, after))
+        })
+        .unwrap();
+        assert_eq!(
+            (BitFlags::AF, 0),
+            (flags.0 & BitFlags::AF, flags.1 & BitFlags::AF)
+        );
+
+        let mut rtc = shared.into_inner();
+        assert_eq!(now, block_on(rtc.get_datetime()).unwrap());
+    }
+}
@@ -0,0 +1,851 @@
+//! A simulated chip on a virtual clock, for testing code that uses the driver on the host,
+//! enabled with the `simulator` feature.
+//!
+//! The [`Simulator`] keeps the registers and counts time, the alarm, the minute interrupts and
+//! the countdown timer as the chip does, but on virtual time that only passes when asked to. It
+//! hands out a bus for the driver, an INT pin, a delay and a CLKOUT edge counter, all of which
+//! complete at once, so [`block_on`] runs the driver's futures:
+//!
+//! ```
+//! use pcf85063a::simulator::{block_on, Simulator};
+//! use pcf85063a::PCF85063;
+//! use embedded_hal_async::digital::Wait;
+//! use time::macros::datetime;
+//!
+//! let simulator = Simulator::new();
+//! let mut rtc = PCF85063::new(simulator.bus());
+//!
+//! block_on(async {
+//!     rtc.set_datetime(&datetime!(2024-02-29 23:59:00)).await.unwrap();
+//!     rtc.set_alarm_at(datetime!(2024-03-01 07:30:00)).await.unwrap();
+//!     rtc.arm_alarm_interrupt().await.unwrap();
+//!
+//!     // waiting on the pin lets virtual time pass until the alarm fires
+//!     simulator.int_pin().wait_for_low().await.unwrap();
+//!     assert_eq!(datetime!(2024-03-01 07:30:00), rtc.get_datetime().await.unwrap());
+//! });
+//! ```
+//!
+//! Recovery code can be exercised with faults: [`Simulator::power_loss`] and
+//! [`Simulator::brown_out`] model the supply dropping out, and
+//! [`Simulator::interrupt_write_after`] cuts a write short after some bytes.
+//!
+//! Long scenarios can be checkpointed with [`Simulator::save`] and resumed from there with
+//! [`Simulator::restore`], instead of counting the virtual time up to that point again.
+//!
+//! The interrupt output is modelled in permanent mode only; the pulse mode of the timer
+//! interrupt is not.
+
+pub use super::block_on::block_on;
+use super::persist::crc8;
+use super::{BitFlags, Register, DEVICE_ADDRESS};
+use crate::capture::EdgeCounter;
+use core::cell::{Cell, RefCell};
+use core::time::Duration;
+use embedded_hal::digital;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::{self, I2c, NoAcknowledgeSource, Operation, SevenBitAddress};
+use time::{Date, Month, PrimitiveDateTime, Time};
+
+/// Number of bytes taken by a snapshot of the simulator, see [`Simulator::save`].
+pub const SNAPSHOT_LEN: usize = 62;
+
+const NS_PER_SECOND: u64 = 1_000_000_000;
+const MAGIC: u8 = 0x5e;
+/// Longest virtual time the INT pin waits for an interrupt.
+const MAX_WAIT: Duration = Duration::from_secs(64 * 24 * 3600);
+/// Register values after power-on.
+const POWER_ON_DEFAULTS: [u8; 18] = [
+    0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x01, 0x06, 0x01, 0x00, 0x80, 0x80, 0x80, 0x80, 0x80,
+    0x00, 0x18,
+];
+/// Pattern written to control 1 to trigger a software reset.
+const SOFTWARE_RESET: u8 = 0b0101_1000;
+/// PM flag of the hours register in 12-hour mode
+const PM: u8 = 0b0010_0000;
+
+/// Errors of the simulated bus and INT pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SimError {
+    /// A transaction addressed another device
+    NoAcknowledge,
+    /// No enabled interrupt asserted the INT pin within 64 days of virtual time
+    NeverAsserted,
+    /// The INT pin is held low, so it can not go low again until the flags are cleared
+    StuckLow,
+    /// A write was cut short, see [`Simulator::interrupt_write_after`]
+    Interrupted,
+}
+
+impl i2c::Error for SimError {
+    fn kind(&self) -> i2c::ErrorKind {
+        match self {
+            SimError::NoAcknowledge => i2c::ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            SimError::Interrupted => i2c::ErrorKind::Bus,
+            _ => i2c::ErrorKind::Other,
+        }
+    }
+}
+
+impl digital::Error for SimError {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+/// The chip's state, and the virtual time it has been counting
+#[derive(Debug, Clone)]
+struct State {
+    registers: [u8; 18],
+    /// Register the next byte is read from or written to
+    pointer: u8,
+    /// Value the countdown timer reloads with
+    timer_reload: u8,
+    /// Nanoseconds into the current second
+    prescaler: u64,
+    /// Progress towards the next timer tick, in nanoseconds times the ticks per period
+    timer_progress: u64,
+    /// Progress towards the next CLKOUT edge, in nanoseconds times the frequency
+    clkout_progress: u64,
+    clkout_edges: u32,
+    elapsed: Duration,
+}
+
+impl State {
+    const fn new() -> Self {
+        State {
+            registers: POWER_ON_DEFAULTS,
+            pointer: 0,
+            timer_reload: 0,
+            prescaler: 0,
+            timer_progress: 0,
+            clkout_progress: 0,
+            clkout_edges: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; SNAPSHOT_LEN] {
+        let mut bytes = [0; SNAPSHOT_LEN];
+        bytes[0] = MAGIC;
+        bytes[1..19].copy_from_slice(&self.registers);
+        bytes[19] = self.pointer;
+        bytes[20] = self.timer_reload;
+        bytes[21..29].copy_from_slice(&self.prescaler.to_le_bytes());
+        bytes[29..37].copy_from_slice(&self.timer_progress.to_le_bytes());
+        bytes[37..45].copy_from_slice(&self.clkout_progress.to_le_bytes());
+        bytes[45..49].copy_from_slice(&self.clkout_edges.to_le_bytes());
+        bytes[49..57].copy_from_slice(&self.elapsed.as_secs().to_le_bytes());
+        bytes[57..61].copy_from_slice(&self.elapsed.subsec_nanos().to_le_bytes());
+        bytes[61] = crc8(&bytes[..61]);
+        bytes
+    }
+
+    /// Decode a snapshot, `None` if it is not valid.
+    fn from_bytes(bytes: &[u8; SNAPSHOT_LEN]) -> Option<Self> {
+        if bytes[0] != MAGIC || bytes[61] != crc8(&bytes[..61]) {
+            return None;
+        }
+        let u64_at = |start: usize| {
+            let mut value = [0; 8];
+            value.copy_from_slice(&bytes[start..start + 8]);
+            u64::from_le_bytes(value)
+        };
+        let u32_at = |start: usize| {
+            let mut value = [0; 4];
+            value.copy_from_slice(&bytes[start..start + 4]);
+            u32::from_le_bytes(value)
+        };
+        let mut registers = [0; 18];
+        registers.copy_from_slice(&bytes[1..19]);
+        let state = State {
+            registers,
+            pointer: bytes[19],
+            timer_reload: bytes[20],
+            prescaler: u64_at(21),
+            timer_progress: u64_at(29),
+            clkout_progress: u64_at(37),
+            clkout_edges: u32_at(45),
+            elapsed: Duration::new(u64_at(49), u32_at(57)),
+        };
+        // the counters stay below what they count to
+        let valid = state.pointer < 18
+            && state.prescaler < NS_PER_SECOND
+            && state.timer_progress < 60 * NS_PER_SECOND
+            && state.clkout_progress < NS_PER_SECOND
+            && u32_at(57) < NS_PER_SECOND as u32;
+        valid.then_some(state)
+    }
+
+    fn get(&self, register: Register) -> u8 {
+        self.registers[register as usize]
+    }
+
+    fn flag(&self, register: Register, mask: u8) -> bool {
+        self.get(register) & mask != 0
+    }
+
+    fn set_flag(&mut self, register: Register, mask: u8) {
+        self.registers[register as usize] |= mask;
+    }
+
+    fn stopped(&self) -> bool {
+        self.flag(Register::CONTROL_1, BitFlags::STOP)
+    }
+
+    fn write(&mut self, value: u8) {
+        let register = Register::ALL[self.pointer as usize];
+        self.pointer = (self.pointer + 1) % 18;
+
+        match register {
+            Register::CONTROL_1 if value == SOFTWARE_RESET => return self.software_reset(),
+            // the flags are cleared by writing 0, writing 1 leaves them unchanged
+            Register::CONTROL_2 => {
+                let flags = BitFlags::AF | BitFlags::TF;
+                let value = value & (self.get(register) | !flags);
+                self.registers[register as usize] = value;
+                return;
+            }
+            Register::TIMER_VALUE => self.timer_reload = value,
+            _ => {}
+        }
+        self.registers[register as usize] = value & !register.reserved_bits();
+        if register == Register::CONTROL_1 && self.stopped() {
+            self.prescaler = 0;
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let value = self.registers[self.pointer as usize];
+        self.pointer = (self.pointer + 1) % 18;
+        value
+    }
+
+    /// All registers but the time and date take their reset values, and the oscillator stop
+    /// flag is set.
+    fn software_reset(&mut self) {
+        let time = Register::SECONDS as usize..=Register::YEARS as usize;
+        for (address, default) in POWER_ON_DEFAULTS.into_iter().enumerate() {
+            if !time.contains(&address) {
+                self.registers[address] = default;
+            }
+        }
+        self.set_flag(Register::SECONDS, BitFlags::OS);
+        self.timer_reload = 0;
+        self.prescaler = 0;
+    }
+
+    fn int_asserted(&self) -> bool {
+        let alarm = self.flag(Register::CONTROL_2, BitFlags::AF)
+            && self.flag(Register::CONTROL_2, BitFlags::AIE);
+        let timer_enabled = self.flag(Register::CONTROL_2, BitFlags::MI | BitFlags::HMI)
+            || self.flag(Register::TIMER_MODE, BitFlags::TIE);
+        alarm || (self.flag(Register::CONTROL_2, BitFlags::TF) && timer_enabled)
+    }
+
+    /// Can an enabled interrupt assert the INT pin in the future?
+    fn int_enabled(&self) -> bool {
+        let alarm_fields = [
+            Register::SECOND_ALARM,
+            Register::MINUTE_ALARM,
+            Register::HOUR_ALARM,
+            Register::DAY_ALARM,
+            Register::WEEKDAY_ALARM,
+        ];
+        let alarm = self.flag(Register::CONTROL_2, BitFlags::AIE)
+            && alarm_fields
+                .into_iter()
+                .any(|register| !self.flag(register, BitFlags::AE));
+        let timer = self.flag(Register::TIMER_MODE, BitFlags::TIE)
+            && self.flag(Register::TIMER_MODE, BitFlags::TE)
+            && self.get(Register::TIMER_VALUE) != 0;
+        !self.stopped()
+            && (alarm || timer || self.flag(Register::CONTROL_2, BitFlags::MI | BitFlags::HMI))
+    }
+
+    /// CLKOUT frequency in Hz; below 8192 Hz it stops along with the clock.
+    fn clkout_hz(&self) -> u32 {
+        let hz = crate::OutputFrequency::from_bits(self.get(Register::CONTROL_2)).hz();
+        if self.stopped() && hz < 8192 {
+            0
+        } else {
+            hz
+        }
+    }
+
+    fn advance(&mut self, duration: Duration) {
+        self.elapsed += duration;
+        let mut ns = duration.as_nanos() as u64;
+        while ns > 0 {
+            if self.stopped() {
+                self.count_clkout(ns);
+                return;
+            }
+            let step = ns.min(NS_PER_SECOND - self.prescaler);
+            self.count_clkout(step);
+            self.count_timer(step);
+            self.prescaler += step;
+            ns -= step;
+            if self.prescaler == NS_PER_SECOND {
+                self.prescaler = 0;
+                self.tick();
+            }
+        }
+    }
+
+    fn count_clkout(&mut self, ns: u64) {
+        self.clkout_progress += ns * self.clkout_hz() as u64;
+        let edges = self.clkout_progress / NS_PER_SECOND;
+        self.clkout_progress %= NS_PER_SECOND;
+        self.clkout_edges = self.clkout_edges.wrapping_add(edges as u32);
+    }
+
+    fn count_timer(&mut self, ns: u64) {
+        let mode = self.get(Register::TIMER_MODE);
+        if mode & BitFlags::TE == 0 {
+            return;
+        }
+        // ticks per period, and the period in nanoseconds
+        let (ticks, period) = match (mode & BitFlags::TCF) >> 3 {
+            0b00 => (4096, NS_PER_SECOND),
+            0b01 => (64, NS_PER_SECOND),
+            0b10 => (1, NS_PER_SECOND),
+            _ => (1, 60 * NS_PER_SECOND),
+        };
+        self.timer_progress += ns * ticks;
+        let elapsed = self.timer_progress / period;
+        self.timer_progress %= period;
+
+        let value = self.get(Register::TIMER_VALUE) as u64;
+        if elapsed == 0 || value == 0 {
+            return;
+        }
+        let value = if elapsed < value {
+            value - elapsed
+        } else {
+            self.set_flag(Register::CONTROL_2, BitFlags::TF);
+            let reload = self.timer_reload as u64;
+            if reload == 0 {
+                0
+            } else {
+                reload - (elapsed - value) % reload
+            }
+        };
+        self.registers[Register::TIMER_VALUE as usize] = value as u8;
+    }
+
+    /// Count one second, and set the flags of the events it starts.
+    fn tick(&mut self) {
+        let matched = self.alarm_matches();
+        let Some(second) = self.count_second() else {
+            return;
+        };
+        let minute = second == 0 && self.flag(Register::CONTROL_2, BitFlags::MI);
+        let half_minute = second % 30 == 0 && self.flag(Register::CONTROL_2, BitFlags::HMI);
+        if minute || half_minute {
+            self.set_flag(Register::CONTROL_2, BitFlags::TF);
+        }
+        if !matched && self.alarm_matches() {
+            self.set_flag(Register::CONTROL_2, BitFlags::AF);
+        }
+    }
+
+    /// Advance the time and date registers by a second, returning the new seconds; `None` if
+    /// they do not hold a valid date and time, which stalls the count.
+    fn count_second(&mut self) -> Option<u8> {
+        let seconds = self.get(Register::SECONDS);
+        let second = crate::bcd::decode_checked(seconds & 0x7f)?;
+        if second < 59 {
+            // within the minute only the seconds change
+            let os = seconds & BitFlags::OS;
+            self.registers[Register::SECONDS as usize] = crate::bcd::encode(second + 1) | os;
+            return Some(second + 1);
+        }
+
+        let datetime = self.datetime()?;
+        let next = match datetime.checked_add(time::Duration::SECOND) {
+            // the year counter wraps around after 99
+            Some(next) if next.year() < 2100 => next,
+            _ => PrimitiveDateTime::new(Date::MIN, Time::MIDNIGHT)
+                .replace_year(2000)
+                .unwrap_or(datetime),
+        };
+        self.set_datetime(next);
+        if next.day() != datetime.day() {
+            let weekday = (self.get(Register::WEEKDAYS) + 1) % 7;
+            self.registers[Register::WEEKDAYS as usize] = weekday;
+        }
+        Some(0)
+    }
+
+    fn twelve_hour(&self) -> bool {
+        self.flag(Register::CONTROL_1, BitFlags::MODE_12_24)
+    }
+
+    /// The time and date registers, `None` if they do not hold a valid date and time.
+    fn datetime(&self) -> Option<PrimitiveDateTime> {
+        let decode =
+            |register: Register, mask: u8| crate::bcd::decode_checked(self.get(register) & mask);
+        let hours = self.get(Register::HOURS);
+        let hour = if self.twelve_hour() {
+            let hour = crate::bcd::decode_checked(hours & 0x1f)? % 12;
+            hour + if hours & PM != 0 { 12 } else { 0 }
+        } else {
+            decode(Register::HOURS, 0x3f)?
+        };
+        let time = Time::from_hms(
+            hour,
+            decode(Register::MINUTES, 0x7f)?,
+            decode(Register::SECONDS, 0x7f)?,
+        )
+        .ok()?;
+        let date = Date::from_calendar_date(
+            2000 + decode(Register::YEARS, 0xff)? as i32,
+            Month::try_from(decode(Register::MONTHS, 0x1f)?).ok()?,
+            decode(Register::DAYS, 0x3f)?,
+        )
+        .ok()?;
+        Some(PrimitiveDateTime::new(date, time))
+    }
+
+    /// Write the time and date registers, keeping the oscillator stop flag and the weekday.
+    fn set_datetime(&mut self, datetime: PrimitiveDateTime) {
+        let encode = crate::bcd::encode;
+        let hours = if self.twelve_hour() {
+            let hour = match datetime.hour() % 12 {
+                0 => 12,
+                hour => hour,
+            };
+            encode(hour) | if datetime.hour() >= 12 { PM } else { 0 }
+        } else {
+            encode(datetime.hour())
+        };
+        let os = self.get(Register::SECONDS) & BitFlags::OS;
+        let registers = &mut self.registers;
+        registers[Register::SECONDS as usize] = encode(datetime.second()) | os;
+        registers[Register::MINUTES as usize] = encode(datetime.minute());
+        registers[Register::HOURS as usize] = hours;
+        registers[Register::DAYS as usize] = encode(datetime.day());
+        registers[Register::MONTHS as usize] = encode(datetime.month() as u8);
+        registers[Register::YEARS as usize] = encode((datetime.year() - 2000) as u8);
+    }
+
+    /// Do all enabled alarm fields match the time, with at least one enabled?
+    fn alarm_matches(&self) -> bool {
+        let fields = [
+            (Register::SECOND_ALARM, Register::SECONDS, 0x7f),
+            (Register::MINUTE_ALARM, Register::MINUTES, 0x7f),
+            (Register::HOUR_ALARM, Register::HOURS, 0x3f),
+            (Register::DAY_ALARM, Register::DAYS, 0x3f),
+            (Register::WEEKDAY_ALARM, Register::WEEKDAYS, 0x07),
+        ];
+        let mut enabled = fields
+            .into_iter()
+            .filter(|&(alarm, _, _)| !self.flag(alarm, BitFlags::AE))
+            .peekable();
+        enabled.peek().is_some()
+            && enabled.all(|(alarm, time, mask)| self.get(alarm) & mask == self.get(time) & mask)
+    }
+}
+
+/// A simulated PCF85063A
+#[derive(Debug)]
+pub struct Simulator {
+    state: RefCell<State>,
+    /// Number of bytes the next write is cut short after
+    write_fault: Cell<Option<usize>>,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    /// A chip just powered on, with all registers at their power-on defaults.
+    pub const fn new() -> Self {
+        Simulator {
+            state: RefCell::new(State::new()),
+            write_fault: Cell::new(None),
+        }
+    }
+
+    /// The I2C bus the chip is on, for the driver.
+    pub fn bus(&self) -> SimBus<'_> {
+        SimBus { simulator: self }
+    }
+
+    /// The INT pin; waiting on it lets virtual time pass until it is asserted.
+    pub fn int_pin(&self) -> SimPin<'_> {
+        SimPin { simulator: self }
+    }
+
+    /// A delay that lets virtual time pass.
+    pub fn delay(&self) -> SimDelay<'_> {
+        SimDelay { simulator: self }
+    }
+
+    /// A counter of the edges on CLKOUT.
+    pub fn clkout(&self) -> SimClkout<'_> {
+        SimClkout { simulator: self }
+    }
+
+    /// Let `duration` of virtual time pass.
+    pub fn advance(&self, duration: Duration) {
+        self.state.borrow_mut().advance(duration);
+    }
+
+    /// The virtual time passed since the chip was created.
+    pub fn elapsed(&self) -> Duration {
+        self.state.borrow().elapsed
+    }
+
+    /// The register values, in address order.
+    pub fn registers(&self) -> [u8; 18] {
+        self.state.borrow().registers
+    }
+
+    /// The date and time the chip holds, `None` if the registers do not hold a valid one.
+    pub fn datetime(&self) -> Option<PrimitiveDateTime> {
+        self.state.borrow().datetime()
+    }
+
+    /// Is the INT pin asserted, i.e. low?
+    pub fn int_asserted(&self) -> bool {
+        self.state.borrow().int_asserted()
+    }
+
+    /// The frequency of CLKOUT in Hz, 0 while it is off.
+    pub fn clkout_hz(&self) -> u32 {
+        self.state.borrow().clkout_hz()
+    }
+
+    /// Both the main and the backup supply drop out: all registers return to their power-on
+    /// defaults, including the oscillator stop flag.
+    pub fn power_loss(&self) {
+        let mut state = self.state.borrow_mut();
+        *state = State {
+            elapsed: state.elapsed,
+            clkout_edges: state.clkout_edges,
+            ..State::new()
+        };
+    }
+
+    /// The supply sags for `duration`: the oscillator stops and the oscillator stop flag is
+    /// set, but the registers are retained. The time is behind by `duration` afterwards.
+    pub fn brown_out(&self, duration: Duration) {
+        let mut state = self.state.borrow_mut();
+        state.set_flag(Register::SECONDS, BitFlags::OS);
+        state.prescaler = 0;
+        state.elapsed += duration;
+    }
+
+    /// Cut the next write short after `bytes` register bytes, as a reset of the microcontroller
+    /// or a disturbed bus would. The registers written up to then keep their new values, and
+    /// the write returns a `SimError::Interrupted`. The pending fault is not part of snapshots.
+    pub fn interrupt_write_after(&self, bytes: usize) {
+        self.write_fault.set(Some(bytes));
+    }
+
+    /// A snapshot of the registers and the virtual time, to resume from with
+    /// [`restore`](Self::restore) or [`load`](Self::load).
+    pub fn save(&self) -> [u8; SNAPSHOT_LEN] {
+        self.state.borrow().to_bytes()
+    }
+
+    /// A simulator resumed from a snapshot, `None` if it is not valid.
+    pub fn load(snapshot: &[u8; SNAPSHOT_LEN]) -> Option<Self> {
+        State::from_bytes(snapshot).map(|state| Simulator {
+            state: RefCell::new(state),
+            write_fault: Cell::new(None),
+        })
+    }
+
+    /// Resume from a snapshot, keeping the buses and pins handed out. Returns `false`, leaving
+    /// the state unchanged, if the snapshot is not valid.
+    pub fn restore(&self, snapshot: &[u8; SNAPSHOT_LEN]) -> bool {
+        match State::from_bytes(snapshot) {
+            Some(state) => {
+                *self.state.borrow_mut() = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Let virtual time pass until the INT pin is asserted.
+    fn wait_for_int(&self) -> Result<(), SimError> {
+        let mut state = self.state.borrow_mut();
+        let mut waited = Duration::ZERO;
+        while !state.int_asserted() {
+            if !state.int_enabled() || waited >= MAX_WAIT {
+                return Err(SimError::NeverAsserted);
+            }
+            // the fast timer clocks count in between the seconds
+            let fast_timer = state.flag(Register::TIMER_MODE, BitFlags::TE)
+                && state.get(Register::TIMER_MODE) & BitFlags::TCF < 0b10 << 3;
+            let step = if fast_timer {
+                Duration::from_micros(100)
+            } else {
+                Duration::from_nanos(NS_PER_SECOND - state.prescaler)
+            };
+            state.advance(step);
+            waited += step;
+        }
+        Ok(())
+    }
+}
+
+/// The simulated chip's I2C bus, see [`Simulator::bus`]
+#[derive(Debug, Clone, Copy)]
+pub struct SimBus<'a> {
+    simulator: &'a Simulator,
+}
+
+impl i2c::ErrorType for SimBus<'_> {
+    type Error = SimError;
+}
+
+impl I2c for SimBus<'_> {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if address != DEVICE_ADDRESS {
+            return Err(SimError::NoAcknowledge);
+        }
+        let mut state = self.simulator.state.borrow_mut();
+        // the first byte written after a (repeated) start addresses a register
+        let mut addressing = true;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    for &byte in bytes.iter() {
+                        if addressing {
+                            state.pointer = byte % 18;
+                            addressing = false;
+                            continue;
+                        }
+                        match self.simulator.write_fault.get() {
+                            Some(0) => {
+                                self.simulator.write_fault.set(None);
+                                return Err(SimError::Interrupted);
+                            }
+                            Some(left) => self.simulator.write_fault.set(Some(left - 1)),
+                            None => {}
+                        }
+                        state.write(byte);
+                    }
+                }
+                Operation::Read(buffer) => {
+                    buffer.iter_mut().for_each(|byte| *byte = state.read());
+                    addressing = true;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The simulated chip's INT pin, see [`Simulator::int_pin`]
+#[derive(Debug, Clone, Copy)]
+pub struct SimPin<'a> {
+    simulator: &'a Simulator,
+}
+
+impl digital::ErrorType for SimPin<'_> {
+    type Error = SimError;
+}
+
+impl Wait for SimPin<'_> {
+    /// Returns `SimError::StuckLow`, as only the driver can release the pin.
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        if self.simulator.int_asserted() {
+            return Err(SimError::StuckLow);
+        }
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.simulator.wait_for_int()
+    }
+
+    /// Returns `SimError::StuckLow`, as only the driver can release the pin.
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        Err(SimError::StuckLow)
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        if self.simulator.int_asserted() {
+            return Err(SimError::StuckLow);
+        }
+        self.simulator.wait_for_int()
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_falling_edge().await
+    }
+}
+
+/// A delay on the simulator's virtual time, see [`Simulator::delay`]
+#[derive(Debug, Clone, Copy)]
+pub struct SimDelay<'a> {
+    simulator: &'a Simulator,
+}
+
+impl DelayNs for SimDelay<'_> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.simulator.advance(Duration::from_nanos(ns.into()));
+    }
+}
+
+/// A counter of the simulated CLKOUT edges, see [`Simulator::clkout`]
+#[derive(Debug, Clone, Copy)]
+pub struct SimClkout<'a> {
+    simulator: &'a Simulator,
+}
+
+impl EdgeCounter for SimClkout<'_> {
+    fn count(&mut self) -> u32 {
+        self.simulator.state.borrow().clkout_edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutputFrequency, PowerState, Tick, PCF85063};
+    use time::macros::datetime;
+
+    #[test]
+    fn counts_time_and_fires_alarm() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let mut int = simulator.int_pin();
+
+        block_on(async {
+            rtc.set_datetime(&datetime!(2099-12-31 23:59:58))
+                .await
+                .unwrap();
+            simulator.advance(Duration::from_secs(3));
+            assert_eq!(
+                datetime!(2000-01-01 00:00:01),
+                rtc.get_datetime().await.unwrap()
+            );
+
+            assert_eq!(Err(SimError::NeverAsserted), int.wait_for_low().await);
+            rtc.set_alarm_at(datetime!(2000-01-01 12:00:00))
+                .await
+                .unwrap();
+            rtc.arm_alarm_interrupt().await.unwrap();
+            int.wait_for_low().await.unwrap();
+            assert_eq!(
+                datetime!(2000-01-01 12:00:00),
+                rtc.get_datetime().await.unwrap()
+            );
+            assert_eq!(Err(SimError::StuckLow), int.wait_for_falling_edge().await);
+
+            rtc.clear_alarm_flag().await.unwrap();
+            assert!(!simulator.int_asserted());
+            rtc.wait_for_minute_tick(&mut int, Tick::Minute)
+                .await
+                .unwrap();
+            assert_eq!(
+                datetime!(2000-01-01 12:01:00),
+                rtc.get_datetime().await.unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn counts_down_and_outputs_clock() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let mut clkout = simulator.clkout();
+
+        block_on(async {
+            rtc.write_clock_output_frequency(OutputFrequency::Hz1024)
+                .await
+                .unwrap();
+            simulator.advance(Duration::from_millis(1500));
+            assert_eq!(1536, clkout.count());
+
+            rtc.set_wake(Duration::from_millis(250)).await.unwrap();
+            simulator.advance(Duration::from_millis(249));
+            assert!(!simulator.int_asserted());
+            simulator.advance(Duration::from_millis(1));
+            assert!(rtc.get_timer_flag().await.unwrap());
+            assert!(simulator.int_asserted());
+        });
+        assert_eq!(1024, simulator.clkout_hz());
+    }
+
+    #[test]
+    fn resumes_from_snapshot() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+
+        block_on(async {
+            rtc.set_datetime(&datetime!(2024-03-31 01:59:59))
+                .await
+                .unwrap();
+            rtc.write_clock_output_frequency(OutputFrequency::Hz1)
+                .await
+                .unwrap();
+        });
+        simulator.advance(Duration::from_millis(2500));
+        let snapshot = simulator.save();
+
+        simulator.advance(Duration::from_secs(3600));
+        let resumed = Simulator::load(&snapshot).unwrap();
+        resumed.advance(Duration::from_secs(3600));
+        assert_eq!(simulator.registers(), resumed.registers());
+        assert_eq!(simulator.elapsed(), resumed.elapsed());
+        assert_eq!(simulator.clkout().count(), resumed.clkout().count());
+
+        assert!(simulator.restore(&snapshot));
+        assert_eq!(Some(datetime!(2024-03-31 02:00:01)), simulator.datetime());
+        let mut corrupted = snapshot;
+        corrupted[5] ^= 1;
+        assert!(!simulator.restore(&corrupted));
+        assert!(Simulator::load(&corrupted).is_none());
+        assert_eq!(snapshot, simulator.save());
+    }
+
+    #[test]
+    fn models_supply_faults() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+
+        block_on(async {
+            assert_eq!(PowerState::PowerLost, rtc.power_state().await.unwrap());
+            rtc.set_datetime(&datetime!(2024-12-31 23:59:59))
+                .await
+                .unwrap();
+            rtc.enable_alarm_interrupt(true).await.unwrap();
+            assert_eq!(PowerState::Intact, rtc.power_state().await.unwrap());
+
+            // the time is written in part only
+            simulator.interrupt_write_after(3);
+            let written = rtc.set_datetime(&datetime!(2025-06-15 12:30:45)).await;
+            assert!(written.is_err());
+            assert_eq!(Some(datetime!(2024-12-31 12:30:45)), simulator.datetime());
+            rtc.set_datetime(&datetime!(2025-06-15 12:30:45))
+                .await
+                .unwrap();
+
+            simulator.brown_out(Duration::from_secs(10));
+            simulator.advance(Duration::from_secs(1));
+            assert_eq!(PowerState::TimeInvalid, rtc.power_state().await.unwrap());
+            assert_eq!(Some(datetime!(2025-06-15 12:30:46)), simulator.datetime());
+
+            simulator.power_loss();
+            assert_eq!(PowerState::PowerLost, rtc.power_state().await.unwrap());
+            assert_eq!(POWER_ON_DEFAULTS, simulator.registers());
+        });
+    }
+}
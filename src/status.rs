@@ -0,0 +1,90 @@
+//! A cheap summary of the chip state, for dashboards and periodic health logging.
+//!
+//! Unlike [`PCF85063::diagnostics`], which also reads the timer, [`PCF85063::status`] takes a
+//! single burst read.
+
+use super::{codec, BitFlags, Error, HourMode, OutputFrequency, Register, PCF85063};
+use embedded_hal_async::i2c::I2c;
+
+/// Chip state, as returned by [`PCF85063::status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChipStatus {
+    /// The STOP bit is clear.
+    pub running: bool,
+    /// The oscillator stop flag is clear: the clock integrity is guaranteed.
+    pub integrity: bool,
+    pub hour_mode: HourMode,
+    /// An alarm event happened and was not cleared yet.
+    pub alarm_flag: bool,
+    /// A timer or (half) minute event happened and was not cleared yet.
+    pub timer_flag: bool,
+    pub alarm_interrupt_enabled: bool,
+    pub minute_interrupt_enabled: bool,
+    pub half_minute_interrupt_enabled: bool,
+    pub clock_output: OutputFrequency,
+}
+
+impl ChipStatus {
+    /// Decode registers control 1 up to and including seconds.
+    pub(crate) fn decode(registers: [u8; 5]) -> Self {
+        let [control_1, control_2, _offset, _ram, seconds] = registers;
+        let flag = |value: u8, mask: u8| value & mask != 0;
+
+        ChipStatus {
+            running: !flag(control_1, BitFlags::STOP),
+            integrity: !flag(seconds, BitFlags::OS),
+            hour_mode: if flag(control_1, BitFlags::MODE_12_24) {
+                HourMode::H12
+            } else {
+                HourMode::H24
+            },
+            alarm_flag: flag(control_2, BitFlags::AF),
+            timer_flag: flag(control_2, BitFlags::TF),
+            alarm_interrupt_enabled: flag(control_2, BitFlags::AIE),
+            minute_interrupt_enabled: flag(control_2, BitFlags::MI),
+            half_minute_interrupt_enabled: flag(control_2, BitFlags::HMI),
+            clock_output: OutputFrequency::from_bits(control_2),
+        }
+    }
+
+    /// Is the chip keeping reliable time?
+    pub fn is_healthy(&self) -> bool {
+        self.running && self.integrity
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read control 1, control 2 and seconds in one burst, and decode the chip state.
+    pub async fn status(&mut self) -> Result<ChipStatus, Error<E>> {
+        // offset and RAM lie in between, and come along in the burst
+        let mut registers = [0; 5];
+        self.read_bytes(Register::CONTROL_1, &mut registers).await?;
+        Ok(codec::decode_status(registers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_status() {
+        let status = ChipStatus::decode([0x00, 0x00, 0x00, 0x00, 0x80]);
+        assert!(status.running);
+        assert!(!status.integrity);
+        assert!(!status.is_healthy());
+        assert_eq!(OutputFrequency::Hz32768, status.clock_output);
+
+        let status = ChipStatus::decode([0b0010_0010, 0b1100_1110, 0x00, 0x00, 0x00]);
+        assert!(!status.running);
+        assert!(status.integrity);
+        assert_eq!(HourMode::H12, status.hour_mode);
+        assert!(status.alarm_flag && status.timer_flag && status.alarm_interrupt_enabled);
+        assert!(!status.minute_interrupt_enabled);
+        assert_eq!(OutputFrequency::Hz1, status.clock_output);
+    }
+}
@@ -0,0 +1,84 @@
+//! Coarse measurement of long durations, hours to weeks, from RTC snapshots.
+//!
+//! A [`Stopwatch`] only holds the date and times it was started and last lapped, so durations
+//! are right across midnight and month ends. To survive a reset of the microcontroller, keep
+//! `started_at` e.g. in retained RAM or storage and restore it with `starting_at`:
+//!
+//! ```ignore
+//! let mut stopwatch = Stopwatch::start(&mut rtc).await?;
+//! // ...
+//! let since_start = stopwatch.elapsed(&mut rtc).await?;
+//! let since_last_lap = stopwatch.lap(&mut rtc).await?;
+//! ```
+
+use super::{Error, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{Duration, PrimitiveDateTime};
+
+/// A stopwatch with one-second resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stopwatch {
+    start: PrimitiveDateTime,
+    lap: PrimitiveDateTime,
+}
+
+impl Stopwatch {
+    /// A stopwatch started at `start`, e.g. restored after a reset.
+    pub const fn starting_at(start: PrimitiveDateTime) -> Self {
+        Stopwatch { start, lap: start }
+    }
+
+    /// Start a stopwatch at the current time.
+    pub async fn start<I2C, E>(rtc: &mut PCF85063<I2C>) -> Result<Self, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+    {
+        Ok(Self::starting_at(rtc.get_datetime().await?))
+    }
+
+    pub fn started_at(&self) -> PrimitiveDateTime {
+        self.start
+    }
+
+    /// Time elapsed since the start.
+    pub async fn elapsed<I2C, E>(&self, rtc: &mut PCF85063<I2C>) -> Result<Duration, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+    {
+        Ok(rtc.get_datetime().await? - self.start)
+    }
+
+    /// Time elapsed since the previous lap, or the start for the first one.
+    pub async fn lap<I2C, E>(&mut self, rtc: &mut PCF85063<I2C>) -> Result<Duration, Error<E>>
+    where
+        I2C: I2c<Error = E>,
+    {
+        Ok(self.lap_at(rtc.get_datetime().await?))
+    }
+
+    fn lap_at(&mut self, now: PrimitiveDateTime) -> Duration {
+        let lap = now - self.lap;
+        self.lap = now;
+        lap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn laps_across_month_end() {
+        let mut stopwatch = Stopwatch::starting_at(datetime!(2024-02-28 22:00:00));
+        assert_eq!(
+            Duration::hours(26),
+            stopwatch.lap_at(datetime!(2024-03-01 00:00:00))
+        );
+        assert_eq!(
+            Duration::seconds(90),
+            stopwatch.lap_at(datetime!(2024-03-01 00:01:30))
+        );
+        assert_eq!(datetime!(2024-02-28 22:00:00), stopwatch.started_at());
+    }
+}
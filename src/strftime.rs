@@ -0,0 +1,131 @@
+//! A minimal strftime-like formatter writing into a byte buffer, for user-configurable date
+//! formats without a formatting crate.
+//!
+//! Supported are `%Y` (year), `%m` (month), `%d` (day), `%H` (hour), `%M` (minute), `%S`
+//! (second), all zero-padded, the names `%a` (weekday) and `%b` (month) and `%%`:
+//!
+//! ```
+//! use pcf85063a::names::Names;
+//! use time::macros::datetime;
+//!
+//! let mut buffer = [0; 32];
+//! let len = pcf85063a::strftime::format(
+//!     "%a %d %b %Y %H:%M",
+//!     &datetime!(2024-02-29 23:30:59),
+//!     &Names::ENGLISH_SHORT,
+//!     &mut buffer,
+//! )
+//! .unwrap();
+//! assert_eq!(b"Thu 29 Feb 2024 23:30", &buffer[..len]);
+//! ```
+
+use super::names::Names;
+use time::PrimitiveDateTime;
+
+/// Errors of [`format()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The output does not fit in the buffer
+    BufferFull,
+    /// The pattern holds an unsupported specifier, or ends in a lone `%`
+    InvalidPattern,
+}
+
+/// Format `datetime` according to `pattern` into `buffer`, returning the number of bytes
+/// written.
+pub fn format(
+    pattern: &str,
+    datetime: &PrimitiveDateTime,
+    names: &Names,
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let mut out = Output { buffer, len: 0 };
+    let mut bytes = pattern.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'%' {
+            out.push(&[byte])?;
+            continue;
+        }
+        match bytes.next().ok_or(Error::InvalidPattern)? {
+            b'Y' => out.number(datetime.year().unsigned_abs(), 4)?,
+            b'm' => out.number(u8::from(datetime.month()) as u32, 2)?,
+            b'd' => out.number(datetime.day() as u32, 2)?,
+            b'H' => out.number(datetime.hour() as u32, 2)?,
+            b'M' => out.number(datetime.minute() as u32, 2)?,
+            b'S' => out.number(datetime.second() as u32, 2)?,
+            b'a' => out.push(names.weekday(datetime.weekday()).as_bytes())?,
+            b'b' => out.push(names.month(datetime.month()).as_bytes())?,
+            b'%' => out.push(b"%")?,
+            _ => return Err(Error::InvalidPattern),
+        }
+    }
+    Ok(out.len)
+}
+
+struct Output<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl Output<'_> {
+    fn push(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let end = self.len + bytes.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(Error::BufferFull)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    /// Push `value` in decimal, zero-padded to at least `width` digits.
+    fn number(&mut self, value: u32, width: usize) -> Result<(), Error> {
+        let mut digits = [b'0'; 10];
+        let mut value = value;
+        let mut count = 0;
+        while value > 0 || count < width {
+            digits[digits.len() - 1 - count] = b'0' + (value % 10) as u8;
+            value /= 10;
+            count += 1;
+        }
+        self.push(&digits[digits.len() - count..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn formats_all_specifiers() {
+        let mut buffer = [0; 40];
+        let len = format(
+            "%Y-%m-%d %H:%M:%S %a %b 100%%",
+            &datetime!(2025-01-05 07:08:09),
+            &Names::ENGLISH_SHORT,
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(b"2025-01-05 07:08:09 Sun Jan 100%", &buffer[..len]);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let datetime = datetime!(2025-01-05 07:08:09);
+        let names = Names::ENGLISH_SHORT;
+        assert_eq!(
+            Err(Error::BufferFull),
+            format("%Y", &datetime, &names, &mut [0; 3])
+        );
+        assert_eq!(
+            Err(Error::InvalidPattern),
+            format("%q", &datetime, &names, &mut [0; 8])
+        );
+        assert_eq!(
+            Err(Error::InvalidPattern),
+            format("%", &datetime, &names, &mut [0; 8])
+        );
+    }
+}
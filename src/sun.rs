@@ -0,0 +1,214 @@
+//! Sunrise and sunset, and setting the alarm to them, enabled with the `sun` feature.
+//!
+//! The times follow the NOAA general solar position approximation, accurate to a couple of
+//! minutes away from the polar circles; good enough for lighting, irrigation or cameras. After
+//! each alarm, arm the next one:
+//!
+//! ```ignore
+//! const GARDEN: Location = Location {
+//!     latitude: 52.37,
+//!     longitude: 4.90,
+//!     offset: time::macros::offset!(+1),
+//! };
+//!
+//! loop {
+//!     rtc.arm_sun_alarm(&GARDEN, SunEvent::Sunset).await?;
+//!     rtc.wait_for_alarm_polling(&mut delay, 1000).await?;
+//!     lights_on();
+//! }
+//! ```
+
+use super::{Error, PCF85063};
+use core::f64::consts::PI;
+use embedded_hal_async::i2c::I2c;
+use libm::{acos, cos, sin, tan};
+use time::{Date, Duration, PrimitiveDateTime, UtcOffset};
+
+/// Zenith angle of the sun's center at sunrise and sunset, including refraction, in degrees.
+const ZENITH: f64 = 90.833;
+/// Days ahead to look for the next event, within the reach of the alarm.
+const SEARCH_DAYS: u16 = 28;
+
+/// Where on earth, and the offset from UTC of the time the chip keeps there
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    /// Degrees north
+    pub latitude: f64,
+    /// Degrees east
+    pub longitude: f64,
+    pub offset: UtcOffset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SunEvent {
+    Sunrise,
+    Sunset,
+}
+
+/// Daylight on a date, as returned by [`sun_times`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Daylight {
+    /// Times in the location's local time
+    Times {
+        sunrise: PrimitiveDateTime,
+        sunset: PrimitiveDateTime,
+    },
+    /// The sun does not set
+    PolarDay,
+    /// The sun does not rise
+    PolarNight,
+}
+
+impl Daylight {
+    /// The time of `event`, `None` if it does not happen.
+    pub fn get(self, event: SunEvent) -> Option<PrimitiveDateTime> {
+        match (self, event) {
+            (Daylight::Times { sunrise, .. }, SunEvent::Sunrise) => Some(sunrise),
+            (Daylight::Times { sunset, .. }, SunEvent::Sunset) => Some(sunset),
+            _ => None,
+        }
+    }
+}
+
+/// Sunrise and sunset at `location` on `date`, in its local time.
+pub fn sun_times(date: Date, location: &Location) -> Daylight {
+    let gamma = 2.0 * PI / 365.0 * (date.ordinal() - 1) as f64;
+    let equation_of_time = 229.18
+        * (0.000075 + 0.001868 * cos(gamma)
+            - 0.032077 * sin(gamma)
+            - 0.014615 * cos(2.0 * gamma)
+            - 0.040849 * sin(2.0 * gamma));
+    let declination = 0.006918 - 0.399912 * cos(gamma) + 0.070257 * sin(gamma)
+        - 0.006758 * cos(2.0 * gamma)
+        + 0.000907 * sin(2.0 * gamma)
+        - 0.002697 * cos(3.0 * gamma)
+        + 0.00148 * sin(3.0 * gamma);
+
+    let latitude = location.latitude.to_radians();
+    let cos_hour_angle = cos(ZENITH.to_radians()) / (cos(latitude) * cos(declination))
+        - tan(latitude) * tan(declination);
+    if cos_hour_angle > 1.0 {
+        return Daylight::PolarNight;
+    }
+    if cos_hour_angle < -1.0 {
+        return Daylight::PolarDay;
+    }
+    let hour_angle = acos(cos_hour_angle).to_degrees();
+
+    // minutes after midnight UTC
+    let at = |minutes: f64| {
+        let seconds = (minutes * 60.0) as i64 + location.offset.whole_seconds() as i64;
+        date.midnight() + Duration::seconds(seconds)
+    };
+    Daylight::Times {
+        sunrise: at(720.0 - 4.0 * (location.longitude + hour_angle) - equation_of_time),
+        sunset: at(720.0 - 4.0 * (location.longitude - hour_angle) - equation_of_time),
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Set the alarm to the next `event` at `location`, clear the alarm flag and enable the
+    /// alarm interrupt. Returns the time the alarm was set to.
+    ///
+    /// Returns `None` without changing the alarm if the event does not happen in the coming
+    /// four weeks, in the polar day or night.
+    pub async fn arm_sun_alarm(
+        &mut self,
+        location: &Location,
+        event: SunEvent,
+    ) -> Result<Option<PrimitiveDateTime>, Error<E>> {
+        let now = self.get_datetime().await?;
+        let Some(at) = next_event(now, location, event) else {
+            return Ok(None);
+        };
+        self.set_alarm_at(at).await?;
+        self.arm_alarm_interrupt().await?;
+        Ok(Some(at))
+    }
+}
+
+/// The first `event` after `now`, looking a few weeks ahead.
+fn next_event(
+    now: PrimitiveDateTime,
+    location: &Location,
+    event: SunEvent,
+) -> Option<PrimitiveDateTime> {
+    // the local time of an event may fall on the day before its UTC date
+    let mut date = now.date().previous_day()?;
+    for _ in 0..=SEARCH_DAYS {
+        if let Some(at) = sun_times(date, location).get(event).filter(|&at| at > now) {
+            return Some(at);
+        }
+        date = date.next_day()?;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, datetime, offset};
+
+    const AMSTERDAM: Location = Location {
+        latitude: 52.37,
+        longitude: 4.90,
+        offset: offset!(+2),
+    };
+
+    const TROMSO: Location = Location {
+        latitude: 69.65,
+        longitude: 18.96,
+        offset: offset!(+1),
+    };
+
+    fn assert_near(expected: PrimitiveDateTime, actual: Option<PrimitiveDateTime>) {
+        let difference = (actual.unwrap() - expected).abs();
+        assert!(difference < Duration::minutes(3), "{actual:?}");
+    }
+
+    #[test]
+    fn calculates_sunrise_and_sunset() {
+        let daylight = sun_times(date!(2024 - 06 - 21), &AMSTERDAM);
+        assert_near(
+            datetime!(2024-06-21 05:18:00),
+            daylight.get(SunEvent::Sunrise),
+        );
+        assert_near(
+            datetime!(2024-06-21 22:06:00),
+            daylight.get(SunEvent::Sunset),
+        );
+
+        assert_eq!(
+            Daylight::PolarDay,
+            sun_times(date!(2024 - 06 - 21), &TROMSO)
+        );
+        assert_eq!(
+            Daylight::PolarNight,
+            sun_times(date!(2024 - 12 - 21), &TROMSO)
+        );
+    }
+
+    #[test]
+    fn finds_next_event() {
+        assert_near(
+            datetime!(2024-06-21 22:06:00),
+            next_event(datetime!(2024-06-21 12:00:00), &AMSTERDAM, SunEvent::Sunset),
+        );
+        assert_near(
+            datetime!(2024-06-22 05:18:00),
+            next_event(
+                datetime!(2024-06-21 12:00:00),
+                &AMSTERDAM,
+                SunEvent::Sunrise,
+            ),
+        );
+        assert_eq!(
+            None,
+            next_event(datetime!(2024-06-01 12:00:00), &TROMSO, SunEvent::Sunset)
+        );
+    }
+}
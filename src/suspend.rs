@@ -0,0 +1,130 @@
+//! Suspending the driver across deep sleep, for firmware that tears down its peripherals.
+//!
+//! `suspend` hands back the bus together with the driver's settings as a small blob that can be
+//! kept in retained RAM or backup registers; `resume` rebuilds the driver from it without
+//! touching the chip. Bus traffic counters and the access log start afresh.
+
+use super::persist::crc8;
+use super::PCF85063;
+use time::Duration;
+
+/// Number of bytes taken by an encoded [`DriverState`].
+pub const DRIVER_STATE_LEN: usize = 31;
+
+const MAGIC: u8 = 0x63;
+const HAS_BACKWARDS_LIMIT: u8 = 0b0000_0001;
+const CHECK_WEEKDAY: u8 = 0b0000_0010;
+const HAS_WRITE_THRESHOLD: u8 = 0b0000_0100;
+
+/// The settings of a suspended driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriverState {
+    base_year: i32,
+    backwards_limit: Option<Duration>,
+    write_threshold: Option<Duration>,
+    check_weekday: bool,
+}
+
+impl DriverState {
+    /// Encode as bytes with a checksum.
+    pub fn to_bytes(&self) -> [u8; DRIVER_STATE_LEN] {
+        let mut bytes = [0; DRIVER_STATE_LEN];
+        bytes[0] = MAGIC;
+        bytes[1..5].copy_from_slice(&self.base_year.to_le_bytes());
+        if self.check_weekday {
+            bytes[5] |= CHECK_WEEKDAY;
+        }
+        if let Some(limit) = self.backwards_limit {
+            bytes[5] |= HAS_BACKWARDS_LIMIT;
+            encode_duration(limit, &mut bytes[6..18]);
+        }
+        if let Some(threshold) = self.write_threshold {
+            bytes[5] |= HAS_WRITE_THRESHOLD;
+            encode_duration(threshold, &mut bytes[18..30]);
+        }
+        bytes[30] = crc8(&bytes[..30]);
+        bytes
+    }
+
+    /// Decode bytes, `None` if they are not a valid encoded state.
+    pub fn from_bytes(bytes: &[u8; DRIVER_STATE_LEN]) -> Option<Self> {
+        if bytes[0] != MAGIC || bytes[30] != crc8(&bytes[..30]) {
+            return None;
+        }
+        let mut base_year = [0; 4];
+        base_year.copy_from_slice(&bytes[1..5]);
+        let base_year = i32::from_le_bytes(base_year);
+        if base_year % 4 != 0 {
+            return None;
+        }
+        let flag = |mask: u8| bytes[5] & mask != 0;
+        Some(DriverState {
+            base_year,
+            backwards_limit: flag(HAS_BACKWARDS_LIMIT).then(|| decode_duration(&bytes[6..18])),
+            write_threshold: flag(HAS_WRITE_THRESHOLD).then(|| decode_duration(&bytes[18..30])),
+            check_weekday: bytes[5] & CHECK_WEEKDAY != 0,
+        })
+    }
+}
+
+impl<I2C> PCF85063<I2C> {
+    /// Release the I2C bus, keeping the driver's settings for `resume`.
+    pub fn suspend(self) -> (I2C, DriverState) {
+        let state = DriverState {
+            base_year: self.base_year,
+            backwards_limit: self.backwards_limit,
+            write_threshold: self.write_threshold,
+            check_weekday: self.check_weekday,
+        };
+        (self.i2c, state)
+    }
+
+    /// Rebuild a driver suspended with `suspend`, without accessing the chip.
+    pub fn resume(i2c: I2C, state: DriverState) -> Self {
+        let mut rtc = Self::with_base_year(i2c, state.base_year);
+        rtc.backwards_limit = state.backwards_limit;
+        rtc.write_threshold = state.write_threshold;
+        rtc.check_weekday = state.check_weekday;
+        rtc
+    }
+}
+
+/// Encode `duration` into 12 bytes: the whole seconds and the nanoseconds.
+fn encode_duration(duration: Duration, bytes: &mut [u8]) {
+    bytes[..8].copy_from_slice(&duration.whole_seconds().to_le_bytes());
+    bytes[8..12].copy_from_slice(&duration.subsec_nanoseconds().to_le_bytes());
+}
+
+fn decode_duration(bytes: &[u8]) -> Duration {
+    let mut seconds = [0; 8];
+    seconds.copy_from_slice(&bytes[..8]);
+    let mut nanoseconds = [0; 4];
+    nanoseconds.copy_from_slice(&bytes[8..12]);
+    Duration::new(i64::from_le_bytes(seconds), i32::from_le_bytes(nanoseconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_round_trip_state() {
+        let state = DriverState {
+            base_year: 1972,
+            backwards_limit: Some(Duration::new(-90, -500)),
+            write_threshold: Some(Duration::milliseconds(1500)),
+            check_weekday: true,
+        };
+        assert_eq!(Some(state), DriverState::from_bytes(&state.to_bytes()));
+
+        let state = DriverState {
+            backwards_limit: None,
+            write_threshold: None,
+            ..state
+        };
+        let mut bytes = state.to_bytes();
+        assert_eq!(Some(state), DriverState::from_bytes(&bytes));
+        bytes[2] ^= 1;
+        assert_eq!(None, DriverState::from_bytes(&bytes));
+    }
+}
@@ -0,0 +1,141 @@
+//! A compact message for distributing the time from a coordinator to leaf nodes.
+//!
+//! The coordinator reads its RTC into a [`TimeSync`] and sends the encoded bytes over UART or
+//! radio; each leaf applies them to its own RTC in one call, and may relay the message further
+//! with one hop more:
+//!
+//! ```ignore
+//! // coordinator
+//! let message = rtc.get_time_sync().await?;
+//! radio.broadcast(&message.to_bytes().unwrap()).await;
+//!
+//! // leaf
+//! let bytes: [u8; TIME_SYNC_LEN] = radio.receive().await;
+//! let (message, _) = rtc.apply_time_sync(&bytes).await?;
+//! ```
+//!
+//! The time is sent as 32-bit Unix seconds, assuming the clocks keep UTC. The transit delay is
+//! not compensated for; over a slow link, a leaf can add it before applying the message.
+
+use super::persist::crc8;
+use super::{codec, Error, RawDateTime, Register, UsageError, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+/// Number of bytes taken by an encoded [`TimeSync`].
+pub const TIME_SYNC_LEN: usize = 8;
+
+const MAGIC: u8 = 0x5c;
+const INTEGRITY: u8 = 0b1000_0000;
+
+/// The time as distributed to other nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSync {
+    /// Date and time of the sender, in UTC
+    pub datetime: PrimitiveDateTime,
+    /// The sender's oscillator kept running since its time was set
+    pub integrity: bool,
+    /// Number of times the message was relayed, 0 when sent by the coordinator
+    pub hops: u8,
+}
+
+impl TimeSync {
+    /// Encode as bytes with a checksum, `None` if the date and time do not fit 32-bit Unix
+    /// seconds (1970 up to 2106).
+    pub fn to_bytes(&self) -> Option<[u8; TIME_SYNC_LEN]> {
+        let seconds = self.datetime.assume_utc().unix_timestamp();
+        let seconds = u32::try_from(seconds).ok()?;
+        let mut bytes = [0; TIME_SYNC_LEN];
+        bytes[0] = MAGIC;
+        bytes[1..5].copy_from_slice(&seconds.to_le_bytes());
+        bytes[5] = if self.integrity { INTEGRITY } else { 0 };
+        bytes[6] = self.hops;
+        bytes[7] = crc8(&bytes[..7]);
+        Some(bytes)
+    }
+
+    /// Decode bytes, `None` if they are not a valid encoded message.
+    pub fn from_bytes(bytes: &[u8; TIME_SYNC_LEN]) -> Option<Self> {
+        if bytes[0] != MAGIC || bytes[7] != crc8(&bytes[..7]) {
+            return None;
+        }
+        let mut seconds = [0; 4];
+        seconds.copy_from_slice(&bytes[1..5]);
+        let datetime =
+            OffsetDateTime::from_unix_timestamp(u32::from_le_bytes(seconds).into()).ok()?;
+        Some(TimeSync {
+            datetime: PrimitiveDateTime::new(datetime.date(), datetime.time()),
+            integrity: bytes[5] & INTEGRITY != 0,
+            hops: bytes[6],
+        })
+    }
+
+    /// The same message one hop further, for relaying.
+    pub fn relayed(&self) -> Self {
+        TimeSync {
+            hops: self.hops.saturating_add(1),
+            ..*self
+        }
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the date and time and the oscillator stop flag into a message to send, with no hops.
+    pub async fn get_time_sync(&mut self) -> Result<TimeSync, Error<E>> {
+        let mut data = [0; 7];
+        self.read_bytes(Register::SECONDS, &mut data).await?;
+        let datetime = codec::decode_datetime(data, self.base_year, self.check_weekday)
+            .map_err(Error::Data)?;
+        Ok(TimeSync {
+            datetime,
+            integrity: !RawDateTime::from_bytes_unchecked(data).oscillator_stopped,
+            hops: 0,
+        })
+    }
+
+    /// Set the date and time from a received message, and return it with whether the clock
+    /// was written.
+    ///
+    /// The time is set with `set_datetime`, so the backwards limit and the write threshold
+    /// apply. Will return a 'UsageError::InvalidInputData' if the bytes are not a valid
+    /// message, or the sender's clock lost its integrity.
+    pub async fn apply_time_sync(
+        &mut self,
+        bytes: &[u8; TIME_SYNC_LEN],
+    ) -> Result<(TimeSync, bool), Error<E>> {
+        let message = TimeSync::from_bytes(bytes)
+            .filter(|message| message.integrity)
+            .ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        let written = self.set_datetime(&message.datetime).await?;
+        Ok((message, written))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn can_round_trip_messages() {
+        let message = TimeSync {
+            datetime: datetime!(2024-02-29 23:30:59),
+            integrity: true,
+            hops: 2,
+        };
+        let mut bytes = message.to_bytes().unwrap();
+        assert_eq!(Some(message), TimeSync::from_bytes(&bytes));
+        assert_eq!(3, message.relayed().hops);
+
+        bytes[3] ^= 1;
+        assert_eq!(None, TimeSync::from_bytes(&bytes));
+        let message = TimeSync {
+            datetime: datetime!(2107-01-01 0:00),
+            ..message
+        };
+        assert_eq!(None, message.to_bytes());
+    }
+}
@@ -0,0 +1,161 @@
+//! Minute and half-minute interrupts, and waiting for them on the INT pin.
+
+#[allow(deprecated)]
+use super::Control;
+use super::{BitFlags, Error, Register, UsageError, PCF85063};
+use embedded_hal_async::{digital::Wait, i2c::I2c};
+
+/// Period of the heartbeat generated by the minute interrupts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Tick {
+    /// Every minute (MI)
+    Minute,
+    /// Every half minute (HMI)
+    HalfMinute,
+}
+
+impl Tick {
+    fn bit(self) -> u8 {
+        match self {
+            Tick::Minute => BitFlags::MI,
+            Tick::HalfMinute => BitFlags::HMI,
+        }
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Enable or disable the minute interrupt.
+    pub async fn enable_minute_interrupt(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.enable_tick(Tick::Minute, enabled).await
+    }
+
+    /// Enable or disable the half minute interrupt.
+    pub async fn enable_half_minute_interrupt(&mut self, enabled: bool) -> Result<(), Error<E>> {
+        self.enable_tick(Tick::HalfMinute, enabled).await
+    }
+
+    /// Enable or disable the minute interrupt.
+    #[deprecated(note = "use `enable_minute_interrupt`")]
+    #[allow(deprecated)]
+    pub async fn control_minute_interrupt(&mut self, status: Control) -> Result<(), Error<E>> {
+        self.enable_minute_interrupt(status.into()).await
+    }
+
+    /// Enable or disable the half minute interrupt.
+    #[deprecated(note = "use `enable_half_minute_interrupt`")]
+    #[allow(deprecated)]
+    pub async fn control_half_minute_interrupt(&mut self, status: Control) -> Result<(), Error<E>> {
+        self.enable_half_minute_interrupt(status.into()).await
+    }
+
+    async fn enable_tick(&mut self, tick: Tick, enabled: bool) -> Result<(), Error<E>> {
+        if enabled {
+            self.set_register_bit_flag(Register::CONTROL_2, tick.bit())
+                .await
+        } else {
+            self.clear_register_bit_flag(Register::CONTROL_2, tick.bit())
+                .await
+        }
+    }
+
+    /// Get the timer flag, which is also set by the minute interrupts.
+    pub async fn get_timer_flag(&mut self) -> Result<bool, Error<E>> {
+        self.is_register_bit_flag_high(Register::CONTROL_2, BitFlags::TF)
+            .await
+    }
+
+    /// Clear the timer flag, leaving the alarm flag unchanged.
+    pub async fn clear_timer_flag(&mut self) -> Result<(), Error<E>> {
+        let value = self.read_register(Register::CONTROL_2).await?;
+        // flags are cleared by writing 0, writing 1 leaves them unchanged
+        let value = (value | BitFlags::AF) & !BitFlags::TF;
+        self.write_register(Register::CONTROL_2, value).await
+    }
+
+    /// Enable the minute or half-minute interrupt, wait for it on the INT pin, and
+    /// acknowledge it.
+    ///
+    /// Call this in a loop for a heartbeat aligned with the start of every (half) minute. The
+    /// INT pin is active low; a pin error is returned as 'Error::Pin'. The pin is shared with
+    /// the alarm and with other devices on the line, so the timer flag is checked each time it
+    /// is low. Another device holding the line is waited out. A pending alarm with its
+    /// interrupt enabled holds the line until acknowledged, so the tick can not be seen: a
+    /// 'UsageError::InUse' is returned then, leaving the alarm flag for the caller.
+    pub async fn wait_for_minute_tick(
+        &mut self,
+        int: &mut impl Wait,
+        tick: Tick,
+    ) -> Result<(), Error<E>> {
+        self.set_register_bit_flag(Register::CONTROL_2, tick.bit())
+            .await?;
+        // a stale flag would keep the pin low in permanent interrupt mode
+        self.clear_timer_flag().await?;
+
+        loop {
+            int.wait_for_low().await.map_err(|_| Error::Pin)?;
+            let control_2 = self.read_register(Register::CONTROL_2).await?;
+            if control_2 & BitFlags::TF != 0 {
+                return self.clear_timer_flag().await;
+            }
+            if control_2 & BitFlags::AF != 0 && control_2 & BitFlags::AIE != 0 {
+                return Err(Error::Usage(UsageError::InUse));
+            }
+            int.wait_for_high().await.map_err(|_| Error::Pin)?;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use time::macros::datetime;
+
+    #[test]
+    fn ticks_with_alarm_armed() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:10)).await?;
+            rtc.set_alarm_at(datetime!(2024-02-29 12:05:00)).await?;
+            rtc.arm_alarm_interrupt().await?;
+            rtc.wait_for_minute_tick(&mut simulator.int_pin(), Tick::Minute)
+                .await
+        })
+        .unwrap();
+
+        assert_eq!(
+            datetime!(2024-02-29 12:01:00),
+            simulator.datetime().unwrap()
+        );
+    }
+
+    #[test]
+    fn pending_alarm_is_not_a_tick() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let (result, alarm_flag) = block_on(async {
+            rtc.set_datetime(&datetime!(2024-02-29 12:00:10)).await?;
+            rtc.set_alarm_at(datetime!(2024-02-29 12:00:20)).await?;
+            rtc.arm_alarm_interrupt().await?;
+            simulator.int_pin().wait_for_low().await.unwrap();
+
+            let result = rtc
+                .wait_for_minute_tick(&mut simulator.int_pin(), Tick::Minute)
+                .await;
+            Ok::<_, Error<_>>((result, rtc.get_alarm_flag().await?))
+        })
+        .unwrap();
+
+        assert!(matches!(result, Err(Error::Usage(UsageError::InUse))));
+        assert!(alarm_flag);
+        assert_eq!(
+            datetime!(2024-02-29 12:00:20),
+            simulator.datetime().unwrap()
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Serving the time from the MCU's fast clock, reading the RTC only now and then.
+//!
+//! Applications that timestamp often would otherwise read the RTC for every timestamp.
+//! [`TimeKeeper`] reads it once per sync interval, e.g. hourly, and serves the time from an
+//! application monotonic milliseconds counter in between. At every sync the rate of the counter
+//! is measured against the RTC, so its drift is corrected for until the next one.
+//!
+//! ```ignore
+//! let mut keeper = TimeKeeper::new(3_600_000);
+//! let now_ms = || embassy_time::Instant::now().as_millis();
+//!
+//! // syncs at the first call and then hourly, taking up to a second when it does
+//! let timestamp = rtc.get_datetime_from_keeper(&mut keeper, &mut delay, now_ms).await?;
+//! ```
+
+use super::{DataError, Error, FineClock, PCF85063};
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+use time::{Duration, PrimitiveDateTime};
+
+/// Poll interval while looking for the start of a second.
+const SYNC_POLL_MS: u32 = 1;
+/// Largest plausible drift of the monotonic counter; beyond it the time was changed.
+const MAX_DRIFT_PPM: i64 = 10_000;
+
+/// The RTC time anchored to a monotonic milliseconds counter, with its measured drift.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeKeeper {
+    /// The start of an RTC second, anchored to the monotonic time it was seen at
+    clock: FineClock,
+    /// How much faster the RTC runs than the monotonic counter, in ppm
+    drift_ppm: i32,
+    sync_interval_ms: u64,
+}
+
+impl TimeKeeper {
+    /// Keep the time, syncing with the RTC every `sync_interval_ms`.
+    pub const fn new(sync_interval_ms: u64) -> Self {
+        TimeKeeper {
+            clock: FineClock::new(),
+            drift_ppm: 0,
+            sync_interval_ms,
+        }
+    }
+
+    /// Sync with the start of the RTC second `datetime`, seen at `monotonic_ms`, and measure
+    /// the drift since the previous sync.
+    pub fn sync(&mut self, datetime: PrimitiveDateTime, monotonic_ms: u64) {
+        self.drift_ppm = self
+            .clock
+            .anchored()
+            .and_then(|previous| {
+                let rtc_ms = (datetime - previous).whole_milliseconds() as i64;
+                let monotonic_ms = i64::try_from(self.clock.since_anchor(monotonic_ms)?).ok()?;
+                if monotonic_ms == 0 {
+                    return None;
+                }
+                let ppm = (rtc_ms - monotonic_ms) * 1_000_000 / monotonic_ms;
+                (ppm.abs() <= MAX_DRIFT_PPM).then_some(ppm as i32)
+            })
+            .unwrap_or(0);
+        self.clock.anchor(datetime, monotonic_ms);
+    }
+
+    /// Whether the next sync is due at `monotonic_ms`.
+    pub fn needs_sync(&self, monotonic_ms: u64) -> bool {
+        self.clock.anchored().is_none()
+            || self
+                .clock
+                .since_anchor(monotonic_ms)
+                .is_some_and(|elapsed| elapsed >= self.sync_interval_ms)
+    }
+
+    /// The measured drift of the RTC against the monotonic counter, in ppm.
+    pub fn drift_ppm(&self) -> i32 {
+        self.drift_ppm
+    }
+
+    /// The time at `monotonic_ms`, `None` if never synced or `monotonic_ms` lies before the
+    /// last sync.
+    pub fn now(&self, monotonic_ms: u64) -> Option<PrimitiveDateTime> {
+        let elapsed = i64::try_from(self.clock.since_anchor(monotonic_ms)?).ok()?;
+        let corrected = elapsed + elapsed * self.drift_ppm as i64 / 1_000_000;
+        self.clock
+            .anchored()?
+            .checked_add(Duration::milliseconds(corrected))
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Wait for the next second to start and sync `keeper` to it, with `now_ms` reading the
+    /// monotonic milliseconds counter.
+    pub async fn sync_time_keeper(
+        &mut self,
+        keeper: &mut TimeKeeper,
+        delay: &mut impl DelayNs,
+        mut now_ms: impl FnMut() -> u64,
+    ) -> Result<(), Error<E>> {
+        self.wait_for_second_change(delay, SYNC_POLL_MS).await?;
+        let monotonic_ms = now_ms();
+        let datetime = self.get_datetime().await?;
+        keeper.sync(datetime, monotonic_ms);
+        Ok(())
+    }
+
+    /// The time from `keeper`, syncing it with the RTC first if due.
+    pub async fn get_datetime_from_keeper(
+        &mut self,
+        keeper: &mut TimeKeeper,
+        delay: &mut impl DelayNs,
+        mut now_ms: impl FnMut() -> u64,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        if keeper.needs_sync(now_ms()) {
+            self.sync_time_keeper(keeper, delay, &mut now_ms).await?;
+        }
+        keeper
+            .now(now_ms())
+            .ok_or(Error::Data(DataError::ComponentRange))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn corrects_measured_drift() {
+        let mut keeper = TimeKeeper::new(3_600_000);
+        assert!(keeper.needs_sync(0));
+        assert_eq!(None, keeper.now(0));
+
+        keeper.sync(datetime!(2025-01-01 00:00:00), 0);
+        assert!(!keeper.needs_sync(1000));
+        assert_eq!(Some(datetime!(2025-01-01 00:00:01)), keeper.now(1000));
+
+        // the monotonic counter runs 100 ppm slow
+        keeper.sync(datetime!(2025-01-01 01:00:00), 3_599_640);
+        assert_eq!(100, keeper.drift_ppm());
+        assert!(keeper.needs_sync(3_599_640 + 3_600_000));
+        assert_eq!(
+            Some(datetime!(2025-01-01 01:00:10.001)),
+            keeper.now(3_599_640 + 10_000)
+        );
+
+        // the time was set in between
+        keeper.sync(datetime!(2025-06-01 00:00:00), 7_200_000);
+        assert_eq!(0, keeper.drift_ppm());
+    }
+}
@@ -0,0 +1,192 @@
+//! A bus wrapper that bounds the duration of every I2C transaction.
+//!
+//! A wedged bus (a missing pull-up, a peripheral holding SDA low) can make an I2C future never
+//! complete. Wrapping the bus in [`WithTimeout`] before handing it to the driver turns such a hang
+//! into an [`Error::Timeout`]:
+//!
+//! ```ignore
+//! let bus = pcf85063a::timeout::WithTimeout::new(i2c, embassy_time::Delay, 10_000);
+//! let mut rtc = pcf85063a::PCF85063::new(bus);
+//!
+//! match rtc.get_datetime().await {
+//!     Err(pcf85063a::Error::I2C(pcf85063a::timeout::Error::Timeout)) => { /* bus is stuck */ }
+//!     _ => {}
+//! }
+//! ```
+
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::{self, ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+
+/// Errors of a bus wrapped in [`WithTimeout`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// Error of the underlying I2C bus
+    I2C(E),
+    /// The transaction did not complete in time
+    Timeout,
+}
+
+impl<E: i2c::Error> i2c::Error for Error<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::I2C(e) => e.kind(),
+            Error::Timeout => ErrorKind::Other,
+        }
+    }
+}
+
+/// I2C bus wrapper that fails transactions taking longer than a fixed timeout.
+#[derive(Debug)]
+pub struct WithTimeout<I2C, D> {
+    i2c: I2C,
+    delay: D,
+    timeout_us: u32,
+}
+
+impl<I2C, D> WithTimeout<I2C, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    /// Wrap `i2c`, using `delay` to abort transactions taking longer than `timeout_us`.
+    pub const fn new(i2c: I2C, delay: D, timeout_us: u32) -> Self {
+        WithTimeout {
+            i2c,
+            delay,
+            timeout_us,
+        }
+    }
+
+    /// Destroy the wrapper, returning the I2C bus and delay instances.
+    pub fn destroy(self) -> (I2C, D) {
+        (self.i2c, self.delay)
+    }
+}
+
+/// Run `transaction`, unless `delay` runs out first.
+async fn race<T, E>(
+    transaction: impl Future<Output = Result<T, E>>,
+    delay: &mut impl DelayNs,
+    timeout_us: u32,
+) -> Result<T, Error<E>> {
+    let mut transaction = pin!(transaction);
+    let mut timeout = pin!(delay.delay_us(timeout_us));
+
+    poll_fn(|cx| {
+        if let Poll::Ready(result) = transaction.as_mut().poll(cx) {
+            return Poll::Ready(result.map_err(Error::I2C));
+        }
+        if timeout.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Err(Error::Timeout));
+        }
+        Poll::Pending
+    })
+    .await
+}
+
+impl<I2C, D> ErrorType for WithTimeout<I2C, D>
+where
+    I2C: I2c,
+{
+    type Error = Error<I2C::Error>;
+}
+
+impl<I2C, D> I2c for WithTimeout<I2C, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    async fn read(&mut self, address: SevenBitAddress, read: &mut [u8]) -> Result<(), Self::Error> {
+        race(
+            self.i2c.read(address, read),
+            &mut self.delay,
+            self.timeout_us,
+        )
+        .await
+    }
+
+    async fn write(&mut self, address: SevenBitAddress, write: &[u8]) -> Result<(), Self::Error> {
+        race(
+            self.i2c.write(address, write),
+            &mut self.delay,
+            self.timeout_us,
+        )
+        .await
+    }
+
+    async fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        race(
+            self.i2c.write_read(address, write, read),
+            &mut self.delay,
+            self.timeout_us,
+        )
+        .await
+    }
+
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        race(
+            self.i2c.transaction(address, operations),
+            &mut self.delay,
+            self.timeout_us,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use core::task::{Context, Waker};
+
+    /// A bus on which every transaction hangs forever.
+    struct StuckBus;
+
+    impl ErrorType for StuckBus {
+        type Error = Infallible;
+    }
+
+    impl I2c for StuckBus {
+        async fn transaction(
+            &mut self,
+            _address: SevenBitAddress,
+            _operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            core::future::pending().await
+        }
+    }
+
+    /// A delay that has always already elapsed.
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        async fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn stuck_transaction_times_out() {
+        let mut bus = WithTimeout::new(StuckBus, NoDelay, 1_000);
+        let mut data = [0];
+        let future = pin!(bus.write_read(0x51, &[0x04], &mut data));
+
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(matches!(
+            future.poll(&mut cx),
+            Poll::Ready(Err(Error::Timeout))
+        ));
+    }
+}
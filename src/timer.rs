@@ -0,0 +1,77 @@
+//! The countdown timer: source clock selection for a requested period.
+
+use core::time::Duration;
+
+/// Source clock of the countdown timer (TCF bits)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TimerClock {
+    Hz4096 = 0b00,
+    Hz64 = 0b01,
+    Hz1 = 0b10,
+    /// One tick per minute
+    Hz1_60 = 0b11,
+}
+
+impl TimerClock {
+    /// Duration of a single tick, in nanoseconds.
+    const fn tick_ns(self) -> u64 {
+        match self {
+            TimerClock::Hz4096 => 244_141,
+            TimerClock::Hz64 => 15_625_000,
+            TimerClock::Hz1 => 1_000_000_000,
+            TimerClock::Hz1_60 => 60_000_000_000,
+        }
+    }
+
+    /// The TCF bits of the timer mode register.
+    pub const fn bits(self) -> u8 {
+        (self as u8) << 3
+    }
+}
+
+/// The timer setting that comes closest to `duration`: the finest clock that can count it,
+/// and the number of ticks [1-255]. `None` if the duration is zero or above 255 minutes.
+pub fn timer_setting(duration: Duration) -> Option<(TimerClock, u8)> {
+    let ns = duration.as_nanos();
+    [
+        TimerClock::Hz4096,
+        TimerClock::Hz64,
+        TimerClock::Hz1,
+        TimerClock::Hz1_60,
+    ]
+    .into_iter()
+    .find_map(|clock| {
+        let tick = clock.tick_ns() as u128;
+        let ticks = (ns + tick / 2) / tick;
+        (1..=255).contains(&ticks).then_some((clock, ticks as u8))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_finest_clock_that_fits() {
+        assert_eq!(
+            Some((TimerClock::Hz4096, 41)),
+            timer_setting(Duration::from_millis(10))
+        );
+        assert_eq!(
+            Some((TimerClock::Hz64, 64)),
+            timer_setting(Duration::from_secs(1))
+        );
+        assert_eq!(
+            Some((TimerClock::Hz1, 90)),
+            timer_setting(Duration::from_secs(90))
+        );
+        assert_eq!(
+            Some((TimerClock::Hz1_60, 60)),
+            timer_setting(Duration::from_secs(3600))
+        );
+        assert_eq!(None, timer_setting(Duration::ZERO));
+        assert_eq!(None, timer_setting(Duration::from_secs(256 * 60)));
+    }
+}
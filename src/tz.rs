@@ -0,0 +1,392 @@
+//! POSIX TZ strings, for converting between UTC kept by the chip and local time with daylight
+//! saving time, configured by a single standard string rather than hard-coded rules.
+//!
+//! ```
+//! use pcf85063a::tz::TimeZone;
+//! use time::macros::datetime;
+//!
+//! let amsterdam = TimeZone::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+//! assert_eq!(
+//!     datetime!(2024-07-01 14:00:00),
+//!     amsterdam.to_local(datetime!(2024-07-01 12:00:00))
+//! );
+//! assert_eq!(
+//!     datetime!(2024-12-01 12:00:00),
+//!     amsterdam.to_utc(datetime!(2024-12-01 13:00:00))
+//! );
+//! ```
+//!
+//! Offsets in TZ strings count west of Greenwich, so "CET-1" is one hour ahead of UTC. The time
+//! zone names are checked but not kept.
+
+use super::{Error, PCF85063};
+use core::str::FromStr;
+use embedded_hal_async::i2c::I2c;
+use time::{Date, Duration, Month, PrimitiveDateTime, Weekday};
+
+/// Default time of day of the transitions, 02:00.
+const DEFAULT_TRANSITION: i32 = 2 * 3600;
+
+/// The string is not a valid POSIX TZ string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParseError;
+
+/// A time zone: an offset from UTC, and optionally daylight saving time rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeZone {
+    /// Standard offset, in seconds east of UTC
+    offset: i32,
+    dst: Option<Dst>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Dst {
+    /// Daylight saving time offset, in seconds east of UTC
+    offset: i32,
+    /// Start, in standard time
+    start: Transition,
+    /// End, in daylight saving time
+    end: Transition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Transition {
+    day: Day,
+    /// Local time of day in seconds, may be negative or beyond a day
+    time: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Day {
+    /// `Jn`: day 1-365, February 29 is never counted
+    Julian(u16),
+    /// `n`: day 0-365, February 29 is counted in leap years
+    Ordinal(u16),
+    /// `Mm.w.d`: weekday `d` (0 is Sunday) of week `w` (5 is the last) of month `m`
+    NthWeekday { month: u8, week: u8, weekday: u8 },
+}
+
+impl Day {
+    fn date(self, year: i32) -> Option<Date> {
+        match self {
+            Day::Julian(n) => {
+                let leap_day = time::util::is_leap_year(year) && n >= 60;
+                Date::from_ordinal_date(year, n + leap_day as u16).ok()
+            }
+            Day::Ordinal(n) => Date::from_ordinal_date(year, n + 1).ok(),
+            Day::NthWeekday {
+                month,
+                week,
+                weekday,
+            } => {
+                let month = Month::try_from(month).ok()?;
+                let first = Date::from_calendar_date(year, month, 1).ok()?;
+                let weekday = Weekday::Sunday.nth_next(weekday);
+                let mut day = 1
+                    + (7 + weekday.number_days_from_sunday()
+                        - first.weekday().number_days_from_sunday())
+                        % 7
+                    + (week - 1) * 7;
+                while day > month.length(year) {
+                    day -= 7;
+                }
+                first.replace_day(day).ok()
+            }
+        }
+    }
+}
+
+impl Transition {
+    /// The moment of the transition in `year`, in UTC, given the offset in effect before it.
+    fn utc(self, year: i32, offset: i32) -> Option<PrimitiveDateTime> {
+        let local = self.day.date(year)?.midnight();
+        local.checked_add(Duration::seconds((self.time - offset) as i64))
+    }
+}
+
+impl TimeZone {
+    /// A time zone `offset` seconds east of UTC, without daylight saving time.
+    pub const fn fixed(offset: i32) -> Self {
+        TimeZone { offset, dst: None }
+    }
+
+    /// Parse a POSIX TZ string, such as "CET-1CEST,M3.5.0,M10.5.0/3" or "<+0530>-5:30".
+    ///
+    /// Daylight saving time must come with its start and end rules.
+    pub fn parse(tz: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            bytes: tz.as_bytes(),
+            pos: 0,
+        };
+        parser.name()?;
+        let offset = -parser.signed_hms(24)?;
+        if parser.at_end() {
+            return Ok(TimeZone::fixed(offset));
+        }
+
+        parser.name()?;
+        let dst_offset = match parser.peek() {
+            Some(b',') => offset + 3600,
+            _ => -parser.signed_hms(24)?,
+        };
+        parser.expect(b',')?;
+        let start = parser.transition()?;
+        parser.expect(b',')?;
+        let end = parser.transition()?;
+        if !parser.at_end() {
+            return Err(ParseError);
+        }
+        Ok(TimeZone {
+            offset,
+            dst: Some(Dst {
+                offset: dst_offset,
+                start,
+                end,
+            }),
+        })
+    }
+
+    /// The offset from UTC at the UTC time `utc`, in seconds east.
+    pub fn offset_at(&self, utc: PrimitiveDateTime) -> i32 {
+        match self.dst {
+            Some(dst) if dst.is_active(utc, self.offset) => dst.offset,
+            _ => self.offset,
+        }
+    }
+
+    /// Whether daylight saving time is in effect at the UTC time `utc`.
+    pub fn is_dst(&self, utc: PrimitiveDateTime) -> bool {
+        self.dst.is_some_and(|dst| dst.is_active(utc, self.offset))
+    }
+
+    /// Convert the UTC time `utc` to local time.
+    pub fn to_local(&self, utc: PrimitiveDateTime) -> PrimitiveDateTime {
+        utc + Duration::seconds(self.offset_at(utc) as i64)
+    }
+
+    /// Convert the local time `local` to UTC.
+    ///
+    /// A local time that occurs twice, when daylight saving time ends, is taken as the first
+    /// one; a local time skipped when it starts is taken as standard time.
+    pub fn to_utc(&self, local: PrimitiveDateTime) -> PrimitiveDateTime {
+        if let Some(dst) = self.dst {
+            let utc = local - Duration::seconds(dst.offset as i64);
+            if dst.is_active(utc, self.offset) {
+                return utc;
+            }
+        }
+        local - Duration::seconds(self.offset as i64)
+    }
+}
+
+impl Dst {
+    fn is_active(&self, utc: PrimitiveDateTime, offset: i32) -> bool {
+        let year = (utc + Duration::seconds(offset as i64)).year();
+        let (Some(start), Some(end)) = (
+            self.start.utc(year, offset),
+            self.end.utc(year, self.offset),
+        ) else {
+            return false;
+        };
+        if start < end {
+            start <= utc && utc < end
+        } else {
+            // southern hemisphere: daylight saving time spans the new year
+            utc < end || start <= utc
+        }
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = ParseError;
+
+    fn from_str(tz: &str) -> Result<Self, Self::Err> {
+        TimeZone::parse(tz)
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read date and time as local time in `tz`, the chip keeping UTC.
+    pub async fn get_local_datetime(
+        &mut self,
+        tz: &TimeZone,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        Ok(tz.to_local(self.get_datetime().await?))
+    }
+
+    /// Set date and time from local time in `tz`, the chip keeping UTC, and return whether the
+    /// clock was written, see `set_datetime`.
+    pub async fn set_local_datetime(
+        &mut self,
+        tz: &TimeZone,
+        local: &PrimitiveDateTime,
+    ) -> Result<bool, Error<E>> {
+        self.set_datetime(&tz.to_utc(*local)).await
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.bytes.len()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        self.pos += found as usize;
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), ParseError> {
+        self.eat(byte).then_some(()).ok_or(ParseError)
+    }
+
+    /// A name of at least three letters, or quoted in angle brackets.
+    fn name(&mut self) -> Result<(), ParseError> {
+        let quoted = self.eat(b'<');
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            let allowed = byte.is_ascii_alphabetic()
+                || quoted && (byte.is_ascii_digit() || byte == b'+' || byte == b'-');
+            if !allowed {
+                break;
+            }
+            self.pos += 1;
+        }
+        if self.pos - start < 3 || quoted && !self.eat(b'>') {
+            return Err(ParseError);
+        }
+        Ok(())
+    }
+
+    fn number(&mut self, range: core::ops::RangeInclusive<u16>) -> Result<u16, ParseError> {
+        let start = self.pos;
+        let mut value: u16 = 0;
+        while let Some(digit) = self.peek().filter(u8::is_ascii_digit) {
+            value = value
+                .checked_mul(10)
+                .and_then(|value| value.checked_add((digit - b'0') as u16))
+                .ok_or(ParseError)?;
+            self.pos += 1;
+        }
+        if self.pos == start || !range.contains(&value) {
+            return Err(ParseError);
+        }
+        Ok(value)
+    }
+
+    /// `[+|-]hh[:mm[:ss]]` in seconds, with hours up to `max_hours`.
+    fn signed_hms(&mut self, max_hours: u16) -> Result<i32, ParseError> {
+        let sign = if self.eat(b'-') {
+            -1
+        } else {
+            self.eat(b'+');
+            1
+        };
+        let mut seconds = self.number(0..=max_hours)? as i32 * 3600;
+        if self.eat(b':') {
+            seconds += self.number(0..=59)? as i32 * 60;
+            if self.eat(b':') {
+                seconds += self.number(0..=59)? as i32;
+            }
+        }
+        Ok(sign * seconds)
+    }
+
+    /// A date rule, optionally followed by `/time`.
+    fn transition(&mut self) -> Result<Transition, ParseError> {
+        let day = if self.eat(b'M') {
+            let month = self.number(1..=12)? as u8;
+            self.expect(b'.')?;
+            let week = self.number(1..=5)? as u8;
+            self.expect(b'.')?;
+            let weekday = self.number(0..=6)? as u8;
+            Day::NthWeekday {
+                month,
+                week,
+                weekday,
+            }
+        } else if self.eat(b'J') {
+            Day::Julian(self.number(1..=365)?)
+        } else {
+            Day::Ordinal(self.number(0..=365)?)
+        };
+        let time = if self.eat(b'/') {
+            self.signed_hms(167)?
+        } else {
+            DEFAULT_TRANSITION
+        };
+        Ok(Transition { day, time })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn parses_tz_strings() {
+        assert_eq!(Ok(TimeZone::fixed(19800)), TimeZone::parse("<+0530>-5:30"));
+        assert_eq!(Ok(TimeZone::fixed(-10800)), TimeZone::parse("BRT3"));
+        for invalid in [
+            "",
+            "CE-1",
+            "CET",
+            "CET-1CEST",
+            "CET-1CEST,M3.5.0",
+            "CET-1CEST,M13.5.0,M10.5.0",
+        ] {
+            assert_eq!(Err(ParseError), TimeZone::parse(invalid), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn follows_daylight_saving_time() {
+        let tz = TimeZone::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        // 2024-03-31 01:00 UTC and 2024-10-27 01:00 UTC
+        assert!(!tz.is_dst(datetime!(2024-03-31 00:59:59)));
+        assert!(tz.is_dst(datetime!(2024-03-31 01:00:00)));
+        assert!(tz.is_dst(datetime!(2024-10-27 00:59:59)));
+        assert!(!tz.is_dst(datetime!(2024-10-27 01:00:00)));
+
+        // the repeated hour is taken as daylight saving time, the skipped one as standard time
+        assert_eq!(
+            datetime!(2024-10-27 00:30:00),
+            tz.to_utc(datetime!(2024-10-27 02:30:00))
+        );
+        assert_eq!(
+            datetime!(2024-03-31 01:30:00),
+            tz.to_utc(datetime!(2024-03-31 02:30:00))
+        );
+    }
+
+    #[test]
+    fn handles_southern_hemisphere() {
+        let tz = TimeZone::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(
+            datetime!(2024-01-15 11:00:00),
+            tz.to_local(datetime!(2024-01-15 00:00:00))
+        );
+        assert_eq!(
+            datetime!(2024-07-15 10:00:00),
+            tz.to_local(datetime!(2024-07-15 00:00:00))
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Range-checked time components.
+//!
+//! The alarm and time setters accept these in place of plain `u8`s. Constructing one checks the
+//! range, so an out-of-range value is caught where it is created instead of as an
+//! `UsageError::InvalidInputData` from the driver call. For literals, the `new_const` constructors
+//! fail at compile time:
+//!
+//! ```
+//! use pcf85063a::units::{Hours, Minutes};
+//!
+//! const WAKE_HOUR: Hours = Hours::new_const(7);
+//! const WAKE_MINUTE: Minutes = Minutes::new_const(30);
+//! ```
+//!
+//! ```compile_fail
+//! const NOPE: pcf85063a::units::Minutes = pcf85063a::units::Minutes::new_const(60);
+//! ```
+
+/// Error of converting an out-of-range value into a time component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutOfRange;
+
+macro_rules! component {
+    ($(#[$doc:meta])* $name:ident, $min:literal..=$max:literal) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub struct $name(u8);
+
+        impl $name {
+            pub const MIN: Self = Self($min);
+            pub const MAX: Self = Self($max);
+
+            #[doc = concat!("Check that `value` is in the range [", $min, "-", $max, "].")]
+            pub const fn new(value: u8) -> Option<Self> {
+                match value {
+                    $min..=$max => Some(Self(value)),
+                    _ => None,
+                }
+            }
+
+            /// Like `new`, but panics when out of range; in a const context that is a
+            /// compile-time error.
+            pub const fn new_const(value: u8) -> Self {
+                match Self::new(value) {
+                    Some(component) => component,
+                    None => panic!(concat!(stringify!($name), " out of range")),
+                }
+            }
+
+            pub const fn get(self) -> u8 {
+                self.0
+            }
+        }
+
+        impl TryFrom<u8> for $name {
+            type Error = OutOfRange;
+
+            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                Self::new(value).ok_or(OutOfRange)
+            }
+        }
+
+        impl From<$name> for u8 {
+            fn from(component: $name) -> u8 {
+                component.0
+            }
+        }
+    };
+}
+
+component!(
+    /// Seconds [0-59]
+    Seconds, 0..=59
+);
+component!(
+    /// Minutes [0-59]
+    Minutes, 0..=59
+);
+component!(
+    /// Hours in 24-hour format [0-23]
+    Hours, 0..=23
+);
+component!(
+    /// Day of the month [1-31]
+    DayOfMonth, 1..=31
+);
+
+/// Convert a plain value or a checked component, mapping range errors to `InvalidInputData`.
+pub(crate) fn check<T, E>(value: impl TryInto<T>) -> Result<T, crate::Error<E>> {
+    value
+        .try_into()
+        .map_err(|_| crate::Error::Usage(crate::UsageError::InvalidInputData))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_ranges() {
+        assert_eq!(Some(59), Seconds::new(59).map(Seconds::get));
+        assert_eq!(None, Seconds::new(60));
+        assert_eq!(Err(OutOfRange), Hours::try_from(24));
+        assert_eq!(None, DayOfMonth::new(0));
+        assert_eq!(31, DayOfMonth::MAX.get());
+    }
+}
@@ -0,0 +1,143 @@
+//! A synchronous Unix time source fed by the RTC, for TLS certificate validity checks.
+//!
+//! TLS stacks ask for the current time through synchronous calls: embedded-tls through
+//! `TlsClock::now() -> Option<u64>`, rustls through `TimeProvider::current_time()`. Reading the
+//! RTC is asynchronous, so [`UnixClock`] caches the Unix time and advances it between updates.
+//!
+//! ```ignore
+//! static CLOCK: UnixClock = UnixClock::new();
+//!
+//! struct Clock;
+//!
+//! impl embedded_tls::TlsClock for Clock {
+//!     fn now() -> Option<u64> {
+//!         CLOCK.now(embassy_time::Instant::now().as_secs())
+//!     }
+//! }
+//!
+//! // at boot and then e.g. hourly
+//! rtc.update_unix_clock(&CLOCK, embassy_time::Instant::now().as_secs()).await?;
+//! ```
+
+use super::{Error, PCF85063};
+use core::sync::atomic::{fence, AtomicU32, Ordering};
+use embedded_hal_async::i2c::I2c;
+
+/// Cached Unix time in seconds, advanced using an application monotonic seconds counter.
+///
+/// Only needs 32-bit load/store atomics, so it can live in a `static` on any target. Updates
+/// go to the slot not being read, so a reader never combines a time with the anchor of another
+/// update, and never waits on an update it interrupted. Update it from one context at a time.
+#[derive(Debug, Default)]
+pub struct UnixClock {
+    /// Number of updates; the latest is in slot `version % 2`.
+    version: AtomicU32,
+    /// Unix time and monotonic seconds at an update; Unix time 0 if never updated.
+    slots: [(AtomicU32, AtomicU32); 2],
+}
+
+impl UnixClock {
+    pub const fn new() -> Self {
+        UnixClock {
+            version: AtomicU32::new(0),
+            slots: [
+                (AtomicU32::new(0), AtomicU32::new(0)),
+                (AtomicU32::new(0), AtomicU32::new(0)),
+            ],
+        }
+    }
+
+    /// Set the clock to `unix_seconds` at monotonic time `monotonic_seconds`.
+    pub fn set(&self, unix_seconds: u32, monotonic_seconds: u64) {
+        let version = self.version.load(Ordering::Relaxed).wrapping_add(1);
+        // publishing the previous update goes before overwriting the slot it left behind
+        fence(Ordering::Release);
+        let (unix, anchor) = &self.slots[version as usize % 2];
+        unix.store(unix_seconds, Ordering::Relaxed);
+        anchor.store(monotonic_seconds as u32, Ordering::Relaxed);
+        self.version.store(version, Ordering::Release);
+    }
+
+    /// The current Unix time in seconds, given the current monotonic time, or `None` if the
+    /// clock was never set.
+    ///
+    /// A monotonic time from before the last update, e.g. read just before it, counts back
+    /// from it.
+    pub fn now(&self, monotonic_seconds: u64) -> Option<u64> {
+        let (unix_seconds, anchor) = loop {
+            let version = self.version.load(Ordering::Acquire);
+            let (unix, anchor) = &self.slots[version as usize % 2];
+            let read = (unix.load(Ordering::Relaxed), anchor.load(Ordering::Relaxed));
+            fence(Ordering::Acquire);
+            // retry if the slot was reused for a newer update while reading it
+            if self.version.load(Ordering::Relaxed) == version {
+                break read;
+            }
+        };
+        if unix_seconds == 0 {
+            return None;
+        }
+        let elapsed = (monotonic_seconds as u32).wrapping_sub(anchor) as i32;
+        u64::try_from(unix_seconds as i64 + elapsed as i64).ok()
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read the time, assuming the chip keeps UTC, and store it in `clock`.
+    pub async fn update_unix_clock(
+        &mut self,
+        clock: &UnixClock,
+        monotonic_seconds: u64,
+    ) -> Result<(), Error<E>> {
+        let unix_seconds = self.get_datetime().await?.assume_utc().unix_timestamp();
+        clock.set(
+            unix_seconds.clamp(1, u32::MAX as i64) as u32,
+            monotonic_seconds,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advances_with_monotonic_time() {
+        let clock = UnixClock::new();
+        assert_eq!(None, clock.now(5));
+
+        clock.set(1_700_000_000, 10);
+        assert_eq!(Some(1_700_000_000), clock.now(10));
+        assert_eq!(Some(1_700_000_050), clock.now(60));
+    }
+
+    #[test]
+    fn counts_back_before_the_anchor() {
+        let clock = UnixClock::new();
+        clock.set(1_700_000_000, 100);
+        assert_eq!(Some(1_699_999_990), clock.now(90));
+    }
+
+    #[test]
+    fn never_mixes_updates() {
+        extern crate std;
+
+        const OFFSET: u64 = 1_700_000_000;
+        let clock = UnixClock::new();
+        clock.set(OFFSET as u32, 0);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for monotonic in 1..200_000 {
+                    clock.set((OFFSET + monotonic) as u32, monotonic);
+                }
+            });
+            for monotonic in 0..200_000 {
+                assert_eq!(Some(OFFSET + monotonic), clock.now(monotonic));
+            }
+        });
+    }
+}
@@ -0,0 +1,180 @@
+//! Accumulating the time spent awake across deep-sleep cycles.
+//!
+//! A record in persistent storage holds the total awake time of earlier cycles and the time of
+//! the latest wake. Call `record_wake` when waking up and `record_sleep` before going to sleep;
+//! `get_uptime` reports the total including the current cycle:
+//!
+//! ```ignore
+//! rtc.record_wake(&mut eeprom, UPTIME_ADDRESS).await?;
+//! do_work().await;
+//! let total = rtc.record_sleep(&mut eeprom, UPTIME_ADDRESS).await?;
+//! ```
+//!
+//! A cycle that ends without `record_sleep`, e.g. by a reset, is not counted.
+
+use super::persist::{crc8, PersistError, PersistentStorage};
+use super::{Error, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+
+/// Number of bytes taken by the uptime record.
+pub const UPTIME_RECORD_LEN: usize = 15;
+
+const MAGIC: u8 = 0xa7;
+const AWAKE: u8 = 0b0000_0001;
+
+/// Contents of the uptime record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Uptime {
+    /// Total awake time of the finished cycles, in seconds
+    total: u32,
+    /// Start of the current cycle, if awake
+    awake_since: Option<PrimitiveDateTime>,
+}
+
+impl Uptime {
+    const ZERO: Uptime = Uptime {
+        total: 0,
+        awake_since: None,
+    };
+
+    /// Total awake time at `now`, including the current cycle.
+    fn total_at(self, now: PrimitiveDateTime) -> u32 {
+        let current = self.awake_since.map_or(0, |since| {
+            // a clock set back in between gives no negative time
+            (now - since).whole_seconds().clamp(0, u32::MAX as i64) as u32
+        });
+        self.total.saturating_add(current)
+    }
+
+    fn to_record(self) -> [u8; UPTIME_RECORD_LEN] {
+        let mut record = [0; UPTIME_RECORD_LEN];
+        record[0] = MAGIC;
+        record[1..5].copy_from_slice(&self.total.to_le_bytes());
+        if let Some(since) = self.awake_since {
+            record[5] = AWAKE;
+            record[6..14].copy_from_slice(&since.assume_utc().unix_timestamp().to_le_bytes());
+        }
+        record[14] = crc8(&record[..14]);
+        record
+    }
+
+    /// Decode a record, `None` if it is blank or corrupted.
+    fn from_record(record: &[u8; UPTIME_RECORD_LEN]) -> Option<Self> {
+        if record[0] != MAGIC || record[14] != crc8(&record[..14]) {
+            return None;
+        }
+        let mut total = [0; 4];
+        total.copy_from_slice(&record[1..5]);
+        let awake_since = if record[5] & AWAKE != 0 {
+            let mut timestamp = [0; 8];
+            timestamp.copy_from_slice(&record[6..14]);
+            let datetime =
+                OffsetDateTime::from_unix_timestamp(i64::from_le_bytes(timestamp)).ok()?;
+            Some(PrimitiveDateTime::new(datetime.date(), datetime.time()))
+        } else {
+            None
+        };
+        Some(Uptime {
+            total: u32::from_le_bytes(total),
+            awake_since,
+        })
+    }
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Start a cycle awake in the uptime record at `address` in `storage`, which starts at zero
+    /// if blank or corrupted. Returns the total of the earlier cycles.
+    pub async fn record_wake<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+    ) -> Result<Duration, PersistError<Error<E>, S::Error>> {
+        let (uptime, now) = self.read_uptime(storage, address).await?;
+        let uptime = Uptime {
+            awake_since: Some(now),
+            ..uptime
+        };
+        write_uptime(storage, address, uptime).await?;
+        Ok(Duration::seconds(uptime.total as i64))
+    }
+
+    /// End the cycle awake in the uptime record at `address` in `storage`, returning the new
+    /// total.
+    pub async fn record_sleep<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+    ) -> Result<Duration, PersistError<Error<E>, S::Error>> {
+        let (uptime, now) = self.read_uptime(storage, address).await?;
+        let uptime = Uptime {
+            total: uptime.total_at(now),
+            awake_since: None,
+        };
+        write_uptime(storage, address, uptime).await?;
+        Ok(Duration::seconds(uptime.total as i64))
+    }
+
+    /// The total awake time in the uptime record at `address` in `storage`, including the
+    /// current cycle.
+    pub async fn get_uptime<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+    ) -> Result<Duration, PersistError<Error<E>, S::Error>> {
+        let (uptime, now) = self.read_uptime(storage, address).await?;
+        Ok(Duration::seconds(uptime.total_at(now) as i64))
+    }
+
+    /// Read the record, and the current time.
+    async fn read_uptime<S: PersistentStorage>(
+        &mut self,
+        storage: &mut S,
+        address: u32,
+    ) -> Result<(Uptime, PrimitiveDateTime), PersistError<Error<E>, S::Error>> {
+        let now = self.get_datetime().await.map_err(PersistError::Rtc)?;
+        let mut record = [0; UPTIME_RECORD_LEN];
+        storage
+            .read(address, &mut record)
+            .await
+            .map_err(PersistError::Storage)?;
+        Ok((Uptime::from_record(&record).unwrap_or(Uptime::ZERO), now))
+    }
+}
+
+async fn write_uptime<S: PersistentStorage, R>(
+    storage: &mut S,
+    address: u32,
+    uptime: Uptime,
+) -> Result<(), PersistError<R, S::Error>> {
+    storage
+        .write(address, &uptime.to_record())
+        .await
+        .map_err(PersistError::Storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn accumulates_awake_time() {
+        let uptime = Uptime {
+            total: 100,
+            awake_since: Some(datetime!(2024-02-29 23:59:30)),
+        };
+        assert_eq!(Some(uptime), Uptime::from_record(&uptime.to_record()));
+        assert_eq!(160, uptime.total_at(datetime!(2024-03-01 00:00:30)));
+        assert_eq!(100, uptime.total_at(datetime!(2024-02-29 23:00:00)));
+
+        assert_eq!(
+            Some(Uptime::ZERO),
+            Uptime::from_record(&Uptime::ZERO.to_record())
+        );
+        assert_eq!(None, Uptime::from_record(&[0xff; UPTIME_RECORD_LEN]));
+    }
+}
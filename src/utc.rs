@@ -0,0 +1,50 @@
+//! Getting and setting the time as `time::UtcDateTime`.
+//!
+//! The chip has no notion of time zones; these assume it is kept in UTC, as is usual for
+//! devices that sync to a server, and only change the type, so the same range and leap year
+//! checks apply.
+
+use super::{Error, PCF85063};
+use embedded_hal_async::i2c::I2c;
+use time::{PrimitiveDateTime, UtcDateTime};
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Read date and time all at once, as UTC.
+    pub async fn get_utc_datetime(&mut self) -> Result<UtcDateTime, Error<E>> {
+        let datetime = self.get_datetime().await?;
+        Ok(UtcDateTime::new(datetime.date(), datetime.time()))
+    }
+
+    /// Set date and time all at once from a UTC date and time.
+    ///
+    /// Subject to the same checks as `set_datetime`, and returns whether the clock was written.
+    pub async fn set_utc_datetime(&mut self, datetime: UtcDateTime) -> Result<bool, Error<E>> {
+        self.set_datetime(&PrimitiveDateTime::new(datetime.date(), datetime.time()))
+            .await
+    }
+}
+
+#[cfg(all(test, feature = "simulator"))]
+mod tests {
+    use super::*;
+    use crate::simulator::{block_on, Simulator};
+    use time::macros::{datetime, utc_datetime};
+
+    #[test]
+    fn keeps_chip_in_utc() {
+        let simulator = Simulator::new();
+        let mut rtc = PCF85063::new(simulator.bus());
+        let local = datetime!(2024-03-01 01:30:00 +2);
+        let now = block_on(async {
+            rtc.set_utc_datetime(local.to_utc()).await?;
+            rtc.get_utc_datetime().await
+        })
+        .unwrap();
+
+        assert_eq!(utc_datetime!(2024-02-29 23:30:00), now);
+        assert_eq!(Some(datetime!(2024-02-29 23:30:00)), simulator.datetime());
+    }
+}
@@ -0,0 +1,105 @@
+//! Waiting on the clock by polling, for boards where the INT pin is not wired.
+
+use super::{bcd, DataError, Error, Register, PCF85063};
+use embedded_hal_async::{delay::DelayNs, i2c::I2c};
+use time::{Duration, PrimitiveDateTime};
+
+/// Longest single sleep of `wait_until`, so changes to the time are noticed reasonably soon.
+const MAX_SLEEP_MS: u32 = 60_000;
+/// Poll interval of `wait_until` near the target.
+const FINE_POLL_MS: u32 = 10;
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Poll the seconds register every `poll_interval_ms` until it changes, and return the new
+    /// seconds value.
+    ///
+    /// The new second started at most `poll_interval_ms` before this returns. Will return a
+    /// 'DataError::NotRunning' if the seconds did not change for two seconds.
+    pub async fn wait_for_second_change(
+        &mut self,
+        delay: &mut impl DelayNs,
+        poll_interval_ms: u32,
+    ) -> Result<u8, Error<E>> {
+        let poll_interval_ms = poll_interval_ms.max(1);
+        let start = self.read_register(Register::SECONDS).await? & 0b0111_1111;
+
+        let mut waited_ms: u32 = 0;
+        while waited_ms < 2000 {
+            delay.delay_ms(poll_interval_ms).await;
+            waited_ms = waited_ms.saturating_add(poll_interval_ms);
+
+            let seconds = self.read_register(Register::SECONDS).await? & 0b0111_1111;
+            if seconds != start {
+                return Ok(bcd::decode(seconds));
+            }
+        }
+        Err(Error::Data(DataError::NotRunning))
+    }
+
+    /// Poll the alarm flag every `poll_interval_ms` until it is set, then clear it.
+    ///
+    /// The alarm fired at most `poll_interval_ms` before this returns. Waits indefinitely if
+    /// no alarm is configured.
+    pub async fn wait_for_alarm_polling(
+        &mut self,
+        delay: &mut impl DelayNs,
+        poll_interval_ms: u32,
+    ) -> Result<(), Error<E>> {
+        let poll_interval_ms = poll_interval_ms.max(1);
+        while !self.get_alarm_flag().await? {
+            delay.delay_ms(poll_interval_ms).await;
+        }
+        self.clear_alarm_flag().await
+    }
+
+    /// Sleep until the clock reaches `target`, and return the time at which it did.
+    ///
+    /// While the target is far away this sleeps in chunks of up to a minute; during the last
+    /// couple of seconds it polls for the change of the second, so this returns within about
+    /// 10 ms of the clock reaching the target. Returns immediately if the target has passed.
+    pub async fn wait_until(
+        &mut self,
+        delay: &mut impl DelayNs,
+        target: PrimitiveDateTime,
+    ) -> Result<PrimitiveDateTime, Error<E>> {
+        loop {
+            let now = self.get_datetime().await?;
+            if now >= target {
+                return Ok(now);
+            }
+            match sleep_chunk_ms(target - now) {
+                Some(ms) => delay.delay_ms(ms).await,
+                None => {
+                    self.wait_for_second_change(delay, FINE_POLL_MS).await?;
+                }
+            }
+        }
+    }
+}
+
+/// How long to sleep in one go when `remaining` is left, or `None` if it is time to poll.
+fn sleep_chunk_ms(remaining: Duration) -> Option<u32> {
+    // the time read may be up to a second late, so leave a margin
+    let margin = Duration::seconds(2);
+    if remaining <= margin {
+        return None;
+    }
+    let ms = (remaining - margin).whole_milliseconds();
+    Some(ms.min(MAX_SLEEP_MS as i128) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleeps_in_adaptive_chunks() {
+        assert_eq!(Some(MAX_SLEEP_MS), sleep_chunk_ms(Duration::hours(1)));
+        assert_eq!(Some(8_000), sleep_chunk_ms(Duration::seconds(10)));
+        assert_eq!(None, sleep_chunk_ms(Duration::seconds(2)));
+        assert_eq!(None, sleep_chunk_ms(Duration::milliseconds(500)));
+    }
+}
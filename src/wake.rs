@@ -0,0 +1,153 @@
+//! Waking from deep sleep after a duration or at a time, without choosing the hardware resource.
+//!
+//! Short intervals go to the countdown timer, which needs no reading of the time; longer ones
+//! and times go to the alarm. A target beyond the reach of the alarm is approached in steps:
+//! the alarm wakes at an intermediate time, after which `set_wake` is called again with the
+//! returned target.
+//!
+//! ```ignore
+//! match rtc.set_wake(Duration::from_secs(90 * 24 * 3600)).await? {
+//!     WakePlan::Chained(target) => store_target(target),
+//!     _ => {}
+//! }
+//! ```
+
+use super::timer::{timer_setting, TimerClock};
+use super::{BitFlags, Error, Register, UsageError, PCF85063};
+use core::time::Duration;
+use embedded_hal_async::i2c::I2c;
+use time::PrimitiveDateTime;
+
+/// Longest interval counted by the timer, which resolves it to a second or better.
+const TIMER_REACH: Duration = Duration::from_secs(255);
+/// Furthest the alarm reaches: it matches on the day of the month.
+const ALARM_REACH: time::Duration = time::Duration::days(28);
+
+/// When to wake up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeTarget {
+    /// After a duration from now
+    After(Duration),
+    /// At a date and time
+    At(PrimitiveDateTime),
+}
+
+impl From<Duration> for WakeTarget {
+    fn from(duration: Duration) -> Self {
+        WakeTarget::After(duration)
+    }
+}
+
+impl From<PrimitiveDateTime> for WakeTarget {
+    fn from(at: PrimitiveDateTime) -> Self {
+        WakeTarget::At(at)
+    }
+}
+
+/// How a wake was scheduled by [`PCF85063::set_wake`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakePlan {
+    /// The timer wakes at the target
+    Timer,
+    /// The alarm wakes at the target
+    Alarm,
+    /// The alarm wakes at an intermediate time; call `set_wake` again with this target then
+    Chained(PrimitiveDateTime),
+}
+
+impl<I2C, E> PCF85063<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    /// Schedule a wake, cancelling any wake scheduled before, and enable its interrupt.
+    ///
+    /// Will return a 'UsageError::InvalidInputData' if the duration is zero or the time has
+    /// passed. A timer wake repeats until cancelled with `cancel_wake`.
+    pub async fn set_wake(&mut self, target: impl Into<WakeTarget>) -> Result<WakePlan, Error<E>> {
+        let target = target.into();
+        self.cancel_wake().await?;
+
+        if let WakeTarget::After(duration) = target {
+            if duration <= TIMER_REACH {
+                let (clock, ticks) =
+                    timer_setting(duration).ok_or(Error::Usage(UsageError::InvalidInputData))?;
+                self.start_wake_timer(clock, ticks).await?;
+                return Ok(WakePlan::Timer);
+            }
+        }
+
+        let now = self.get_datetime().await?;
+        let at = match target {
+            WakeTarget::After(duration) => time::Duration::try_from(duration)
+                .ok()
+                .and_then(|duration| now.checked_add(duration))
+                .ok_or(Error::Usage(UsageError::InvalidInputData))?,
+            WakeTarget::At(at) => at,
+        };
+        let (alarm_at, plan) =
+            alarm_plan(now, at).ok_or(Error::Usage(UsageError::InvalidInputData))?;
+        self.set_alarm_at(alarm_at).await?;
+        self.arm_alarm_interrupt().await?;
+        Ok(plan)
+    }
+
+    /// Stop the timer, disable the alarm interrupt and clear both flags.
+    pub async fn cancel_wake(&mut self) -> Result<(), Error<E>> {
+        let timer_mode = self.read_register(Register::TIMER_MODE).await?;
+        self.write_register(
+            Register::TIMER_MODE,
+            timer_mode & !(BitFlags::TE | BitFlags::TIE),
+        )
+        .await?;
+        let control_2 = self.read_register(Register::CONTROL_2).await?;
+        self.write_register(
+            Register::CONTROL_2,
+            control_2 & !(BitFlags::AIE | BitFlags::AF | BitFlags::TF),
+        )
+        .await
+    }
+
+    /// Start the timer with its interrupt; the timer flag was cleared by `cancel_wake`.
+    async fn start_wake_timer(&mut self, clock: TimerClock, ticks: u8) -> Result<(), Error<E>> {
+        let mode = clock.bits() | BitFlags::TE | BitFlags::TIE;
+        self.write_bytes(&[Register::TIMER_VALUE as u8, ticks, mode])
+            .await
+    }
+}
+
+/// The alarm time and plan for a wake at `at`, `None` if that has passed.
+fn alarm_plan(
+    now: PrimitiveDateTime,
+    at: PrimitiveDateTime,
+) -> Option<(PrimitiveDateTime, WakePlan)> {
+    if at <= now {
+        None
+    } else if at - now <= ALARM_REACH {
+        Some((at, WakePlan::Alarm))
+    } else {
+        Some((now + ALARM_REACH, WakePlan::Chained(at)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn chains_alarms_beyond_reach() {
+        let now = datetime!(2025-01-01 12:00:00);
+        assert_eq!(None, alarm_plan(now, now));
+        assert_eq!(
+            Some((datetime!(2025-01-20 06:00:00), WakePlan::Alarm)),
+            alarm_plan(now, datetime!(2025-01-20 06:00:00))
+        );
+        assert_eq!(
+            Some((
+                datetime!(2025-01-29 12:00:00),
+                WakePlan::Chained(datetime!(2025-03-01 00:00:00))
+            )),
+            alarm_plan(now, datetime!(2025-03-01 00:00:00))
+        );
+    }
+}